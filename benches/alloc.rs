@@ -0,0 +1,176 @@
+//! Allocator traffic of the search (`cargo bench --bench alloc`): a
+//! counting global allocator around fixed-budget searches, reporting
+//! allocations per expansion, how many of them are program nodes, and the
+//! live and peak heap. Not a Criterion bench; it prints one line per run.
+//!
+//! The `arena` runs search with `bf_search::arena`, which keeps every
+//! program node ever built in one `Vec` with `u32` children and never
+//! frees one. On `7 200 3 100 9 1`, 1M expansions, release build, two runs
+//! each:
+//!
+//! - 8.3-9.4 → 4.6-5.1 µs and 59.3 → 14.6 allocations per expansion, none
+//!   of them program nodes: a child's nodes are pushes onto the `Vec`.
+//! - Live heap 2316 → 2236 MB. The arena holds 1051 MB of nodes, all it
+//!   ever built, where the tree built 2774 MB and freed a pruned or popped
+//!   state's nodes as soon as their last owner dropped them. The rest of
+//!   both is the open states' tapes, outputs and frames.
+//! - An `ArenaNode` means nothing without its arena, which snapshots,
+//!   `Interpreter`, the `sync` feature and the bindings all rely on, so
+//!   `Searcher` keeps the shared trees and `ArenaSearch` covers the plain
+//!   best-first search only.
+//!
+//! Storage changes measured with it, before and after (300k expansions on
+//! `7 200 3 100 9 1` unless noted):
+//!
+//! - Slab frontier, heap items of (score, seq, slot) instead of whole
+//!   nodes: 7.3 → 7.0 µs per expansion, live heap 694 → 708 MB. Sifts got
+//!   cheaper; the memory is in the nodes themselves, wherever they sit.
+//! - Only the output past the target in each node, not all of it (the
+//!   64-byte target, 300k expansions): 8.8 → 8.6 µs and 62.0 → 57.1
+//!   allocations per expansion, live heap 896 → 912 MB. Most open nodes
+//!   have matched only a few bytes, so their outputs were small anyway.
+//! - The tape written in place rather than cloned for each write (`40 80
+//!   120`, two runs each): 6.0-6.5 → 6.2-7.1 µs and 43.5 → 43.4
+//!   allocations per expansion, live heap ~700 → 687 MB. Within noise:
+//!   cloning the persistent map only bumped a count.
+//! - Interning keyed on structure instead of node ids, in 64k direct-mapped
+//!   slots instead of a map of every live node (1M expansions, the
+//!   `intern` runs): 15.9 → 9.5 µs per expansion, live heap 1311 → 1518
+//!   MB, against 6.0 µs and 2316 MB without interning. The complete map
+//!   keyed on structure got to 1278 MB but took 17.1 µs.
+
+use bf_search::arena::{ArenaSearch, Node};
+use bf_search::{Callbacks, Frontier, ProgramNode, SearchConfig, Searcher};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::time::Instant;
+
+struct Counting;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static NODE_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Size of a shared `ProgramNode` allocation: the node behind two counts.
+const NODE_SIZE: usize = std::mem::size_of::<ProgramNode>() + 2 * std::mem::size_of::<usize>();
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Relaxed);
+        if layout.size() == NODE_SIZE {
+            NODE_ALLOCS.fetch_add(1, Relaxed);
+        }
+        let live = LIVE.fetch_add(layout.size(), Relaxed) + layout.size();
+        PEAK.fetch_max(live, Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Relaxed);
+        if new_size > layout.size() {
+            let live = LIVE.fetch_add(new_size - layout.size(), Relaxed) + new_size - layout.size();
+            PEAK.fetch_max(live, Relaxed);
+        } else {
+            LIVE.fetch_sub(layout.size() - new_size, Relaxed);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Where a run keeps its programs.
+#[derive(Clone, Copy, PartialEq)]
+enum Store {
+    Tree,
+    Interned,
+    Arena,
+}
+
+/// An arena search's callbacks: none.
+struct Quiet;
+
+impl Callbacks for Quiet {}
+
+/// Run `expansions` expansions on `target` and print what they allocated.
+fn measure(name: &str, target: &[u8], expansions: u64, frontier: Frontier, store: Store) {
+    let config = SearchConfig::new(target.to_vec())
+        .frontier(frontier)
+        .intern(store == Store::Interned)
+        .max_expansions(Some(expansions))
+        .build()
+        .unwrap();
+    let (allocs, nodes) = (ALLOCS.load(Relaxed), NODE_ALLOCS.load(Relaxed));
+    let base = LIVE.load(Relaxed);
+    PEAK.store(base, Relaxed);
+    let start = Instant::now();
+    // expansions done, the live heap with the search still alive, and the
+    // bytes of program nodes ever built
+    let (done, live, built) = if store == Store::Arena {
+        let mut search = ArenaSearch::new(config);
+        search.run(&mut Quiet);
+        let built = search.arena().len() * std::mem::size_of::<Node>();
+        (search.expansions(), LIVE.load(Relaxed), built)
+    } else {
+        let mut searcher = Searcher::new(config);
+        while searcher.next_solution().is_some() {}
+        let built = (NODE_ALLOCS.load(Relaxed) - nodes) * NODE_SIZE;
+        (searcher.stats().expansions, LIVE.load(Relaxed), built)
+    };
+    let elapsed = start.elapsed();
+    let per = done.max(1) as f64;
+    println!(
+        "{:<28} {:>8} expansions  {:>6.2} µs/exp  {:>5.1} allocs/exp ({:>4.1} program nodes)  live {:>5} MB  peak {:>5} MB  nodes ever built {:>5} MB",
+        name,
+        done,
+        elapsed.as_secs_f64() * 1e6 / per,
+        (ALLOCS.load(Relaxed) - allocs) as f64 / per,
+        (NODE_ALLOCS.load(Relaxed) - nodes) as f64 / per,
+        (live - base) >> 20,
+        (PEAK.load(Relaxed) - base) >> 20,
+        built >> 20,
+    );
+}
+
+/// 64 bytes with no easy pattern, so nodes carry long outputs.
+const LONG: [u8; 64] = {
+    let mut t = [0; 64];
+    let mut i = 0;
+    while i < 64 {
+        t[i] = (i * 37 % 251) as u8;
+        i += 1;
+    }
+    t
+};
+
+fn main() {
+    // `cargo bench` passes `--bench`; any other argument filters by name
+    let filter = std::env::args().skip(1).find(|a| !a.starts_with("--"));
+    const HEAP: Frontier = Frontier::Heap;
+    const BUCKET: Frontier = Frontier::Bucket { width: 0.01 };
+    use Store::{Arena, Interned, Tree};
+    let runs: &[(&str, &[u8], u64, Frontier, Store)] = &[
+        ("7 200 3 100 9 1, 300k", &[7, 200, 3, 100, 9, 1], 300_000, HEAP, Tree),
+        ("7 200 3 100 9 1, 300k, intern", &[7, 200, 3, 100, 9, 1], 300_000, HEAP, Interned),
+        ("7 200 3 100 9 1, 300k, arena", &[7, 200, 3, 100, 9, 1], 300_000, HEAP, Arena),
+        ("7 200 3 100 9 1, 1M", &[7, 200, 3, 100, 9, 1], 1_000_000, HEAP, Tree),
+        ("7 200 3 100 9 1, 1M, intern", &[7, 200, 3, 100, 9, 1], 1_000_000, HEAP, Interned),
+        ("7 200 3 100 9 1, 1M, arena", &[7, 200, 3, 100, 9, 1], 1_000_000, HEAP, Arena),
+        ("7 200 3 100 9 1, 1M, bucket", &[7, 200, 3, 100, 9, 1], 1_000_000, BUCKET, Tree),
+        ("64-byte target, 300k", &LONG, 300_000, HEAP, Tree),
+        ("64-byte target, 300k, arena", &LONG, 300_000, HEAP, Arena),
+        ("40 80 120, 300k", &[40, 80, 120], 300_000, HEAP, Tree),
+    ];
+    for &(name, target, expansions, frontier, store) in runs {
+        if filter.as_ref().is_none_or(|f| name.contains(f.as_str())) {
+            measure(name, target, expansions, frontier, store);
+        }
+    }
+}
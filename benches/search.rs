@@ -0,0 +1,183 @@
+//! Benchmarks for the search core (`cargo bench`). Inputs are fixed, so
+//! runs are comparable across changes.
+
+use bf_search::interp::{Interpreter, RunEnd};
+use bf_search::{
+    exec_known_step, replace_hole, step_once, AdvancePolicy, Frontier, InstrSet, PKind, Program,
+    ProgramNode, SearchConfig, SearchNode, Searcher, StepOutcome, Tape,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use std::time::Duration;
+
+/// Search state of the partial program `src` once its known prefix has run
+/// and the pc reached a hole.
+fn at_hole(src: &str) -> SearchNode {
+    let p: Program = src.parse().unwrap();
+    let mut node = SearchNode::concrete(p.root);
+    while !matches!(node.pc.kind, PKind::Hole) {
+        node = exec_known_step(node, &[]).unwrap().advanced().unwrap();
+    }
+    node
+}
+
+fn expansion(c: &mut Criterion) {
+    let long = format!("{}?", "+>".repeat(40));
+    let frontier: Vec<SearchNode> = ["?", "+>++?", "+[->?]", "++[>+[>?]]", &long]
+        .into_iter()
+        .map(at_hole)
+        .collect();
+    let set = InstrSet::parse("><+-.[]").unwrap();
+    c.bench_function("expand frontier of 5 holes", |b| {
+        b.iter(|| {
+            for node in &frontier {
+                black_box(step_once(node, &[1, 2, 3], &set, AdvancePolicy::Search).unwrap());
+            }
+        })
+    });
+}
+
+fn children_per_second(c: &mut Criterion) {
+    // All children of an expansion are built around one located hole
+    // rather than by a `replace_hole` walk each. Measured with this input
+    // before and after that change: 0.17-0.29M and 0.18-0.22M children/s,
+    // no difference beyond the noise.
+    let node = at_hole(&format!("{}+[>+[>?]]", "+>-<".repeat(15)));
+    let set = InstrSet::parse("><+-.[]").unwrap();
+    let expand = || step_once(&node, &[1, 2, 3], &set, AdvancePolicy::Search).unwrap();
+    let StepOutcome::Expanded(children) = expand() else { unreachable!() };
+    let mut group = c.benchmark_group("children");
+    let live = children.iter().filter(|c| !matches!(c, StepOutcome::Pruned(_))).count();
+    group.throughput(Throughput::Elements(live as u64)); // the pruned ones are not children
+    group.bench_function("expand a hole two loops deep after 60 commands", |b| b.iter(expand));
+    group.finish();
+}
+
+fn node_clone(c: &mut Criterion) {
+    // Every child starts as a clone of its parent. The loop stack is a
+    // persistent list, so depth costs nothing here (a Vec of 16 frames took
+    // ~90 ns a clone, the list ~46 ns).
+    let node = at_hole(&format!("+[{}?{}", ">+[".repeat(15), "]".repeat(16)));
+    assert_eq!(node.loop_stack.len(), 16);
+    c.bench_function("clone a search node 16 loops deep", |b| b.iter(|| black_box(&node).clone()));
+}
+
+fn concrete_run(c: &mut Criterion) {
+    // never halts; the inner loop clears a cell five times per round. About
+    // 12 ms: building a placeholder node for every step took 340 ms.
+    let program: Program = "+[>+++++[-]<]".parse().unwrap();
+    c.bench_function("run loop-heavy program 1e6 steps", |b| {
+        b.iter(|| {
+            let mut interp = Interpreter::new(program.root.clone()).with_step_cap(1_000_000);
+            assert_eq!(interp.run_to_limit(1).unwrap(), RunEnd::StepCap);
+        })
+    });
+    // The same loops behind 4000 commands, for a tenth of the steps. Every
+    // `]` jumps through its loop frame, so this should take a tenth of the
+    // time above; finding the loop by node id grew with the tree.
+    let program: Program = format!("{}+[>+++++[-]<]", "+-".repeat(2000)).parse().unwrap();
+    c.bench_function("run loop-heavy program after 4000 commands 1e5 steps", |b| {
+        b.iter(|| {
+            let mut interp = Interpreter::new(program.root.clone()).with_step_cap(100_000);
+            assert_eq!(interp.run_to_limit(1).unwrap(), RunEnd::StepCap);
+        })
+    });
+}
+
+fn tape_access(c: &mut Criterion) {
+    // cells near the origin (the dense block) and far from it (the map)
+    for (name, base) in [("near", 0), ("far", 1_000)] {
+        let tape: Tape = (0..16).map(|i| (base + i, i as u8 + 1)).collect();
+        c.bench_function(&format!("tape read 16 {} cells", name), |b| {
+            b.iter(|| {
+                (base..base + 16)
+                    .map(|i| black_box(&tape).get(i) as u32)
+                    .sum::<u32>()
+            })
+        });
+        c.bench_function(&format!("tape clone and write 16 {} cells", name), |b| {
+            b.iter(|| {
+                let mut t = black_box(&tape).clone();
+                for i in base..base + 16 {
+                    t.set(i, 7);
+                }
+                t
+            })
+        });
+    }
+}
+
+fn hole_replacement(c: &mut Criterion) {
+    // the last hole behind 1000 commands, behind 500 loops holding holes,
+    // or after a loop whose 20000-command body has none: only the path to
+    // the hole is copied, so that body costs nothing (it took ~330 µs a call
+    // when every subtree was rebuilt)
+    let deep: Program = format!("{}?", "+".repeat(1000)).parse().unwrap();
+    let wide: Program = format!("{}?", "[?]".repeat(500)).parse().unwrap();
+    let big_body: Program = format!("[{}]?", "+".repeat(20_000)).parse().unwrap();
+    for (name, p) in [
+        ("replace_hole deep", deep),
+        ("replace_hole wide", wide),
+        ("replace_hole after a big loop", big_body),
+    ] {
+        let hole = p.root.size - 1;
+        c.bench_function(name, |b| {
+            b.iter(|| replace_hole(&p.root, hole, ProgramNode::empty()).unwrap())
+        });
+    }
+}
+
+fn end_to_end(c: &mut Criterion) {
+    for target in [vec![1, 2, 3], vec![5, 5, 5, 5]] {
+        let config = SearchConfig::new(target.clone())
+            .max_expansions(Some(100_000))
+            .build()
+            .unwrap();
+        c.bench_function(&format!("first solution for {:?}", target), |b| {
+            b.iter(|| Searcher::new(config.clone()).next_solution().unwrap())
+        });
+    }
+}
+
+fn frontier_kinds(c: &mut Criterion) {
+    // a target not solved within the budget, so every run does the same work;
+    // `cargo bench --bench alloc -- 1M` compares the two over a long run
+    // (two runs each: heap 8.0 and 8.8 µs, buckets 7.7 and 8.8 µs per
+    // expansion)
+    for (name, frontier) in [
+        ("heap", Frontier::Heap),
+        ("bucket", Frontier::Bucket { width: 0.01 }),
+    ] {
+        let config = SearchConfig::new(vec![7, 200, 3, 100, 9, 1])
+            .frontier(frontier)
+            .max_expansions(Some(50_000))
+            .build()
+            .unwrap();
+        c.bench_function(&format!("50k expansions, {} frontier", name), |b| {
+            b.iter(|| assert!(Searcher::new(config.clone()).next_solution().is_none()))
+        });
+    }
+}
+
+fn rollouts(c: &mut Criterion) {
+    for k in [4, 16] {
+        let config = SearchConfig::new(vec![7, 200, 3, 100, 9, 1])
+            .rollout(k)
+            .max_expansions(Some(50_000))
+            .build()
+            .unwrap();
+        c.bench_function(&format!("50k expansions, rollout {}", k), |b| {
+            b.iter(|| assert!(Searcher::new(config.clone()).next_solution().is_none()))
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+        .sample_size(20);
+    targets = expansion, children_per_second, node_clone, concrete_run, tape_access, hole_replacement, end_to_end,
+        frontier_kinds, rollouts
+}
+criterion_main!(benches);
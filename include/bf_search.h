@@ -0,0 +1,60 @@
+/* C interface to bf_search (build with `cargo build --release --features ffi`
+ * and link against target/release/libbf_search.so or .a). */
+
+#ifndef BF_SEARCH_H
+#define BF_SEARCH_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define BF_SEARCH_OK 0
+#define BF_SEARCH_EXHAUSTED 1        /* no further solutions exist */
+#define BF_SEARCH_BUDGET 2           /* max_expansions used up */
+#define BF_SEARCH_ERR_NULL (-1)      /* a required pointer was NULL */
+#define BF_SEARCH_ERR_BUFFER (-2)    /* buf too small; see code_len */
+#define BF_SEARCH_ERR_INTERNAL (-3)  /* the search aborted */
+
+typedef struct BfSearch BfSearch;
+
+typedef struct BfSearchConfig {
+    double beta;
+    double gamma;
+    uint64_t max_steps;
+    const char *instr_set;   /* e.g. "+-.[]"; NULL for all commands */
+    int golf;                /* nonzero: only strictly shorter solutions */
+    uint64_t max_expansions; /* 0 = unlimited */
+} BfSearchConfig;
+
+typedef struct BfSolutionInfo {
+    size_t code_len;         /* length of the program, without the NUL */
+    uint32_t min_len;
+    uint64_t steps;
+    double score;
+} BfSolutionInfo;
+
+/* Fill *cfg with the defaults (as on the command line). */
+void bf_search_config_default(BfSearchConfig *cfg);
+
+/* Start a search for target[0..len). cfg may be NULL for the defaults.
+ * Returns NULL for an empty target or an invalid config. */
+BfSearch *bf_search_new(const uint8_t *target, size_t len, const BfSearchConfig *cfg);
+
+/* Search on to the next distinct solution and copy its program into buf,
+ * NUL-terminated. Returns BF_SEARCH_OK, BF_SEARCH_EXHAUSTED, BF_SEARCH_BUDGET
+ * or an error. On BF_SEARCH_ERR_BUFFER, out->code_len tells the size needed
+ * (plus one for the NUL) and the next call returns the same solution.
+ * out may be NULL. */
+int bf_search_next_solution(BfSearch *handle, char *buf, size_t buflen, BfSolutionInfo *out);
+
+/* Release a handle from bf_search_new. NULL is ignored. */
+void bf_search_free(BfSearch *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BF_SEARCH_H */
@@ -0,0 +1,194 @@
+//! Solutions found by earlier runs, kept in a file so that later runs of the
+//! same target report only new ones.
+//!
+//! The file holds one JSON `Entry` per line, for any number of targets,
+//! each line tagged with `target_key` of its target. Runs only ever append
+//! to it. A line that does not parse (the end of one cut short by a crash,
+//! say) is skipped, and its number kept in `Archive::skipped` for the
+//! caller to warn about.
+//!
+//! ```
+//! use bf_search::archive::Archive;
+//! use bf_search::{SearchConfig, Searcher};
+//! use std::io::Write;
+//!
+//! let path = std::env::temp_dir().join(format!("bf_search-archive-{}.ndjson", std::process::id()));
+//! let _ = std::fs::remove_file(&path);
+//! // one run: seed the searcher from the archive, report, archive what was reported
+//! let run = |target: Vec<u8>| -> Vec<String> {
+//!     let mut archive = Archive::open(&path, &target).unwrap();
+//!     let mut searcher = Searcher::new(SearchConfig::new(target).build().unwrap());
+//!     archive.seed(&mut searcher);
+//!     let found: Vec<_> = searcher.solutions().take(3).collect();
+//!     found.iter().for_each(|s| archive.add(s));
+//!     assert_eq!(archive.save().unwrap(), 3);
+//!     found.into_iter().map(|s| s.code).collect()
+//! };
+//!
+//! let first = run(vec![1, 2]);
+//! run(vec![7]); // another target, in the same file
+//! let second = run(vec![1, 2]);
+//! assert!(second.iter().all(|code| !first.contains(code)));
+//! let archive = Archive::open(&path, &[1, 2]).unwrap();
+//! let known: Vec<&str> = archive.known().iter().map(|e| e.code.as_str()).collect();
+//! assert_eq!(known, [first, second].concat());
+//! assert!(archive.skipped().is_empty());
+//!
+//! // a run that died halfway through writing a line
+//! let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+//! file.write_all(b"{\"target\":\"d5").unwrap();
+//! let archive = Archive::open(&path, &[1, 2]).unwrap();
+//! assert_eq!((archive.known().len(), archive.skipped()), (6, &[10][..]));
+//! let third = run(vec![1, 2]);
+//! let archive = Archive::open(&path, &[1, 2]).unwrap();
+//! assert_eq!((archive.known().len(), archive.skipped()), (9, &[10][..]));
+//! assert_eq!(archive.known()[6].code, third[0]);
+//! std::fs::remove_file(&path).unwrap();
+//! ```
+
+use crate::{Searcher, Solution};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One archived solution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub target: String, // `target_key` of the target it prints
+    pub code: String,
+    pub min_len: u32,
+    pub steps: u64, // `Solution::steps`
+    pub found_at: u64, // seconds since the Unix epoch
+}
+
+/// What archive lines for `target` are tagged with: a hash of the bytes
+/// (64-bit FNV-1a, in hex), the same on every platform and run.
+///
+/// ```
+/// use bf_search::archive::target_key;
+///
+/// assert_eq!(target_key(&[]), "cbf29ce484222325");
+/// assert_eq!(target_key(b"a"), "af63dc4c8601ec8c");
+/// assert_ne!(target_key(&[1, 2]), target_key(&[2, 1]));
+/// ```
+pub fn target_key(target: &[u8]) -> String {
+    let hash = target.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The archive file as far as one target is concerned: the solutions known
+/// for it, and those found since, until they are saved.
+pub struct Archive {
+    path: PathBuf,
+    key: String,
+    known: Vec<Entry>,
+    skipped: Vec<usize>,
+    fresh: Vec<Entry>,
+}
+
+impl Archive {
+    /// Read the entries for `target` from the file at `path`. A missing file
+    /// is an empty archive, created on the first `save`.
+    pub fn open(path: &Path, target: &[u8]) -> io::Result<Archive> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let key = target_key(target);
+        let (mut known, mut skipped) = (Vec::new(), Vec::new());
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Entry>(line) {
+                Ok(entry) if entry.target == key => known.push(entry),
+                Ok(_) => {}
+                Err(_) => skipped.push(i + 1),
+            }
+        }
+        Ok(Archive {
+            path: path.to_path_buf(),
+            key,
+            known,
+            skipped,
+            fresh: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The entries for the target, oldest first.
+    pub fn known(&self) -> &[Entry] {
+        &self.known
+    }
+
+    /// Line numbers (from 1) of the lines that did not parse.
+    pub fn skipped(&self) -> &[usize] {
+        &self.skipped
+    }
+
+    /// Keep `searcher` from reporting the known solutions again.
+    pub fn seed(&self, searcher: &mut Searcher) {
+        for entry in &self.known {
+            searcher.mark_seen(entry.code.clone());
+        }
+    }
+
+    /// Note a solution to append on the next `save`.
+    pub fn add(&mut self, solution: &Solution) {
+        let found_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.fresh.push(Entry {
+            target: self.key.clone(),
+            code: solution.code.clone(),
+            min_len: solution.min_len,
+            steps: solution.steps,
+            found_at,
+        });
+    }
+
+    /// Append the solutions added since the last save to the file, in one
+    /// write. Returns how many there were.
+    pub fn save(&mut self) -> io::Result<usize> {
+        if self.fresh.is_empty() {
+            return Ok(0);
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+        let mut lines = Vec::new();
+        // Start a line of our own after a cut-off one.
+        if !ends_in_newline(&mut file)? {
+            lines.push(b'\n');
+        }
+        for entry in &self.fresh {
+            serde_json::to_writer(&mut lines, entry)?;
+            lines.push(b'\n');
+        }
+        file.write_all(&lines)?;
+        let n = self.fresh.len();
+        self.known.append(&mut self.fresh);
+        Ok(n)
+    }
+}
+
+/// Whether `file` is empty or ends in a newline.
+fn ends_in_newline(file: &mut File) -> io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
@@ -0,0 +1,742 @@
+//! Program trees in an append-only arena, the alternative to shared trees
+//! that `benches/alloc.rs` measures them against.
+//!
+//! Every node of a search lives in one `Vec`, children are `u32` indices
+//! into it, and building a node is a push. Nothing is ever freed, so
+//! states share subtrees by index for as long as the arena lives, and the
+//! one hole and one `Empty` serve every tree. Positions are as in
+//! `node_at`: a node before its loop body, the body before its next.
+//!
+//! `ArenaSearch` runs the plain best-first search on it, with stepping and
+//! expansion of its own, so that the two can be checked against each
+//! other: `tests/arena.rs` expands the same nodes in the same order with
+//! both and finds the same solutions.
+//!
+//! ```
+//! use bf_search::arena::{Arena, Kind};
+//! use bf_search::{Instr, Program};
+//!
+//! let mut arena = Arena::new();
+//! let p: Program = "+[?]?".parse().unwrap();
+//! let root = arena.import(&p.root);
+//! let out = arena.instr(Instr::Output, arena.empty());
+//! let filled = arena.replace_hole(root, 2, out).unwrap();
+//! assert_eq!(arena.to_bf_string(filled), "+[.]?");
+//! assert_eq!(arena.to_bf_string(root), "+[?]?"); // still there
+//! // the `?` after the loop is the very node that stood in its body
+//! assert_eq!(arena.node_at(filled, 4), arena.node_at(root, 2));
+//! assert_eq!(arena[arena.node_at(filled, 4).unwrap()].kind, Kind::Hole);
+//! assert_eq!(arena.export(filled).to_string(), "+[.]?");
+//! ```
+
+use crate::search::{Callbacks, Expansion, SearchConfig, Solution, StopReason};
+use crate::{checked_score, Instr, InstrSet, PKind, ProgramNode, PruneReason, SearchError, Shared, Tape};
+use ordered_float::NotNan;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::ops::Index;
+
+/// A node's index in its `Arena`.
+pub type NodeId = u32;
+
+/// `PKind`, with the children by index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Hole,
+    Empty,
+    Instr(Instr, NodeId),
+    Loop { body: NodeId, next: NodeId },
+}
+
+/// A node with the counts `ProgramNode` keeps.
+#[derive(Clone, Copy, Debug)]
+pub struct Node {
+    pub kind: Kind,
+    pub min_len: u32,
+    pub holes: u32,
+    pub size: u32,
+}
+
+/// The nodes of any number of trees. A node's children come before it.
+pub struct Arena {
+    nodes: Vec<Node>,
+}
+
+const HOLE: NodeId = 0;
+const EMPTY: NodeId = 1;
+
+impl Default for Arena {
+    fn default() -> Arena {
+        Arena::new()
+    }
+}
+
+impl Index<NodeId> for Arena {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+}
+
+impl Arena {
+    pub fn new() -> Arena {
+        let leaf = |kind, holes| Node {
+            kind,
+            min_len: 0,
+            holes,
+            size: 1,
+        };
+        Arena {
+            nodes: vec![leaf(Kind::Hole, 1), leaf(Kind::Empty, 0)],
+        }
+    }
+
+    /// Nodes built so far, the hole and `Empty` included.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn hole(&self) -> NodeId {
+        HOLE
+    }
+
+    pub fn empty(&self) -> NodeId {
+        EMPTY
+    }
+
+    pub fn instr(&mut self, i: Instr, next: NodeId) -> NodeId {
+        let next_node = self[next];
+        self.push(Node {
+            kind: Kind::Instr(i, next),
+            min_len: 1 + next_node.min_len,
+            holes: next_node.holes,
+            size: 1 + next_node.size,
+        })
+    }
+
+    pub fn looped(&mut self, body: NodeId, next: NodeId) -> NodeId {
+        let (b, n) = (self[body], self[next]);
+        self.push(Node {
+            kind: Kind::Loop { body, next },
+            min_len: 2 + b.min_len + n.min_len,
+            holes: b.holes + n.holes,
+            size: 1 + b.size + n.size,
+        })
+    }
+
+    fn push(&mut self, node: Node) -> NodeId {
+        let id = NodeId::try_from(self.nodes.len()).expect("an arena holds at most u32::MAX nodes");
+        self.nodes.push(node);
+        id
+    }
+
+    /// `id` with the child on one side replaced (`into_body` picks a loop's
+    /// body over its next).
+    fn with_child(&mut self, id: NodeId, into_body: bool, child: NodeId) -> NodeId {
+        match self[id].kind {
+            Kind::Instr(i, _) => self.instr(i, child),
+            Kind::Loop { body, next } => {
+                if into_body {
+                    self.looped(child, next)
+                } else {
+                    self.looped(body, child)
+                }
+            }
+            Kind::Hole | Kind::Empty => unreachable!("only inner nodes have children"),
+        }
+    }
+
+    /// Copy of a shared tree into the arena.
+    pub fn import(&mut self, root: &ProgramNode) -> NodeId {
+        // Post-order over an explicit stack, as `ProgramNode::concretize_min`
+        let mut work = vec![(root, false)];
+        let mut done = Vec::new();
+        while let Some((node, children_done)) = work.pop() {
+            match &node.kind {
+                PKind::Hole => done.push(HOLE),
+                PKind::Empty => done.push(EMPTY),
+                PKind::Instr(_, next) | PKind::Loop { next, .. } if !children_done => {
+                    work.push((node, true));
+                    work.push((next, false));
+                    if let PKind::Loop { body, .. } = &node.kind {
+                        work.push((body, false));
+                    }
+                }
+                PKind::Instr(i, _) => {
+                    let next = done.pop().unwrap();
+                    done.push(self.instr(*i, next));
+                }
+                PKind::Loop { .. } => {
+                    let next = done.pop().unwrap();
+                    let body = done.pop().unwrap();
+                    done.push(self.looped(body, next));
+                }
+            }
+        }
+        done.pop().unwrap()
+    }
+
+    /// Copy of the tree at `root` as a shared tree.
+    pub fn export(&self, root: NodeId) -> Shared<ProgramNode> {
+        let mut work = vec![(root, false)];
+        let mut done = Vec::new();
+        while let Some((id, children_done)) = work.pop() {
+            match self[id].kind {
+                Kind::Hole => done.push(ProgramNode::hole()),
+                Kind::Empty => done.push(ProgramNode::empty()),
+                Kind::Instr(_, next) | Kind::Loop { next, .. } if !children_done => {
+                    work.push((id, true));
+                    work.push((next, false));
+                    if let Kind::Loop { body, .. } = self[id].kind {
+                        work.push((body, false));
+                    }
+                }
+                Kind::Instr(i, _) => {
+                    let next = done.pop().unwrap();
+                    done.push(ProgramNode::instr(i, next));
+                }
+                Kind::Loop { .. } => {
+                    let next = done.pop().unwrap();
+                    let body = done.pop().unwrap();
+                    done.push(ProgramNode::looped(body, next));
+                }
+            }
+        }
+        done.pop().unwrap()
+    }
+
+    /// Brainfuck source of the tree at `root`, with holes as `?`.
+    pub fn to_bf_string(&self, root: NodeId) -> String {
+        // As `ProgramNode`'s `Display`: the loops we are inside wait on `open`
+        let mut out = String::new();
+        let mut open = Vec::new();
+        let mut id = root;
+        loop {
+            match self[id].kind {
+                Kind::Instr(i, next) => {
+                    out.push(i.to_char());
+                    id = next;
+                }
+                Kind::Loop { body, next } => {
+                    out.push('[');
+                    open.push(next);
+                    id = body;
+                }
+                Kind::Hole | Kind::Empty => {
+                    if let Kind::Hole = self[id].kind {
+                        out.push('?');
+                    }
+                    let Some(next) = open.pop() else {
+                        return out;
+                    };
+                    out.push(']');
+                    id = next;
+                }
+            }
+        }
+    }
+
+    /// `ProgramNode::concretize_min` in the arena.
+    pub fn concretize_min(&mut self, root: NodeId) -> NodeId {
+        self.concretize(root, false)
+    }
+
+    /// `ProgramNode::concretize_solution` in the arena.
+    pub fn concretize_solution(&mut self, root: NodeId) -> NodeId {
+        self.concretize(root, true)
+    }
+
+    fn concretize(&mut self, root: NodeId, skip_unentered_loops: bool) -> NodeId {
+        let mut work = vec![(root, false)];
+        let mut done = Vec::new();
+        while let Some((id, children_done)) = work.pop() {
+            match self[id].kind {
+                _ if self[id].holes == 0 => done.push(id), // already concrete
+                Kind::Hole | Kind::Empty => done.push(EMPTY),
+                Kind::Loop { body, next } if skip_unentered_loops && body == HOLE => {
+                    work.push((next, false)); // stands in for the loop
+                }
+                Kind::Instr(_, next) | Kind::Loop { next, .. } if !children_done => {
+                    work.push((id, true));
+                    work.push((next, false));
+                    if let Kind::Loop { body, .. } = self[id].kind {
+                        work.push((body, false));
+                    }
+                }
+                Kind::Instr(i, _) => {
+                    let next = done.pop().unwrap();
+                    done.push(self.instr(i, next));
+                }
+                Kind::Loop { .. } => {
+                    let next = done.pop().unwrap();
+                    let body = done.pop().unwrap();
+                    done.push(self.looped(body, next));
+                }
+            }
+        }
+        done.pop().unwrap()
+    }
+
+    /// The child of `id` at position `at` within it (0 being `id` itself),
+    /// with whether that child is its loop body, and `at` within the child.
+    fn child_at(&self, id: NodeId, at: u32) -> Option<(NodeId, bool, u32)> {
+        if at == 0 || at >= self[id].size {
+            return None;
+        }
+        match self[id].kind {
+            Kind::Instr(_, next) => Some((next, false, at - 1)),
+            Kind::Loop { body, .. } if at <= self[body].size => Some((body, true, at - 1)),
+            Kind::Loop { body, next } => Some((next, false, at - 1 - self[body].size)),
+            Kind::Hole | Kind::Empty => None,
+        }
+    }
+
+    /// The node at position `at` of the tree at `root`, if any.
+    pub fn node_at(&self, root: NodeId, at: u32) -> Option<NodeId> {
+        let (mut id, mut at) = (root, at);
+        while at > 0 {
+            (id, _, at) = self.child_at(id, at)?;
+        }
+        Some(id)
+    }
+
+    /// The ancestors of the hole at position `at`, root first, each with
+    /// whether the path continues into its loop body.
+    fn path_to_hole(&self, root: NodeId, at: u32) -> Option<Vec<(NodeId, bool)>> {
+        let mut path = Vec::new();
+        let (mut id, mut at) = (root, at);
+        while at > 0 {
+            let (child, into_body, rest) = self.child_at(id, at)?;
+            path.push((id, into_body));
+            (id, at) = (child, rest);
+        }
+        (self[id].kind == Kind::Hole).then_some(path)
+    }
+
+    /// The tree at `root` with the hole at position `at` replaced: the
+    /// ancestors of the hole are pushed again, everything else is shared.
+    pub fn replace_hole(&mut self, root: NodeId, at: u32, replacement: NodeId) -> Result<NodeId, SearchError> {
+        let path = self.path_to_hole(root, at).ok_or(SearchError::HoleNotFound(at))?;
+        Ok(path.iter().rev().fold(replacement, |child, &(id, into_body)| self.with_child(id, into_body, child)))
+    }
+
+    /// Length of the `[` `]` of the loops under `root` whose body is a hole.
+    fn unentered_len(&self, root: NodeId) -> u32 {
+        let mut len = 0;
+        let mut work = vec![root];
+        while let Some(id) = work.pop() {
+            if self[id].holes == 0 {
+                continue;
+            }
+            match self[id].kind {
+                Kind::Instr(_, next) => work.push(next),
+                Kind::Loop { body, next } => {
+                    if body == HOLE {
+                        len += 2;
+                    } else {
+                        work.push(body);
+                    }
+                    work.push(next);
+                }
+                Kind::Hole | Kind::Empty => {}
+            }
+        }
+        len
+    }
+}
+
+/// `LoopFrame` by index: the loop's position, its body and its next.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub at: u32,
+    pub body: NodeId,
+    pub next: NodeId,
+}
+
+/// `SearchNode` over an arena.
+#[derive(Clone, Debug)]
+pub struct ArenaNode {
+    pub root: NodeId,
+    pub pc: NodeId,
+    pub pc_at: u32,
+    pub frames: Vec<Frame>, // innermost last
+    pub dp: i64,
+    pub tape: Tape,
+    pub steps: u64,
+    pub last_output: u64,
+    pub correct: usize,
+    pub overflow: Vec<u8>,
+    pub dead_len: u32,
+}
+
+impl ArenaNode {
+    /// A single hole, about to run on `tape`.
+    pub fn initial(tape: Tape) -> ArenaNode {
+        ArenaNode {
+            root: HOLE,
+            pc: HOLE,
+            pc_at: 0,
+            frames: Vec::new(),
+            dp: 0,
+            tape,
+            steps: 0,
+            last_output: 0,
+            correct: 0,
+            overflow: Vec::new(),
+            dead_len: 0,
+        }
+    }
+
+    pub fn halted(&self, arena: &Arena) -> bool {
+        arena[self.pc].kind == Kind::Empty && self.frames.is_empty()
+    }
+
+    /// `SearchNode::score`, or with `live` its `live_score`.
+    pub fn score(&self, arena: &Arena, live: bool, beta: f64, gamma: f64) -> f64 {
+        let min_len = arena[self.root].min_len;
+        let len = if live { min_len - self.dead_len } else { min_len };
+        checked_score(self.correct as f64, len, self.steps, beta, gamma).0
+    }
+}
+
+/// `StepOutcome` over an arena.
+#[derive(Clone, Debug)]
+pub enum Outcome {
+    Advanced(ArenaNode),
+    Expanded(Vec<Outcome>),
+    Halted(ArenaNode),
+    Pruned(PruneReason),
+}
+
+/// `step_once` under `AdvancePolicy::Search`, building in `arena`.
+///
+/// ```
+/// use bf_search::arena::{step_once, Arena, ArenaNode, Outcome};
+/// use bf_search::{InstrSet, Tape};
+///
+/// let mut arena = Arena::new();
+/// let set = InstrSet::parse("+.[]").unwrap();
+/// let Ok(Outcome::Expanded(outcomes)) = step_once(&mut arena, &ArenaNode::initial(Tape::new()), &[1], &set) else {
+///     panic!()
+/// };
+/// let kinds: Vec<String> = outcomes
+///     .iter()
+///     .map(|o| match o {
+///         Outcome::Advanced(n) => format!("advanced to {}", arena.to_bf_string(n.root)),
+///         Outcome::Halted(n) => format!("halted as {:?}", arena.to_bf_string(n.root)),
+///         Outcome::Pruned(r) => r.describe().to_string(),
+///         Outcome::Expanded(_) => unreachable!(),
+///     })
+///     .collect();
+/// assert_eq!(kinds, ["halted as \"\"", "advanced to +?", "output mismatch", "advanced to [?]?"]);
+/// ```
+pub fn step_once(arena: &mut Arena, node: &ArenaNode, target: &[u8], set: &InstrSet) -> Result<Outcome, SearchError> {
+    if arena[node.pc].kind != Kind::Hole {
+        return Ok(exec_step(arena, node.clone(), target));
+    }
+    let path = arena.path_to_hole(node.root, node.pc_at).ok_or(SearchError::HoleNotFound(node.pc_at))?;
+    // Each frame's loop is on the path, which goes into its body; the
+    // body is rebuilt one level further down.
+    let mut at = Vec::with_capacity(path.len());
+    let mut pos = 0;
+    for &(id, into_body) in &path {
+        at.push(pos);
+        pos += match arena[id].kind {
+            Kind::Loop { body, .. } if !into_body => 1 + arena[body].size,
+            _ => 1,
+        };
+    }
+    let depths = node
+        .frames
+        .iter()
+        .map(|frame| {
+            at.binary_search(&frame.at)
+                .ok()
+                .filter(|&d| path[d].1)
+                .map(|d| d + 1)
+                .ok_or(SearchError::DanglingFrame(frame.at))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let fill = |arena: &mut Arena, replacement: NodeId| {
+        let mut rebuilt = vec![replacement; path.len() + 1];
+        for (d, &(id, into_body)) in path.iter().enumerate().rev() {
+            rebuilt[d] = arena.with_child(id, into_body, rebuilt[d + 1]);
+        }
+        let mut child = node.clone();
+        child.root = rebuilt[0];
+        child.pc = replacement;
+        for (frame, &d) in child.frames.iter_mut().zip(&depths) {
+            frame.body = rebuilt[d];
+        }
+        child
+    };
+
+    let child = fill(arena, EMPTY);
+    let mut outcomes = vec![if child.frames.is_empty() {
+        Outcome::Halted(child)
+    } else {
+        Outcome::Advanced(child)
+    }];
+    for &i in &set.instrs {
+        let replacement = arena.instr(i, HOLE);
+        let child = fill(arena, replacement);
+        outcomes.push(exec_step(arena, child, target));
+    }
+    if set.loops {
+        let replacement = arena.looped(HOLE, HOLE);
+        let child = fill(arena, replacement);
+        outcomes.push(exec_step(arena, child, target));
+    }
+    Ok(Outcome::Expanded(outcomes))
+}
+
+/// One step of a node whose pc is not a hole (at a hole it halts), as
+/// `step_in_place` takes it.
+fn exec_step(arena: &Arena, mut node: ArenaNode, target: &[u8]) -> Outcome {
+    match arena[node.pc].kind {
+        Kind::Hole => return Outcome::Halted(node),
+        Kind::Empty => {
+            // the program's end, or a `]`
+            let Some(frame) = node.frames.pop() else {
+                return Outcome::Halted(node);
+            };
+            node.steps = node.steps.saturating_add(1);
+            if node.tape.get(node.dp) != 0 {
+                node.frames.push(frame);
+                node.pc = frame.body;
+                node.pc_at = frame.at + 1;
+            } else {
+                if node.frames.is_empty() {
+                    node.dead_len += arena.unentered_len(frame.body);
+                }
+                node.pc = frame.next;
+                node.pc_at = frame.at + 1 + arena[frame.body].size;
+            }
+        }
+        Kind::Instr(i, next) => {
+            node.steps = node.steps.saturating_add(1);
+            match i {
+                Instr::IncPtr => node.dp = node.dp.saturating_add(1),
+                Instr::DecPtr => node.dp = node.dp.saturating_sub(1),
+                Instr::Inc => node.tape.set(node.dp, node.tape.get(node.dp).wrapping_add(1)),
+                Instr::Dec => node.tape.set(node.dp, node.tape.get(node.dp).wrapping_sub(1)),
+                Instr::Output => {
+                    let v = node.tape.get(node.dp);
+                    if node.correct < target.len() {
+                        if v != target[node.correct] {
+                            return Outcome::Pruned(PruneReason::Mismatch);
+                        }
+                        node.correct += 1;
+                    } else {
+                        node.overflow.push(v);
+                    }
+                    node.last_output = node.steps;
+                }
+                Instr::Input => return Outcome::Pruned(PruneReason::Input),
+            }
+            node.pc = next;
+            node.pc_at += 1;
+        }
+        Kind::Loop { body, next } => {
+            node.steps = node.steps.saturating_add(1);
+            if node.tape.get(node.dp) == 0 {
+                if node.frames.is_empty() && body == HOLE {
+                    node.dead_len += 2;
+                }
+                node.pc = next;
+                node.pc_at += 1 + arena[body].size;
+            } else {
+                node.frames.push(Frame {
+                    at: node.pc_at,
+                    body,
+                    next,
+                });
+                node.pc = body;
+                node.pc_at += 1;
+            }
+        }
+    }
+    Outcome::Advanced(node)
+}
+
+struct Item {
+    score: NotNan<f64>,
+    seq: u64,
+    node: ArenaNode,
+}
+
+impl PartialEq for Item {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score && self.seq == other.seq
+    }
+}
+impl Eq for Item {}
+
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Item {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // As the `Searcher`'s heap: best score, then the smaller seq
+        self.score.cmp(&other.score).then(other.seq.cmp(&self.seq))
+    }
+}
+
+/// `Searcher` over an arena, for the plain search only: it follows the
+/// target, tape, instruction set, β, γ, `live_length`, `max_steps`,
+/// `max_steps_per_byte`, `max_len`, `exact_halt` and `max_expansions` of
+/// its config and ignores the rest, searching from the empty program on a
+/// heap frontier and telling solutions apart by source. A solution is
+/// not run again: its `steps` are the node's, and it has no `state`.
+pub struct ArenaSearch {
+    config: SearchConfig,
+    arena: Arena,
+    queue: BinaryHeap<Item>,
+    seq: u64,
+    expansions: u64,
+    seen: HashSet<String>,
+    last_error: Option<SearchError>,
+}
+
+impl ArenaSearch {
+    pub fn new(config: SearchConfig) -> ArenaSearch {
+        let mut search = ArenaSearch {
+            arena: Arena::new(),
+            queue: BinaryHeap::new(),
+            seq: 0,
+            expansions: 0,
+            seen: HashSet::new(),
+            last_error: None,
+            config,
+        };
+        let start = ArenaNode::initial(search.config.tape_init().clone());
+        let score = search.score(&start);
+        search.push(start, score);
+        search
+    }
+
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    pub fn expansions(&self) -> u64 {
+        self.expansions
+    }
+
+    /// `Searcher::run`: best-first until the budget, the frontier or
+    /// `on_solution` ends it.
+    pub fn run(&mut self, callbacks: &mut impl Callbacks) -> StopReason {
+        loop {
+            if self.config.max_expansions().is_some_and(|n| self.expansions >= n) {
+                return StopReason::Budget;
+            }
+            let Some(Item { score, seq, node }) = self.queue.pop() else {
+                return self.last_error.map_or(StopReason::Exhausted, StopReason::Aborted);
+            };
+            let solution = self.check_solution(&node, score);
+            self.expand(seq, &node, score, callbacks);
+            if let Some(solution) = solution {
+                if callbacks.on_solution(&solution).is_break() {
+                    return StopReason::Stopped;
+                }
+            }
+        }
+    }
+
+    fn score(&self, node: &ArenaNode) -> NotNan<f64> {
+        let (live, beta, gamma) = (self.config.live_length(), self.config.beta(), self.config.gamma());
+        NotNan::new(node.score(&self.arena, live, beta, gamma)).expect("clamped scores are numbers")
+    }
+
+    fn push(&mut self, node: ArenaNode, score: NotNan<f64>) -> u64 {
+        let seq = self.seq;
+        self.seq += 1;
+        self.queue.push(Item { score, seq, node });
+        seq
+    }
+
+    fn check_solution(&mut self, node: &ArenaNode, score: NotNan<f64>) -> Option<Solution> {
+        let target = self.config.target();
+        if node.correct < target.len() || self.config.exact_halt() && !node.halted(&self.arena) {
+            return None;
+        }
+        let concrete = self.arena.concretize_solution(node.root);
+        let code = self.arena.to_bf_string(concrete);
+        if !self.seen.insert(code.clone()) {
+            return None;
+        }
+        Some(Solution {
+            code,
+            program: self.arena.export(concrete),
+            min_len: self.arena[concrete].min_len,
+            steps: node.steps,
+            score: score.into_inner(),
+            on_front: false,
+            state: None,
+        })
+    }
+
+    fn expand(&mut self, seq: u64, node: &ArenaNode, score: NotNan<f64>, callbacks: &mut impl Callbacks) {
+        if node.steps > self.config.max_steps() {
+            return;
+        }
+        let target_len = self.config.target().len();
+        let outcome = step_once(&mut self.arena, node, self.config.target(), self.config.instr_set());
+        self.expansions += 1;
+        let outcomes = match outcome {
+            Ok(Outcome::Expanded(outcomes)) => outcomes,
+            Ok(Outcome::Halted(_)) => Vec::new(),
+            Ok(outcome) => vec![outcome],
+            Err(e) => {
+                self.last_error = Some(e);
+                callbacks.on_error(e);
+                Vec::new()
+            }
+        };
+        let mut children = Vec::new();
+        for outcome in outcomes {
+            let child = match outcome {
+                Outcome::Advanced(child) => Ok(child),
+                Outcome::Halted(child) if child.correct >= target_len => Ok(child),
+                Outcome::Halted(_) => Err(PruneReason::PrematureHalt),
+                Outcome::Pruned(reason) => Err(reason),
+                Outcome::Expanded(_) => continue, // not nested
+            };
+            match child.and_then(|child| self.admit(child)) {
+                Ok(child) => {
+                    let score = self.score(&child);
+                    children.push((self.push(child, score), score.into_inner()));
+                }
+                Err(reason) => callbacks.on_prune(reason),
+            }
+        }
+        callbacks.on_expansion(&Expansion {
+            seq,
+            score: score.into_inner(),
+            children: &children,
+        });
+    }
+
+    /// `child`, unless one of the limits `Searcher` checks drops it.
+    fn admit(&self, child: ArenaNode) -> Result<ArenaNode, PruneReason> {
+        let config = &self.config;
+        if config.exact_halt() && !child.overflow.is_empty() {
+            Err(PruneReason::ExtraOutput)
+        } else if child.steps > config.max_steps() {
+            Err(PruneReason::StepLimit)
+        } else if config.max_steps_per_byte().is_some_and(|r| child.steps - child.last_output > r) {
+            Err(PruneReason::StepsPerByte)
+        } else if config.max_len().is_some_and(|l| self.arena[child.root].min_len > l) {
+            Err(PruneReason::LengthCap)
+        } else {
+            Ok(child)
+        }
+    }
+}
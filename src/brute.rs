@@ -0,0 +1,184 @@
+//! Exhaustive enumeration of short programs, as ground truth for the
+//! search: if nothing up to some length prints the target, nothing shorter
+//! than the search's answer was missed.
+//!
+//! `for_each_program` visits every program of one length over an
+//! `InstrSet`, brackets balanced, in the order of
+//! `InstrSet::to_bf_string`. Programs containing `+-`, `-+`, `><`, `<>`
+//! or `][` are left out: the pair cancels, or the second loop is never
+//! entered, so a shorter program does the same (see `simplify`).
+//!
+//! ```
+//! use bf_search::brute::{count_programs, for_each_program, prints};
+//! use bf_search::InstrSet;
+//!
+//! let set = |s| InstrSet::parse(s).unwrap();
+//! let counts = |s, n| (0..=n).map(|len| count_programs(&set(s), len)).collect::<Vec<_>>();
+//! assert_eq!(counts("+-", 3), [1, 2, 2, 2]); // "", then all `+` or all `-`
+//! // "", "+", "++ []", "+++ +[] [+] []+", "++++ ++[] +[+] +[]+ [++] [+]+ []++ [[]]"
+//! assert_eq!(counts("+[]", 4), [1, 1, 2, 4, 8]);
+//!
+//! // the same as filtering every string over the alphabet
+//! let (alphabet, k) = (b"><+-.[]", 7usize);
+//! for len in 0..=5u32 {
+//!     let brute_force = (0..k.pow(len))
+//!         .map(|mut n| {
+//!             let digit = |_| {
+//!                 let c = alphabet[n % k];
+//!                 n /= k;
+//!                 c
+//!             };
+//!             (0..len).map(digit).collect::<Vec<u8>>()
+//!         })
+//!         .filter(|s| {
+//!             let mut open = 0i32;
+//!             s.iter().all(|&c| {
+//!                 open += (c == b'[') as i32 - (c == b']') as i32;
+//!                 open >= 0
+//!             }) && open == 0
+//!         })
+//!         .filter(|s| {
+//!             let dead = ["+-", "-+", "><", "<>", "]["];
+//!             s.windows(2).all(|w| !dead.iter().any(|d| d.as_bytes() == w))
+//!         })
+//!         .count();
+//!     assert_eq!(count_programs(&set("><+-.[]"), len as usize), brute_force as u64);
+//! }
+//!
+//! // the shortest programs printing a target
+//! let shortest = |s, target: &[u8], max_len| {
+//!     (0..=max_len).find_map(|len| {
+//!         let mut found = Vec::new();
+//!         for_each_program(&set(s), len, |src| {
+//!             if prints(src, target, 1000) {
+//!                 found.push(src.to_string());
+//!             }
+//!         });
+//!         (!found.is_empty()).then_some(found)
+//!     })
+//! };
+//! assert_eq!(shortest("+-.", &[2], 5), Some(vec!["++.".to_string()]));
+//! assert_eq!(shortest("+-.", &[254, 255], 5), Some(vec!["--.+.".to_string()]));
+//! assert_eq!(shortest("+.[]", &[3, 6, 9], 7), Some(vec!["+[++.+]".to_string()]));
+//! assert_eq!(shortest("+.[]", &[3, 6, 9], 6), None);
+//! ```
+
+use crate::interp::{Interpreter, StepEvent};
+use crate::parse::parse_program;
+use crate::InstrSet;
+use std::collections::HashSet;
+
+/// Call `visit` with every program of exactly `len` instructions over `set`
+/// (see the module docs for which are left out), in order.
+pub fn for_each_program(set: &InstrSet, len: usize, mut visit: impl FnMut(&str)) {
+    let alphabet = set.to_bf_string().into_bytes();
+    let mut buf = Vec::with_capacity(len);
+    extend(&alphabet, len, 0, true, &mut buf, &mut visit);
+}
+
+/// `for_each_program` with the cancelling pairs left in: every program of
+/// `len` instructions with balanced brackets, as the search writes them.
+pub fn for_each_balanced(set: &InstrSet, len: usize, mut visit: impl FnMut(&str)) {
+    let alphabet = set.to_bf_string().into_bytes();
+    let mut buf = Vec::with_capacity(len);
+    extend(&alphabet, len, 0, false, &mut buf, &mut visit);
+}
+
+/// How many programs `for_each_program` visits.
+pub fn count_programs(set: &InstrSet, len: usize) -> u64 {
+    let mut n = 0;
+    for_each_program(set, len, |_| n += 1);
+    n
+}
+
+/// Whether `src` prints `target` (and maybe more) within `max_steps`.
+pub fn prints(src: &str, target: &[u8], max_steps: u64) -> bool {
+    if !target.is_empty() && !src.contains('.') {
+        return false;
+    }
+    let Ok(interp) = Interpreter::from_source(src) else {
+        return false;
+    };
+    let mut interp = interp.with_step_cap(max_steps);
+    // Stop at the first wrong byte rather than run out the steps.
+    for &expected in target {
+        loop {
+            match interp.step() {
+                Ok(StepEvent::Output(b)) if b == expected => break,
+                Ok(StepEvent::Stepped { .. }) => {}
+                _ => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Whether the search reaches `src` as a solution: within `max_steps` it
+/// prints `target` and runs every one of its commands, so it enters every
+/// loop and has no `,`. The search writes a command only once execution
+/// gets to it, and leaves out the loops it never entered (see
+/// `ProgramNode::concretize_solution`), so under `max_len` it reports just
+/// these programs; `census` counts them.
+pub fn reached(src: &str, target: &[u8], max_steps: u64) -> bool {
+    let Ok(root) = parse_program(src) else {
+        return false;
+    };
+    let columns = root.columns();
+    let mut unrun: HashSet<usize> = (0..src.len()).collect();
+    let mut interp = Interpreter::new(root).with_step_cap(max_steps);
+    while !(unrun.is_empty() && interp.output().len() >= target.len()) {
+        let column = columns[interp.pc_at() as usize];
+        match interp.step() {
+            Ok(StepEvent::Stepped { .. }) => {}
+            Ok(StepEvent::Output(b)) => {
+                let i = interp.output().len() - 1;
+                if target.get(i).is_some_and(|&t| t != b) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+        unrun.remove(&column);
+    }
+    true
+}
+
+/// Fill `buf` up to `len` with every allowed continuation; `open` brackets
+/// are still to be closed.
+fn extend(
+    alphabet: &[u8],
+    len: usize,
+    open: usize,
+    skip_dead: bool,
+    buf: &mut Vec<u8>,
+    visit: &mut impl FnMut(&str),
+) {
+    if buf.len() == len {
+        if open == 0 {
+            visit(std::str::from_utf8(buf).expect("Brainfuck commands are ASCII"));
+        }
+        return;
+    }
+    let left = len - buf.len() - 1; // after this one
+    for &c in alphabet {
+        let open = match c {
+            b'[' => open + 1,
+            b']' if open == 0 => continue,
+            b']' => open - 1,
+            _ => open,
+        };
+        if open > left || skip_dead && buf.last().is_some_and(|&prev| dead(prev, c)) {
+            continue;
+        }
+        buf.push(c);
+        extend(alphabet, len, open, skip_dead, buf, visit);
+        buf.pop();
+    }
+}
+
+fn dead(prev: u8, c: u8) -> bool {
+    matches!(
+        (prev, c),
+        (b'+', b'-') | (b'-', b'+') | (b'>', b'<') | (b'<', b'>') | (b']', b'[')
+    )
+}
@@ -0,0 +1,103 @@
+//! Counting the search space instead of searching it: run a search under
+//! `SearchConfigBuilder::max_len` until it runs dry and tally its
+//! solutions rather than report them.
+//!
+//! Every program up to the length cap that prints the target is one
+//! solution, told apart by source after the loops the run never entered
+//! are left out (see `ProgramNode::concretize_solution`). So `+[-]+.` is
+//! a program, while `[-]+.` is only ever `+.`; `+-+.` is one too, unlike
+//! in `brute::for_each_program`. What the tally
+//! holds is `brute::reached` over every program with balanced brackets:
+//!
+//! ```
+//! use bf_search::brute::{for_each_balanced, reached};
+//! use bf_search::census::Census;
+//! use bf_search::interp::{Interpreter, RunEnd};
+//! use bf_search::{InstrSet, PruneReason, SearchConfig, Searcher, StopReason};
+//!
+//! let (max_len, max_steps) = (4, 1_000);
+//! let config = SearchConfig::new(vec![1]).max_len(Some(max_len)).max_steps(max_steps).build().unwrap();
+//! let mut census = Census::new(&config);
+//! let mut searcher = Searcher::new(config);
+//! assert_eq!(searcher.run(&mut census), StopReason::Exhausted);
+//!
+//! let mut brute = (0, 0, vec![0; max_len as usize + 1]);
+//! for len in 0..=max_len as usize {
+//!     for_each_balanced(&InstrSet::parse("><+-.,[]").unwrap(), len, |src| {
+//!         if reached(src, &[1], max_steps) {
+//!             let mut run = Interpreter::from_source(src).unwrap().with_step_cap(max_steps);
+//!             brute.0 += 1;
+//!             brute.1 += (run.run_to_limit(usize::MAX) == Ok(RunEnd::Halted)) as u64;
+//!             brute.2[len] += 1;
+//!         }
+//!     });
+//! }
+//! assert_eq!((census.programs(), census.halting()), (brute.0, brute.1));
+//! assert_eq!((census.programs(), census.halting()), (58, 56));
+//! let lengths: Vec<(u32, u64)> = census.lengths().collect();
+//! assert_eq!(lengths, [(2, 1), (3, 7), (4, 50)]); // `+.`, then `>+.`, `+..`, ...
+//! assert_eq!(brute.2, [0, 0, 1, 7, 50]);
+//! assert!(searcher.stats().pruned.get(PruneReason::LengthCap) > 0);
+//! ```
+
+use crate::interp::{Interpreter, RunEnd};
+use crate::{Callbacks, SearchConfig, Solution, Tape};
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+
+/// The solutions of a search, counted. As `Callbacks` it lets the search
+/// go on past every solution.
+pub struct Census {
+    tape_init: Tape,
+    max_steps: u64,
+    programs: u64,
+    halting: u64,
+    lengths: BTreeMap<u32, u64>, // programs by length
+}
+
+impl Census {
+    /// An empty tally for a search under `config`, whose tape and step cap
+    /// decide which programs halt.
+    pub fn new(config: &SearchConfig) -> Census {
+        Census {
+            tape_init: config.tape_init().clone(),
+            max_steps: config.max_steps(),
+            programs: 0,
+            halting: 0,
+            lengths: BTreeMap::new(),
+        }
+    }
+
+    /// Count `solution`; true if it halts within the step cap.
+    pub fn add(&mut self, solution: &Solution) -> bool {
+        let mut run = Interpreter::new(solution.program.clone())
+            .with_tape(self.tape_init.clone())
+            .with_step_cap(self.max_steps);
+        let halts = run.run_to_limit(usize::MAX) == Ok(RunEnd::Halted);
+        self.programs += 1;
+        self.halting += halts as u64;
+        *self.lengths.entry(solution.min_len).or_default() += 1;
+        halts
+    }
+
+    pub fn programs(&self) -> u64 {
+        self.programs
+    }
+
+    pub fn halting(&self) -> u64 {
+        self.halting
+    }
+
+    /// (length, programs of that length), shortest first, lengths without
+    /// any left out.
+    pub fn lengths(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.lengths.iter().map(|(&len, &n)| (len, n))
+    }
+}
+
+impl Callbacks for Census {
+    fn on_solution(&mut self, solution: &Solution) -> ControlFlow<()> {
+        self.add(solution);
+        ControlFlow::Continue(())
+    }
+}
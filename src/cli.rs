@@ -0,0 +1,12 @@
+// Command-line front end: everything that talks to the user.
+
+pub mod brute;
+pub mod compare;
+pub mod config;
+pub mod emit;
+pub mod interrupt;
+pub mod prompt;
+pub mod serve;
+pub mod simplify;
+pub mod trace;
+pub mod tui;
@@ -0,0 +1,95 @@
+// `bf_search brute --max-len L TARGET`: try every program up to length L,
+// shortest first, and list those printing the target. Ground truth for
+// small L, to check the search against.
+
+use crate::{parse_hex_bytes, to_dec};
+use bf_search::brute::{count_programs, for_each_program, prints};
+use bf_search::InstrSet;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct BruteArgs {
+    /// Target bytes in decimal (0..=255), as for the search
+    #[arg(value_name = "BYTE", value_parser = clap::value_parser!(u8), num_args = 0.., value_delimiter = ',')]
+    bytes: Vec<u8>,
+
+    /// Target as a hex string
+    #[arg(short = 'x', long = "hex", value_name = "HEX", conflicts_with = "bytes")]
+    hex: Option<String>,
+
+    /// Longest program to try
+    #[arg(long = "max-len", value_name = "L")]
+    max_len: usize,
+
+    /// Safety cap on interpreter steps for each program
+    #[arg(long = "max-steps", value_name = "M", default_value_t = 10_000)]
+    max_steps: u64,
+
+    /// Allowed commands, as for the search
+    #[arg(long = "instr-set", value_name = "CMDS", default_value = "><+-.,[]")]
+    instr_set: String,
+
+    /// Only count the programs of each length; run none of them
+    #[arg(long = "count-only")]
+    count_only: bool,
+}
+
+pub fn run(args: &BruteArgs) -> i32 {
+    let set = match InstrSet::parse(&args.instr_set) {
+        Ok(set) => set,
+        Err(e) => {
+            eprintln!("Invalid --instr-set: {}", e);
+            return 2;
+        }
+    };
+    let target = match args.hex.as_deref().map(parse_hex_bytes) {
+        Some(Ok(bytes)) => bytes,
+        Some(Err(e)) => {
+            eprintln!("Invalid hex input: {}", e);
+            return 2;
+        }
+        None => args.bytes.clone(),
+    };
+    if target.is_empty() && !args.count_only {
+        eprintln!("Target sequence must not be empty (or give --count-only).");
+        return 2;
+    }
+
+    let (mut total, mut found) = (0, 0);
+    for len in 0..=args.max_len {
+        if args.count_only {
+            let n = count_programs(&set, len);
+            total += n;
+            println!("Length {:>2}: {} program(s)", len, n);
+            continue;
+        }
+        let (mut n, mut matches) = (0, Vec::new());
+        for_each_program(&set, len, |src| {
+            n += 1;
+            if prints(src, &target, args.max_steps) {
+                matches.push(src.to_string());
+            }
+        });
+        total += n;
+        found += matches.len();
+        println!("Length {:>2}: {} program(s), {} printing the target", len, n, matches.len());
+        for src in matches {
+            println!("  {}", src);
+        }
+    }
+    println!();
+    println!(
+        "Enumerated {} program(s) of length 0..={} over {}.",
+        total,
+        args.max_len,
+        set.to_bf_string()
+    );
+    if !args.count_only {
+        println!(
+            "{} print {} within {} steps.",
+            found,
+            to_dec(&target),
+            args.max_steps
+        );
+    }
+    0
+}
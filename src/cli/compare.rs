@@ -0,0 +1,123 @@
+// `bf_search compare A B`: run two programs and show where their outputs diverge.
+
+use crate::{program_arg, EXIT_INTERNAL};
+use bf_search::interp::{Interpreter, RunEnd};
+use bf_search::ProgramNode;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CompareArgs {
+    /// First program (Brainfuck source, or a file containing it)
+    #[arg(value_name = "PROG_A")]
+    a: String,
+
+    /// Second program (Brainfuck source, or a file containing it)
+    #[arg(value_name = "PROG_B")]
+    b: String,
+
+    /// Number of output bytes to compare
+    #[arg(long = "bytes", value_name = "N", default_value_t = 64)]
+    bytes: usize,
+
+    /// Safety cap on interpreter steps for each program
+    #[arg(long = "max-steps", value_name = "M", default_value_t = 1_000_000)]
+    max_steps: u64,
+}
+
+/// Bytes per row of the aligned listing.
+const ROW: usize = 16;
+
+/// Render two outputs side by side in rows of `ROW` bytes, with `^^^` under
+/// every column where they differ (including where only one has a byte).
+pub fn render_aligned(a: &[u8], b: &[u8]) -> String {
+    let cell = |v: Option<&u8>| match v {
+        Some(x) => format!("{:>4}", x),
+        None => "   .".to_string(),
+    };
+    let mut s = String::new();
+    let n = a.len().max(b.len());
+    for start in (0..n).step_by(ROW) {
+        let end = (start + ROW).min(n);
+        let (mut ra, mut rb, mut marks) = (String::new(), String::new(), String::new());
+        for i in start..end {
+            ra.push_str(&cell(a.get(i)));
+            rb.push_str(&cell(b.get(i)));
+            marks.push_str(if a.get(i) == b.get(i) { "    " } else { " ^^^" });
+        }
+        s.push_str(&format!("{:>6}  A:{}\n", start, ra));
+        s.push_str(&format!("{:>6}  B:{}\n", "", rb));
+        if marks.contains('^') {
+            s.push_str(&format!("{:>6}    {}\n", "", marks.trim_end()));
+        }
+    }
+    s
+}
+
+/// Index of the first position where the outputs differ, counting one
+/// running out before the other as a difference.
+fn first_divergence(a: &[u8], b: &[u8]) -> Option<usize> {
+    (0..a.len().max(b.len())).find(|&i| a.get(i) != b.get(i))
+}
+
+/// Returns the exit code: 0 identical, 1 different, 2 bad input (like `cmp`).
+pub fn run(args: &CompareArgs) -> i32 {
+    let mut runs = Vec::new();
+    for (name, arg) in [("A", &args.a), ("B", &args.b)] {
+        let (_, root) = match program_arg(arg) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Invalid program {}: {}", name, e);
+                return 2;
+            }
+        };
+        let mut interp = Interpreter::new(root.clone()).with_step_cap(args.max_steps);
+        let end = match interp.run_to_limit(args.bytes) {
+            Ok(end) => end,
+            Err(e) => {
+                eprintln!("Internal error running program {}: {}", name, e);
+                return EXIT_INTERNAL;
+            }
+        };
+        let steps = interp.steps();
+        runs.push((name, root, interp.into_output(), steps, end));
+    }
+
+    for (name, root, outputs, steps, end) in &runs {
+        let status = match end {
+            RunEnd::Halted | RunEnd::InputUnsupported => format!("halted after {} bytes", outputs.len()),
+            RunEnd::OutputLimit => "still running".to_string(),
+            RunEnd::StepCap => format!("step cap reached after {} bytes", outputs.len()),
+        };
+        println!("Program {}: {}", name, ProgramNode::to_bf_string(root));
+        println!("  length {}, {} steps, {}", root.min_len, steps, status);
+    }
+
+    let (a, b) = (&runs[0].2, &runs[1].2);
+    println!();
+    print!("{}", render_aligned(a, b));
+    println!();
+    match first_divergence(a, b) {
+        None if a.len() == args.bytes => {
+            println!("Identical for {} bytes.", args.bytes);
+            0
+        }
+        None => {
+            println!(
+                "Identical for all {} bytes produced (fewer than the {} requested).",
+                a.len(),
+                args.bytes
+            );
+            0
+        }
+        Some(i) => {
+            let show =
+                |v: Option<&u8>| v.map_or("nothing (stopped)".to_string(), |x| x.to_string());
+            println!(
+                "First divergence at byte {}: A gives {}, B gives {}.",
+                i,
+                show(a.get(i)),
+                show(b.get(i))
+            );
+            1
+        }
+    }
+}
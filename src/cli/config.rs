@@ -0,0 +1,443 @@
+// Layered parameter resolution: built-in defaults < config file < command line.
+//
+// The config file is TOML whose keys are the long option names, e.g.
+//
+//     beta = 0.4
+//     gamma = 0.2
+//     max-steps = 5000000
+//     extra = 256
+//
+// Any subset of keys may be given. The target (`hex` / `bytes`) is layered as
+// a unit: if the command line supplies either, the file's target is ignored.
+// Options without a key here (`--record`, `--tui`, ...) are command-line only.
+
+use crate::cli::emit::Lang;
+use crate::{Args, DedupArg, FrontierArg, OptimizeArg};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory};
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct FileConfig {
+    hex: Option<String>,
+    bytes: Option<Vec<u8>>,
+    extra: Option<usize>,
+    beta: Option<f64>,
+    gamma: Option<f64>,
+    max_steps: Option<u64>,
+    retry_relaxed: Option<f64>,
+    relax_after: Option<u64>,
+    max_parked: Option<usize>,
+    max_steps_per_byte: Option<u64>,
+    demo_steps: Option<u64>,
+    instr_set: Option<String>,
+    tape_init: Option<String>,
+    case_check_steps: Option<u64>,
+    idioms: Option<PathBuf>,
+    near: Option<String>,
+    holes: Option<usize>,
+    as_digits: Option<bool>,
+    digits_sep: Option<String>,
+    solutions: Option<usize>,
+    auto_continue_secs: Option<u64>,
+    solution_gap: Option<f64>,
+    solution_gap_expansions: Option<u64>,
+    golf: Option<bool>,
+    max_len: Option<u32>,
+    count_only: Option<bool>,
+    list: Option<bool>,
+    exact_halt: Option<bool>,
+    require_halt: Option<bool>,
+    pareto_only: Option<bool>,
+    optimize: Option<OptimizeArg>,
+    optimize_slack: Option<u32>,
+    frontier: Option<FrontierArg>,
+    bucket_width: Option<f64>,
+    intern: Option<bool>,
+    max_memory: Option<usize>,
+    rollout: Option<u32>,
+    dedup: Option<DedupArg>,
+    eager: Option<bool>,
+    live_length: Option<bool>,
+    emit: Option<Lang>,
+    explain: Option<bool>,
+    targets: Option<PathBuf>,
+    per_target_expansions: Option<u64>,
+    per_target_secs: Option<f64>,
+    portfolio: Option<String>,
+    portfolio_slice: Option<u64>,
+    portfolio_budget: Option<u64>,
+    stats_file: Option<PathBuf>,
+    stats_interval: Option<f64>,
+    archive: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Default => "default",
+            Source::File => "config file",
+            Source::CommandLine => "command line",
+        })
+    }
+}
+
+/// Where each effective parameter came from, for `--print-config`.
+pub struct Provenance {
+    pub path: Option<PathBuf>,
+    entries: Vec<(&'static str, String, Source)>,
+}
+
+impl Provenance {
+    /// The source of a key, e.g. to name the file key in a late validation error.
+    pub fn source_of(&self, key: &str) -> Source {
+        self.entries
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, _, s)| *s)
+            .unwrap_or(Source::Default)
+    }
+
+    /// Print the merged values as a TOML snippet (usable as a config file),
+    /// annotating each line with its source.
+    pub fn print(&self) {
+        match &self.path {
+            Some(p) => println!("# config file: {}", p.display()),
+            None => println!("# config file: (none)"),
+        }
+        for (key, value, source) in &self.entries {
+            let line = if value.is_empty() {
+                format!("# {} =", key)
+            } else {
+                format!("{} = {}", key, value)
+            };
+            println!("{:<32} # {}", line, source);
+        }
+    }
+}
+
+trait ConfigValue {
+    fn render(&self) -> String;
+
+    /// The value as command-line words for the option `--key`.
+    fn words(&self, key: &str) -> Vec<String>;
+}
+
+/// `words` for values whose `Display` is their command-line form.
+macro_rules! display_words {
+    () => {
+        fn words(&self, key: &str) -> Vec<String> {
+            vec![format!("--{}={}", key, self)]
+        }
+    };
+}
+
+/// `words` for `clap::ValueEnum`s.
+macro_rules! enum_words {
+    () => {
+        fn words(&self, key: &str) -> Vec<String> {
+            let value = clap::ValueEnum::to_possible_value(self).expect("no skipped variants");
+            vec![format!("--{}={}", key, value.get_name())]
+        }
+    };
+}
+
+impl ConfigValue for f64 {
+    fn render(&self) -> String {
+        format!("{:?}", self)
+    }
+    display_words!();
+}
+impl ConfigValue for u32 {
+    fn render(&self) -> String {
+        self.to_string()
+    }
+    display_words!();
+}
+impl ConfigValue for u64 {
+    fn render(&self) -> String {
+        self.to_string()
+    }
+    display_words!();
+}
+impl ConfigValue for usize {
+    fn render(&self) -> String {
+        self.to_string()
+    }
+    display_words!();
+}
+impl ConfigValue for String {
+    fn render(&self) -> String {
+        format!("{:?}", self)
+    }
+    display_words!();
+}
+impl ConfigValue for bool {
+    fn render(&self) -> String {
+        self.to_string()
+    }
+    fn words(&self, key: &str) -> Vec<String> {
+        if *self {
+            vec![format!("--{}", key)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+impl ConfigValue for FrontierArg {
+    fn render(&self) -> String {
+        format!("{:?}", format!("{:?}", self).to_lowercase())
+    }
+    enum_words!();
+}
+impl ConfigValue for OptimizeArg {
+    fn render(&self) -> String {
+        format!("{:?}", format!("{:?}", self).to_lowercase())
+    }
+    enum_words!();
+}
+impl ConfigValue for DedupArg {
+    fn render(&self) -> String {
+        format!("{:?}", format!("{:?}", self).to_lowercase())
+    }
+    enum_words!();
+}
+impl ConfigValue for Lang {
+    fn render(&self) -> String {
+        format!("{:?}", format!("{:?}", self).to_lowercase())
+    }
+    enum_words!();
+}
+impl ConfigValue for PathBuf {
+    fn render(&self) -> String {
+        format!("{:?}", self.display().to_string())
+    }
+    fn words(&self, key: &str) -> Vec<String> {
+        vec![format!("--{}={}", key, self.display())]
+    }
+}
+impl<T: ConfigValue> ConfigValue for Option<T> {
+    fn render(&self) -> String {
+        self.as_ref().map(|v| v.render()).unwrap_or_default()
+    }
+    fn words(&self, key: &str) -> Vec<String> {
+        self.as_ref().map(|v| v.words(key)).unwrap_or_default()
+    }
+}
+impl ConfigValue for Vec<u8> {
+    fn render(&self) -> String {
+        let items: Vec<String> = self.iter().map(|b| b.to_string()).collect();
+        format!("[{}]", items.join(", "))
+    }
+    // The positional target bytes
+    fn words(&self, _key: &str) -> Vec<String> {
+        self.iter().map(|b| b.to_string()).collect()
+    }
+}
+
+/// `$XDG_CONFIG_HOME/bf_search.toml`, falling back to `~/.config/bf_search.toml`.
+fn default_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(dir.join("bf_search.toml"))
+}
+
+/// Name the key on the line an error span points into, if there is one.
+fn key_at(src: &str, pos: usize) -> Option<&str> {
+    let start = src[..pos.min(src.len())].rfind('\n').map_or(0, |i| i + 1);
+    let line = src[start..].lines().next()?;
+    let (key, _) = line.split_once('=')?;
+    let key = key.trim();
+    (!key.is_empty() && !key.starts_with('#')).then_some(key)
+}
+
+fn load(path: &Path) -> Result<FileConfig, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("{}: cannot read config file: {}", path.display(), e))?;
+    let cfg: FileConfig = toml::from_str(&src).map_err(|e| {
+        let line = e
+            .span()
+            .map(|s| src[..s.start.min(src.len())].lines().count().max(1));
+        match (e.span().and_then(|s| key_at(&src, s.start)), line) {
+            (Some(key), Some(line)) => format!(
+                "{}:{}: key `{}`: {}",
+                path.display(),
+                line,
+                key,
+                e.message()
+            ),
+            (None, Some(line)) => format!("{}:{}: {}", path.display(), line, e.message()),
+            _ => format!("{}: {}", path.display(), e.message()),
+        }
+    })?;
+    for (key, v) in [
+        ("beta", cfg.beta),
+        ("gamma", cfg.gamma),
+        ("bucket-width", cfg.bucket_width),
+        ("retry-relaxed", cfg.retry_relaxed),
+        ("stats-interval", cfg.stats_interval),
+        ("solution-gap", cfg.solution_gap),
+    ] {
+        if let Some(v) = v {
+            if !v.is_finite() {
+                return Err(format!(
+                    "{}: key `{}`: must be a finite number, got {}",
+                    path.display(),
+                    key,
+                    v
+                ));
+            }
+        }
+    }
+    Ok(cfg)
+}
+
+/// Fill every `args` field not given on the command line (`argv`, parsed
+/// into `matches`) from the config file (`--config FILE`, or the default
+/// path if it exists). The merged options are then checked against the
+/// `conflicts_with`/`requires` constraints clap applied to `argv` alone.
+pub fn resolve(args: &mut Args, matches: &ArgMatches, argv: &[OsString]) -> Result<Provenance, String> {
+    let path = match &args.config {
+        Some(p) => Some(p.clone()),
+        None => default_path().filter(|p| p.is_file()),
+    };
+    let mut file = match &path {
+        Some(p) => load(p)?,
+        None => FileConfig::default(),
+    };
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    let mut prov = Provenance {
+        path,
+        entries: Vec::new(),
+    };
+
+    // The file's values as command-line words, and the keys they came from
+    let mut words: Vec<String> = Vec::new();
+    let mut keys: Vec<&str> = Vec::new();
+
+    // `--case` and `--targets` stand in for the target.
+    let target_src = if ["hex", "bytes", "case", "targets"].into_iter().any(from_cli) {
+        Source::CommandLine
+    } else if file.hex.is_some() || file.bytes.is_some() {
+        args.hex = file.hex.take();
+        args.bytes = file.bytes.take().unwrap_or_default();
+        words.extend(args.hex.words("hex"));
+        words.extend(args.bytes.words("bytes"));
+        if args.hex.is_some() {
+            keys.push("hex");
+        }
+        if !args.bytes.is_empty() {
+            keys.push("bytes");
+        }
+        Source::File
+    } else {
+        Source::Default
+    };
+    prov.entries.push(("hex", args.hex.render(), target_src));
+    prov.entries
+        .push(("bytes", args.bytes.render(), target_src));
+
+    // `opt` marks fields that are `Option`s in `Args` too (no built-in default).
+    macro_rules! layer {
+        ($field:ident, $key:literal) => {
+            layer!(@ $field, $key, |v| v)
+        };
+        (opt $field:ident, $key:literal) => {
+            layer!(@ $field, $key, Some)
+        };
+        (@ $field:ident, $key:literal, $wrap:expr) => {
+            let src = if from_cli(stringify!($field)) {
+                Source::CommandLine
+            } else if let Some(v) = file.$field.take() {
+                words.extend(v.words($key));
+                keys.push($key);
+                args.$field = $wrap(v);
+                Source::File
+            } else {
+                Source::Default
+            };
+            prov.entries.push(($key, args.$field.render(), src));
+        };
+    }
+    layer!(extra, "extra");
+    layer!(beta, "beta");
+    layer!(gamma, "gamma");
+    layer!(max_steps, "max-steps");
+    layer!(opt retry_relaxed, "retry-relaxed");
+    layer!(opt relax_after, "relax-after");
+    layer!(max_parked, "max-parked");
+    layer!(opt max_steps_per_byte, "max-steps-per-byte");
+    layer!(demo_steps, "demo-steps");
+    layer!(instr_set, "instr-set");
+    layer!(opt tape_init, "tape-init");
+    layer!(opt case_check_steps, "case-check-steps");
+    layer!(opt idioms, "idioms");
+    layer!(opt near, "near");
+    layer!(holes, "holes");
+    layer!(as_digits, "as-digits");
+    layer!(digits_sep, "digits-sep");
+    layer!(opt solutions, "solutions");
+    layer!(opt auto_continue_secs, "auto-continue-secs");
+    layer!(opt solution_gap, "solution-gap");
+    layer!(opt solution_gap_expansions, "solution-gap-expansions");
+    layer!(golf, "golf");
+    layer!(opt max_len, "max-len");
+    layer!(count_only, "count-only");
+    layer!(list, "list");
+    layer!(exact_halt, "exact-halt");
+    layer!(require_halt, "require-halt");
+    layer!(pareto_only, "pareto-only");
+    layer!(opt optimize, "optimize");
+    layer!(optimize_slack, "optimize-slack");
+    layer!(frontier, "frontier");
+    layer!(bucket_width, "bucket-width");
+    layer!(intern, "intern");
+    layer!(opt max_memory, "max-memory");
+    layer!(rollout, "rollout");
+    layer!(dedup, "dedup");
+    layer!(eager, "eager");
+    layer!(live_length, "live-length");
+    layer!(opt emit, "emit");
+    layer!(explain, "explain");
+    layer!(opt targets, "targets");
+    layer!(opt per_target_expansions, "per-target-expansions");
+    layer!(opt per_target_secs, "per-target-secs");
+    layer!(opt portfolio, "portfolio");
+    layer!(portfolio_slice, "portfolio-slice");
+    layer!(opt portfolio_budget, "portfolio-budget");
+    layer!(opt stats_file, "stats-file");
+    layer!(stats_interval, "stats-interval");
+    layer!(opt archive, "archive");
+
+    if !words.is_empty() {
+        let merged = argv.iter().take(1).cloned().chain(words.into_iter().map(OsString::from)).chain(argv.iter().skip(1).cloned());
+        if let Err(e) = Args::command().try_get_matches_from(merged) {
+            // clap's message, up to the usage that follows it
+            let message = e.to_string();
+            let lines: Vec<&str> = message.lines().take_while(|l| !l.is_empty()).map(str::trim).collect();
+            let message = lines.join(" ");
+            return Err(format!(
+                "{}: with the keys {}: {}",
+                prov.path.as_deref().unwrap_or(Path::new("")).display(),
+                keys.join(", "),
+                message.strip_prefix("error: ").unwrap_or(&message)
+            ));
+        }
+    }
+
+    Ok(prov)
+}
@@ -0,0 +1,47 @@
+// `bf_search emit PROGRAM`: translate a program into another language, as
+// `--emit` does for each solution.
+
+use crate::program_arg;
+use bf_search::emit::{to_c, to_rust};
+use bf_search::{ProgramNode, Shared};
+
+/// Languages `--emit` and `emit` translate to.
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    C,
+    Rust,
+}
+
+impl Lang {
+    pub fn translate(self, program: &Shared<ProgramNode>) -> String {
+        match self {
+            Lang::C => to_c(program),
+            Lang::Rust => to_rust(program),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct EmitArgs {
+    /// Program to translate (Brainfuck source, or a file containing it)
+    #[arg(value_name = "PROGRAM")]
+    program: String,
+
+    /// Language to translate into
+    #[arg(long = "lang", value_enum, default_value_t = Lang::C)]
+    lang: Lang,
+}
+
+pub fn run(args: &EmitArgs) -> i32 {
+    match program_arg(&args.program) {
+        Ok((_, root)) => {
+            print!("{}", args.lang.translate(&root));
+            0
+        }
+        Err(e) => {
+            eprintln!("Invalid program: {}", e);
+            2
+        }
+    }
+}
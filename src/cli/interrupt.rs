@@ -0,0 +1,27 @@
+// Ctrl+C handling. The first interrupt only sets a flag, which the search loop
+// polls so it can stop cleanly and print its report; a second interrupt (e.g.
+// while that report is being produced) aborts immediately.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Exit code for an abort by a second Ctrl+C (128 + SIGINT, as a shell reports it).
+const EXIT_ABORTED: i32 = 130;
+
+pub fn install() {
+    let res = ctrlc::set_handler(|| {
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_ABORTED);
+        }
+        eprintln!();
+        eprintln!("Interrupted: stopping the search (Ctrl+C again to abort immediately).");
+    });
+    if let Err(e) = res {
+        eprintln!("Warning: cannot install Ctrl+C handler: {}", e);
+    }
+}
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
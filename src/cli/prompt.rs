@@ -0,0 +1,202 @@
+// What happens after a solution is reported: ask on the terminal, or, when
+// stdin is not a terminal (pipes, job schedulers), follow the batch limits.
+
+use crate::StopReason;
+use std::io::{BufRead, Write};
+use std::time::{Duration, Instant};
+
+pub enum Decision {
+    Continue,
+    Stop(StopReason),
+    Steer(Steer), // carry this out, then ask again
+}
+
+/// A command at the prompt that adjusts the search before it goes on.
+#[derive(Debug, PartialEq)]
+pub enum Steer {
+    Beta(f64),
+    Gamma(f64),
+    Show, // print the parameters and frontier
+}
+
+const COMMANDS: &str = "Enter: next solution, q: quit, beta X / gamma X: change the weights, show: parameters and frontier";
+
+/// One line typed at the prompt: `Ok(None)` continues, `Err` is a message.
+fn parse_line(line: &str) -> Result<Option<Steer>, String> {
+    let mut words = line.split_whitespace();
+    let (Some(cmd), arg) = (words.next(), words.next()) else {
+        return Ok(None);
+    };
+    if words.next().is_some() {
+        return Err(format!("too many arguments to '{}'", cmd));
+    }
+    let value = |name: &str| {
+        let arg = arg.ok_or_else(|| format!("'{}' needs a value, e.g. '{} 0.5'", name, name))?;
+        arg.parse::<f64>().map_err(|_| format!("'{}' is not a number", arg))
+    };
+    match cmd.to_ascii_lowercase().as_str() {
+        "beta" => value("beta").map(|v| Some(Steer::Beta(v))),
+        "gamma" => value("gamma").map(|v| Some(Steer::Gamma(v))),
+        "show" if arg.is_none() => Ok(Some(Steer::Show)),
+        _ => Err(format!("unknown command '{}'", line.trim())),
+    }
+}
+
+pub enum ContinuePolicy {
+    /// Prompt after each solution; Enter continues, `q` (or EOF) quits.
+    Interactive,
+    /// Never prompt. Stop after `max_solutions`, or once `window` has passed
+    /// since the first solution, whichever comes first.
+    Batch {
+        max_solutions: Option<usize>,
+        window: Option<Duration>,
+        deadline: Option<Instant>,
+        gap: bool, // a `SolutionGap` ends the search too
+    },
+}
+
+impl ContinuePolicy {
+    /// Without `--auto-continue-secs` or a solution gap, batch mode stops
+    /// after one solution unless `--solutions` says otherwise.
+    pub fn new(
+        interactive: bool,
+        solutions: Option<usize>,
+        auto_continue_secs: Option<u64>,
+        gap: bool,
+    ) -> Self {
+        if interactive {
+            return ContinuePolicy::Interactive;
+        }
+        let window = auto_continue_secs.map(Duration::from_secs);
+        let open_ended = window.is_some() || gap;
+        ContinuePolicy::Batch {
+            max_solutions: solutions.or(if open_ended { None } else { Some(1) }),
+            window,
+            deadline: None,
+            gap,
+        }
+    }
+
+    pub fn describe(&self) -> Option<String> {
+        match self {
+            ContinuePolicy::Interactive => None,
+            ContinuePolicy::Batch {
+                max_solutions,
+                window,
+                gap,
+                ..
+            } => {
+                let mut limits = Vec::new();
+                if let Some(n) = max_solutions {
+                    limits.push(format!("after {} solution(s)", n));
+                }
+                if let Some(w) = window {
+                    limits.push(format!("{}s after the first solution", w.as_secs()));
+                }
+                if *gap {
+                    limits.push("when no new solution comes within the --solution-gap".to_string());
+                }
+                Some(format!(
+                    "stdin is not a terminal; stopping {}",
+                    limits.join(" or ")
+                ))
+            }
+        }
+    }
+
+    /// Called after the `found`-th solution has been reported.
+    pub fn after_solution(&mut self, found: usize, input: &mut impl BufRead) -> Decision {
+        match self {
+            ContinuePolicy::Interactive => {
+                println!();
+                print!("Press Enter to search for the next different solution (or 'q' + Enter to quit, 'help' for more): ");
+                loop {
+                    std::io::stdout().flush().ok();
+                    let mut line = String::new();
+                    let eof = matches!(input.read_line(&mut line), Ok(0) | Err(_));
+                    if eof || line.trim().eq_ignore_ascii_case("q") {
+                        return Decision::Stop(StopReason::Quit);
+                    }
+                    match parse_line(&line) {
+                        Ok(None) => return Decision::Continue,
+                        Ok(Some(steer)) => return Decision::Steer(steer),
+                        Err(_) if line.trim().eq_ignore_ascii_case("help") => println!("{}", COMMANDS),
+                        Err(e) => println!("{} ({})", e, COMMANDS),
+                    }
+                    print!("> ");
+                }
+            }
+            ContinuePolicy::Batch {
+                max_solutions,
+                window,
+                deadline,
+                ..
+            } => {
+                if let (Some(w), None) = (*window, *deadline) {
+                    *deadline = Some(Instant::now() + w);
+                }
+                match max_solutions {
+                    Some(n) if found >= *n => Decision::Stop(StopReason::SolutionLimit),
+                    _ => Decision::Continue,
+                }
+            }
+        }
+    }
+
+    /// Polled by the search loop: has the auto-continue window run out?
+    pub fn expired(&self) -> bool {
+        match self {
+            ContinuePolicy::Batch {
+                deadline: Some(d), ..
+            } => Instant::now() >= *d,
+            _ => false,
+        }
+    }
+}
+
+/// `--solution-gap` and `--solution-gap-expansions`: the longest the search
+/// may go without a new solution once it has found one. Time spent at the
+/// prompt does not count.
+pub struct SolutionGap {
+    secs: Option<Duration>,
+    expansions: Option<u64>,
+    since: Option<(Instant, u64)>, // when the last solution was dealt with, and the expansions then
+}
+
+impl SolutionGap {
+    pub fn new(secs: Option<f64>, expansions: Option<u64>) -> SolutionGap {
+        SolutionGap {
+            secs: secs.map(Duration::from_secs_f64),
+            expansions,
+            since: None,
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.secs.is_some() || self.expansions.is_some()
+    }
+
+    /// Start over after a solution, `expansions` into the search.
+    pub fn reset(&mut self, expansions: u64) {
+        self.since = Some((Instant::now(), expansions));
+    }
+
+    /// Polled by the search loop: has the gap since the last solution run out?
+    pub fn ran_out(&self, expansions: u64) -> bool {
+        self.since.is_some_and(|(at, from)| {
+            self.secs.is_some_and(|secs| at.elapsed() >= secs)
+                || self.expansions.is_some_and(|n| expansions - from >= n)
+        })
+    }
+
+    pub fn describe(&self) -> Option<String> {
+        let mut limits = Vec::new();
+        if let Some(secs) = self.secs {
+            limits.push(format!("{}s", secs.as_secs_f64()));
+        }
+        if let Some(n) = self.expansions {
+            limits.push(format!("{} expansion(s)", n));
+        }
+        (!limits.is_empty()).then(|| format!("Stop: when no new solution comes within {}", limits.join(" or ")))
+    }
+}
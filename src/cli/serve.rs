@@ -0,0 +1,19 @@
+// `bf_search serve`: answer JSON-RPC requests on stdin, one per line, on
+// stdout (see `bf_search::serve`).
+
+use std::io;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ServeArgs {}
+
+/// Returns the exit code: 0 at the end of the input, 1 if reading or
+/// writing failed.
+pub fn run(_args: &ServeArgs) -> i32 {
+    match bf_search::serve::serve(io::stdin().lock(), io::stdout().lock()) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("serve: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// `bf_search simplify PROGRAM`: apply the peephole rewrites and check the
+// result against the original with the interpreter.
+
+use crate::{program_arg, EXIT_INTERNAL};
+use bf_search::simplify::simplify;
+use bf_search::interp::Interpreter;
+use bf_search::{ProgramNode, SearchError};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SimplifyArgs {
+    /// Program to simplify (Brainfuck source, or a file containing it)
+    #[arg(value_name = "PROGRAM")]
+    program: String,
+
+    /// Output bytes over which the simplified program is checked against the original
+    #[arg(long = "bytes", value_name = "N", default_value_t = 256)]
+    bytes: usize,
+
+    /// Safety cap on interpreter steps for the check
+    #[arg(long = "max-steps", value_name = "M", default_value_t = 1_000_000)]
+    max_steps: u64,
+}
+
+pub fn run(args: &SimplifyArgs) -> i32 {
+    let (_, root) = match program_arg(&args.program) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Invalid program: {}", e);
+            return 2;
+        }
+    };
+    let simple = simplify(&root);
+    println!(
+        "Before (length {}): {}",
+        root.min_len,
+        ProgramNode::to_bf_string(&root)
+    );
+    println!(
+        "After  (length {}): {}",
+        simple.min_len,
+        ProgramNode::to_bf_string(&simple)
+    );
+
+    let run = |program| -> Result<_, SearchError> {
+        let mut interp = Interpreter::new(program).with_step_cap(args.max_steps);
+        let halted = interp.run_to_limit(args.bytes)?.stopped();
+        Ok((interp.steps(), halted, interp.into_output()))
+    };
+    let runs = run(root).and_then(|a| run(simple).map(|b| (a, b)));
+    let ((steps_a, halted_a, out_a), (steps_b, halted_b, out_b)) = match runs {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("Internal error: {}", e);
+            return EXIT_INTERNAL;
+        }
+    };
+    // Removing instructions can only save steps, so the simplified program
+    // may get further before the cap; compare what both produced.
+    let n = out_a.len().min(out_b.len());
+    let diverged =
+        out_a[..n] != out_b[..n] || (halted_a && out_b.len() > n) || (halted_b && out_a.len() > n);
+    let complete = out_a == out_b && (n == args.bytes || (halted_a && halted_b));
+    if diverged {
+        eprintln!(
+            "Internal error: simplified program diverges from the original within {} bytes; keep the original.",
+            args.bytes
+        );
+        return 1;
+    }
+    if complete {
+        println!(
+            "Verified: identical output over {} bytes ({} -> {} steps).",
+            n, steps_a, steps_b
+        );
+    } else {
+        println!(
+            "Verified over the first {} bytes only (step cap reached; {} -> {} steps).",
+            n, steps_a, steps_b
+        );
+    }
+    0
+}
@@ -0,0 +1,236 @@
+// `bf_search trace PROGRAM`: step through a program interactively.
+//
+// Commands (Enter alone repeats `s`):
+//   s [N]   execute N instructions (default 1)
+//   b N     run until the Nth output byte has been produced
+//   p       print dp and the tape around it
+//   q       quit
+
+use crate::{program_arg, to_dec};
+use bf_search::interp::{Interpreter, StepEvent};
+use bf_search::SearchError;
+use std::io::{self, BufRead, Write};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct TraceArgs {
+    /// Program to trace (Brainfuck source, or a file containing it)
+    #[arg(value_name = "PROGRAM")]
+    program: String,
+
+    /// Cap on instructions executed by a single `s N` or `b N` command
+    #[arg(long = "max-steps", value_name = "M", default_value_t = 1_000_000)]
+    max_steps: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TraceCmd {
+    Step(u64),
+    Break(usize),
+    Print,
+    Quit,
+}
+
+fn parse_cmd(line: &str) -> Result<TraceCmd, String> {
+    let mut words = line.split_whitespace();
+    let cmd = words.next().unwrap_or("s");
+    let arg = words.next();
+    if words.next().is_some() {
+        return Err("too many arguments".into());
+    }
+    let number = |what: &str| -> Result<u64, String> {
+        match arg {
+            Some(a) => a.parse().map_err(|_| format!("'{}' is not {}", a, what)),
+            None => Err(format!("missing {}", what)),
+        }
+    };
+    match (cmd, arg) {
+        ("s", None) => Ok(TraceCmd::Step(1)),
+        ("s", Some(_)) => Ok(TraceCmd::Step(number("a step count")?)),
+        ("b", _) => match number("an output byte number")? {
+            0 => Err("output bytes are numbered from 1".into()),
+            n => Ok(TraceCmd::Break(n as usize)),
+        },
+        ("p", None) => Ok(TraceCmd::Print),
+        ("q", None) => Ok(TraceCmd::Quit),
+        ("p" | "q", Some(_)) => Err(format!("'{}' takes no argument", cmd)),
+        _ => Err(format!("unknown command '{}' (s [N], b N, p, q)", cmd)),
+    }
+}
+
+/// Why the program cannot take another step.
+#[derive(Clone, Copy)]
+enum Stop {
+    Halted,
+    Input,
+    Error(SearchError),
+}
+
+struct Tracer {
+    interp: Interpreter,
+    stopped: Option<Stop>,
+    last: Option<char>,
+}
+
+impl Tracer {
+    fn step(&mut self) -> bool {
+        if self.stopped.is_some() {
+            return false;
+        }
+        let stop = match self.interp.step() {
+            Ok(StepEvent::Stepped { instr }) => {
+                self.last = Some(instr);
+                return true;
+            }
+            Ok(StepEvent::Output(_)) => {
+                self.last = Some('.');
+                return true;
+            }
+            Ok(StepEvent::InputUnsupported) => Stop::Input,
+            Ok(StepEvent::Halted | StepEvent::StepCapReached) => Stop::Halted,
+            Err(e) => Stop::Error(e),
+        };
+        self.stopped = Some(stop);
+        false
+    }
+
+    /// Execute one command; returns the number of instructions executed.
+    fn run(&mut self, cmd: &TraceCmd, cap: u64) -> u64 {
+        let mut n = 0;
+        match *cmd {
+            TraceCmd::Step(k) => {
+                while n < k.min(cap) && self.step() {
+                    n += 1;
+                }
+            }
+            TraceCmd::Break(byte) => {
+                while self.interp.output().len() < byte && n < cap && self.step() {
+                    n += 1;
+                }
+            }
+            TraceCmd::Print | TraceCmd::Quit => {}
+        }
+        n
+    }
+}
+
+/// What changed between two states: pointer, cells, and output.
+fn render_delta(before: &Interpreter, after: &Interpreter) -> String {
+    let mut parts = Vec::new();
+    if before.dp() != after.dp() {
+        parts.push(format!("dp {} -> {}", before.dp(), after.dp()));
+    }
+    let mut idx: Vec<i64> = before.touched_cells().chain(after.touched_cells()).collect();
+    idx.sort_unstable();
+    idx.dedup();
+    let cells: Vec<String> = idx
+        .into_iter()
+        .filter(|&i| before.cell(i) != after.cell(i))
+        .map(|i| format!("[{}] {} -> {}", i, before.cell(i), after.cell(i)))
+        .collect();
+    if !cells.is_empty() {
+        parts.push(format!("cells {}", cells.join(", ")));
+    }
+    if after.output().len() > before.output().len() {
+        parts.push(format!(
+            "output {}",
+            to_dec(&after.output()[before.output().len()..])
+        ));
+    }
+    if parts.is_empty() {
+        "no state change".into()
+    } else {
+        parts.join("; ")
+    }
+}
+
+/// Cells dp-8..=dp+8, with the current one bracketed.
+fn render_tape(interp: &Interpreter) -> String {
+    let dp = interp.dp();
+    let cells: Vec<String> = (dp - 8..=dp + 8)
+        .map(|i| {
+            if i == dp {
+                format!("[{}]", interp.cell(i))
+            } else {
+                interp.cell(i).to_string()
+            }
+        })
+        .collect();
+    format!(
+        "dp = {}, steps = {}, tape[{}..={}]: {}",
+        dp,
+        interp.steps(),
+        dp - 8,
+        dp + 8,
+        cells.join(" ")
+    )
+}
+
+pub fn run(args: &TraceArgs) -> i32 {
+    let (_, root) = match program_arg(&args.program) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Invalid program: {}", e);
+            return 2;
+        }
+    };
+    let mut tracer = Tracer {
+        interp: Interpreter::new(root),
+        stopped: None,
+        last: None,
+    };
+    println!("Commands: s [N] step, b N run to Nth output byte, p print state, q quit.");
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    loop {
+        print!("trace> ");
+        io::stdout().flush().ok();
+        let mut line = String::new();
+        if matches!(input.read_line(&mut line), Ok(0) | Err(_)) {
+            println!();
+            break;
+        }
+        let cmd = match parse_cmd(&line) {
+            Ok(c) => c,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+        match cmd {
+            TraceCmd::Quit => break,
+            TraceCmd::Print => println!("{}", render_tape(&tracer.interp)),
+            _ => {
+                let before = tracer.interp.clone();
+                let n = tracer.run(&cmd, args.max_steps);
+                if n > 0 {
+                    println!(
+                        "executed {} instruction(s), last '{}' (step {}): {}",
+                        n,
+                        tracer.last.unwrap_or('?'),
+                        tracer.interp.steps(),
+                        render_delta(&before, &tracer.interp)
+                    );
+                }
+                match tracer.stopped {
+                    Some(Stop::Halted) => println!("program halted"),
+                    Some(Stop::Input) => {
+                        println!("program stopped at ',' (input is not supported)")
+                    }
+                    Some(Stop::Error(e)) => println!("internal error: {}", e),
+                    None if n == args.max_steps => {
+                        println!("stopped after --max-steps {} instructions", args.max_steps)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    let interp = &tracer.interp;
+    println!(
+        "{} steps, {} output byte(s): {}",
+        interp.steps(),
+        interp.output().len(),
+        to_dec(interp.output())
+    );
+    0
+}
@@ -0,0 +1,167 @@
+// `--tui`: run the search on a full-screen dashboard instead of printing to
+// stdout. The search runs in slices of `SLICE`, cancelled through
+// `Callbacks::cancelled`; between slices the screen is redrawn and keys are
+// read. What the search would have printed goes to the dashboard as events.
+
+use crate::{SearchOutcome, StopReason};
+use bf_search::dashboard::{Action, Dashboard, Event};
+use bf_search::monitor::SnapshotFile;
+use bf_search::{Callbacks, SearchConfig, SearchError, Searcher, Solution};
+use ratatui::crossterm::event::{self, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::io;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+/// How long the search runs between redraws.
+const SLICE: Duration = Duration::from_millis(50);
+/// How long to wait for a key while the search is paused or over.
+const IDLE: Duration = Duration::from_millis(250);
+
+/// Collects what the search reports during one slice.
+struct Slice {
+    verbose: bool,
+    deadline: Instant,
+    events: Vec<Event>,
+    shortest: Option<(String, u32)>,
+}
+
+impl Callbacks for Slice {
+    fn on_solution(&mut self, solution: &Solution) -> ControlFlow<()> {
+        if self.shortest.as_ref().is_none_or(|(_, len)| solution.min_len < *len) {
+            self.shortest = Some((solution.code.clone(), solution.min_len));
+        }
+        self.events.push(Event::Solution {
+            code: solution.code.clone(),
+            len: solution.min_len,
+        });
+        ControlFlow::Continue(())
+    }
+
+    fn on_unverified(&mut self, solution: &Solution, _output: &[u8]) {
+        self.events.push(Event::Note(format!(
+            "Internal error: {} does not reproduce the target; not counted",
+            solution.code
+        )));
+    }
+
+    fn on_non_halting(&mut self, solution: &Solution) {
+        if self.verbose {
+            self.events.push(Event::Note(format!("Non-halting, suppressed: {}", solution.code)));
+        }
+    }
+
+    fn on_relax(&mut self, max_steps: u64, revived: usize) {
+        self.events.push(Event::Note(format!(
+            "Relaxed --max-steps to {}, {} node(s) revived",
+            max_steps, revived
+        )));
+    }
+
+    fn on_error(&mut self, error: SearchError) {
+        self.events.push(Event::Note(format!("Node skipped: {}", error)));
+    }
+
+    fn cancelled(&mut self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
+
+/// Search under `config` until the user quits, keeping `stats` up to date
+/// if given. The terminal is restored before returning, also on error.
+pub fn run(
+    config: SearchConfig,
+    verbose: bool,
+    mut stats: Option<SnapshotFile>,
+) -> io::Result<SearchOutcome> {
+    let mut searcher = Searcher::new(config);
+    let mut slice = Slice {
+        verbose,
+        deadline: Instant::now(),
+        events: Vec::new(),
+        shortest: None,
+    };
+    let mut terminal = ratatui::try_init()?;
+    let ended = drive(&mut terminal, &mut searcher, &mut slice, &mut stats);
+    ratatui::restore();
+    if let Some(Err(e)) = stats.map(|file| file.finish(&searcher)) {
+        eprintln!("Warning: cannot write --stats-file: {}", e);
+    }
+    let reason = match ended? {
+        None | Some(bf_search::StopReason::Stopped | bf_search::StopReason::Cancelled) => {
+            StopReason::Quit
+        }
+        Some(bf_search::StopReason::Exhausted) => StopReason::Exhausted,
+        Some(bf_search::StopReason::Budget) => StopReason::Budget,
+        Some(bf_search::StopReason::Aborted(e)) => StopReason::Aborted(e),
+    };
+    Ok(SearchOutcome {
+        reason,
+        searcher,
+        shortest: slice.shortest,
+        log: None,
+        archived: None,
+    })
+}
+
+/// The event loop. Returns why the search ended, or `None` if the user quit
+/// while it was still going.
+fn drive(
+    terminal: &mut DefaultTerminal,
+    searcher: &mut Searcher,
+    slice: &mut Slice,
+    stats: &mut Option<SnapshotFile>,
+) -> io::Result<Option<bf_search::StopReason>> {
+    let mut dash = Dashboard::new();
+    let mut ended = None;
+    loop {
+        if ended.is_none() && !dash.paused() {
+            slice.deadline = Instant::now() + SLICE;
+            match searcher.run(slice) {
+                bf_search::StopReason::Cancelled => {}
+                reason => {
+                    slice.events.push(Event::Note(format!(
+                        "Search over ({:?}); press q for the summary",
+                        reason
+                    )));
+                    ended = Some(reason);
+                }
+            }
+        }
+        if let Some(file) = stats.as_mut().filter(|file| file.due()) {
+            if let Some(e) = file.update(searcher) {
+                slice.events.push(Event::Note(format!("Cannot write --stats-file: {}", e)));
+            }
+        }
+        for event in slice.events.drain(..) {
+            dash.apply(event);
+        }
+        dash.observe(searcher);
+        terminal.draw(|frame| dash.draw(frame))?;
+
+        let wait = if ended.is_some() || dash.paused() { IDLE } else { Duration::ZERO };
+        if !event::poll(wait)? {
+            continue;
+        }
+        let event::Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match dash.key(key) {
+            Some(Action::Quit) => return Ok(ended),
+            Some(Action::Weights { beta, gamma }) => {
+                let note = match searcher.set_weights(beta, gamma) {
+                    Ok(()) => format!(
+                        "Re-scored {} open node(s) with beta = {}, gamma = {}",
+                        searcher.frontier_len(),
+                        beta,
+                        gamma
+                    ),
+                    Err(e) => format!("Not changed: {}", e),
+                };
+                dash.apply(Event::Note(note));
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! State and drawing of the `--tui` dashboard, kept apart from the terminal
+//! so that it can be driven and drawn off-screen.
+//!
+//! Whatever the search would print arrives as an `Event`; between runs,
+//! `Dashboard::observe` samples the `Searcher` for counters and the best
+//! node so far. `Dashboard::key` turns key presses into `Action`s for the
+//! caller to carry out (pausing is kept here).
+//!
+//! ```
+//! use bf_search::dashboard::{Action, Dashboard, Event};
+//! use bf_search::{SearchConfig, Searcher};
+//! use ratatui::backend::TestBackend;
+//! use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+//! use ratatui::Terminal;
+//!
+//! let mut searcher = Searcher::new(SearchConfig::new(vec![3, 6, 9]).build().unwrap());
+//! let mut dash = Dashboard::new();
+//! let found: Vec<_> = searcher.solutions().take(2).collect();
+//! for s in found {
+//!     dash.apply(Event::Solution { code: s.code, len: s.min_len });
+//! }
+//! dash.apply(Event::Note("checkpoint".into()));
+//! dash.observe(&searcher);
+//!
+//! let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+//! terminal.draw(|frame| dash.draw(frame)).unwrap();
+//! let screen: String = terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect();
+//! for shown in ["+[++.+]", "++[+.++]", "checkpoint", "Expansions", "running"] {
+//!     assert!(screen.contains(shown), "{} missing", shown);
+//! }
+//!
+//! let mut key = |code| dash.key(KeyEvent::new(code, KeyModifiers::NONE));
+//! assert_eq!(key(KeyCode::Char('B')), Some(Action::Weights { beta: 1.1, gamma: 1.0 }));
+//! assert_eq!(key(KeyCode::Char('g')), Some(Action::Weights { beta: 1.0, gamma: 0.9 }));
+//! assert_eq!(key(KeyCode::Char(' ')), None);
+//! assert_eq!(key(KeyCode::Char('x')), None);
+//! assert_eq!(key(KeyCode::Char('q')), Some(Action::Quit));
+//! assert!(dash.paused());
+//! let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+//! assert_eq!(dash.key(ctrl_c), Some(Action::Quit));
+//!
+//! terminal.draw(|frame| dash.draw(frame)).unwrap();
+//! let screen: String = terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect();
+//! assert!(screen.contains("paused"));
+//! ```
+
+use crate::{ProgramNode, Searcher};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, Paragraph, Sparkline};
+use ratatui::Frame;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Best scores kept for the sparkline, one per `observe`.
+const HISTORY: usize = 256;
+/// β and γ change by this much per key press.
+const WEIGHT_STEP: f64 = 0.1;
+
+/// Something the search has to say.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Solution { code: String, len: u32 },
+    Note(String), // a line of text, e.g. a relaxation or an error
+}
+
+/// What a key press asks of the caller.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    Weights { beta: f64, gamma: f64 },
+}
+
+#[derive(Default)]
+pub struct Dashboard {
+    scores: VecDeque<f64>, // best score at each `observe`, newest last
+    target_len: usize,
+    beta: f64,
+    gamma: f64,
+    expansions: u64,
+    frontier: usize,
+    rate: f64, // pushes per second over the last sampling window
+    window: Option<(Instant, u64)>, // (start, pushes) of that window
+    best: Option<(String, usize, f64)>, // (program, correct, score)
+    solutions: Vec<(String, u32)>,
+    notes: Vec<String>,
+    paused: bool,
+}
+
+impl Dashboard {
+    pub fn new() -> Dashboard {
+        Dashboard::default()
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn apply(&mut self, event: Event) {
+        match event {
+            Event::Solution { code, len } => self.solutions.push((code, len)),
+            Event::Note(text) => self.notes.push(text),
+        }
+    }
+
+    /// Take in the searcher's current state.
+    pub fn observe(&mut self, searcher: &Searcher) {
+        let (config, stats) = (searcher.config(), searcher.stats());
+        self.target_len = config.target().len();
+        (self.beta, self.gamma) = (config.beta(), config.gamma());
+        self.expansions = stats.expansions;
+        self.frontier = searcher.frontier_len();
+        let now = Instant::now();
+        match self.window {
+            Some((start, pushed)) if now - start >= Duration::from_millis(500) => {
+                self.rate = (stats.pushed - pushed) as f64 / (now - start).as_secs_f64();
+                self.window = Some((now, stats.pushed));
+            }
+            Some(_) => {}
+            None => self.window = Some((now, stats.pushed)),
+        }
+        if let (Some(node), Some(score)) = (searcher.best(), searcher.best_score()) {
+            let program = ProgramNode::to_bf_string(&node.root);
+            self.best = Some((program, node.correct, score));
+            if self.scores.len() == HISTORY {
+                self.scores.pop_front();
+            }
+            self.scores.push_back(score);
+        }
+    }
+
+    /// The action `key` asks for, if any. Space pauses and resumes; `b`/`B`
+    /// lower and raise β, `g`/`G` γ; `q`, Esc and Ctrl+C quit.
+    pub fn key(&mut self, key: KeyEvent) -> Option<Action> {
+        let step = |w: f64, d: f64| ((w + d) * 100.0).round().max(0.0) / 100.0;
+        let (beta, gamma) = (self.beta, self.gamma);
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Action::Quit),
+            KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+            KeyCode::Char(' ') => {
+                self.paused = !self.paused;
+                None
+            }
+            KeyCode::Char('b') => Some(Action::Weights { beta: step(beta, -WEIGHT_STEP), gamma }),
+            KeyCode::Char('B') => Some(Action::Weights { beta: step(beta, WEIGHT_STEP), gamma }),
+            KeyCode::Char('g') => Some(Action::Weights { beta, gamma: step(gamma, -WEIGHT_STEP) }),
+            KeyCode::Char('G') => Some(Action::Weights { beta, gamma: step(gamma, WEIGHT_STEP) }),
+            _ => None,
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [header, sparkline, best, lists, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Length(4),
+            Constraint::Min(4),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let state = if self.paused { "paused" } else { "running" };
+        let counters = format!(
+            "Expansions: {}   Frontier: {}   Pushes/s: {:.0}   beta = {}, gamma = {}",
+            self.expansions, self.frontier, self.rate, self.beta, self.gamma
+        );
+        let title = format!("bf_search: {} byte target, {}", self.target_len, state);
+        frame.render_widget(Paragraph::new(counters).block(Block::bordered().title(title)), header);
+
+        // Sparklines take non-negative integers: shift the scores up to
+        // start at 1, in hundredths.
+        let low = self.scores.iter().copied().fold(f64::INFINITY, f64::min);
+        let data: Vec<u64> =
+            self.scores.iter().map(|s| ((s - low) * 100.0).round() as u64 + 1).collect();
+        let skip = data.len().saturating_sub(sparkline.width.saturating_sub(2) as usize);
+        frame.render_widget(
+            Sparkline::default().data(&data[skip..]).block(Block::bordered().title("Best score")),
+            sparkline,
+        );
+
+        let text = match &self.best {
+            Some((program, correct, score)) => vec![
+                Line::from(program.as_str()),
+                Line::from(format!("{}/{} bytes correct, score {:.3}", correct, self.target_len, score)),
+            ],
+            None => vec![Line::from("(none yet)")],
+        };
+        frame.render_widget(Paragraph::new(text).block(Block::bordered().title("Best node")), best);
+
+        let [solutions, notes] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(lists);
+        let found = self.solutions.iter().enumerate().rev().map(|(i, (code, len))| {
+            format!("#{} ({}): {}", i + 1, len, code)
+        });
+        let title = format!("Solutions ({})", self.solutions.len());
+        frame.render_widget(List::new(found).block(Block::bordered().title(title)), solutions);
+        let messages = self.notes.iter().rev().map(String::as_str);
+        frame.render_widget(List::new(messages).block(Block::bordered().title("Messages")), notes);
+
+        frame.render_widget(
+            Paragraph::new("space pause/resume   b/B beta -/+   g/G gamma -/+   q quit"),
+            footer,
+        );
+    }
+}
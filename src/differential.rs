@@ -0,0 +1,223 @@
+//! The interpreter semantics, run several ways behind one trait so that
+//! the ways can be checked against each other.
+//!
+//! `Reference` is a textbook interpreter over the source text, sharing no
+//! code with the rest of the crate. `Concrete` is `interp::Interpreter`.
+//! `Guided` grows the program from a single hole the way the search does,
+//! choosing at each expansion the production the program has there, so the
+//! run goes through `step_once` like a search node's. They all follow the
+//! conventions of `Interpreter::run_to_limit`: `[` and `]` are a step each,
+//! the tape is unbounded with wrapping cells, and a `,` stops the program
+//! without running. `tests/differential.rs` feeds them random programs.
+//!
+//! ```
+//! use bf_search::differential::{agree, Budget, Run, RUNNERS};
+//! use bf_search::interp::RunEnd;
+//!
+//! let budget = Budget { steps: 1000, output: 4 };
+//! let run = |output: &[u8], steps, end| Run { output: output.to_vec(), steps, end };
+//! assert_eq!(agree("++[>+++<-]>.", budget), Ok(run(&[6], 19, RunEnd::Halted)));
+//! assert_eq!(agree("<+.,+.", budget), Ok(run(&[1], 3, RunEnd::InputUnsupported)));
+//! assert_eq!(agree("+[.]", budget), Ok(run(&[1; 4], 9, RunEnd::OutputLimit)));
+//! assert_eq!(agree("+[[]]", budget), Ok(run(&[], 1000, RunEnd::StepCap)));
+//! assert_eq!(agree("+[>+]", budget).map(|r| r.end), Ok(RunEnd::StepCap));
+//! assert_eq!(RUNNERS.len(), 3);
+//! ```
+
+use crate::interp::{Interpreter, RunEnd};
+use crate::parse::parse_program;
+use crate::{step_once, AdvancePolicy, InstrSet, PKind, ProgramNode, PruneReason, SearchNode, StepOutcome};
+use std::collections::HashMap;
+
+/// Limits on one run, as for `Interpreter::run_to_limit` with a step cap.
+#[derive(Clone, Copy, Debug)]
+pub struct Budget {
+    pub steps: u64,
+    pub output: usize,
+}
+
+/// What a run did, to be the same whichever runner did it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Run {
+    pub output: Vec<u8>,
+    pub steps: u64,
+    pub end: RunEnd,
+}
+
+/// One implementation of the semantics.
+pub trait Runner {
+    fn name(&self) -> &'static str;
+
+    /// Run `src` (a valid program) within `budget`.
+    fn run(&self, src: &str, budget: Budget) -> Result<Run, String>;
+}
+
+/// Every runner, `Reference` first.
+pub const RUNNERS: &[&dyn Runner] = &[&Reference, &Concrete, &Guided];
+
+/// The run all of `RUNNERS` agree on, or what the first to differ from
+/// `Reference` did instead.
+pub fn agree(src: &str, budget: Budget) -> Result<Run, String> {
+    let expected = Reference.run(src, budget)?;
+    for runner in &RUNNERS[1..] {
+        let run = runner.run(src, budget)?;
+        if run != expected {
+            return Err(format!(
+                "{}: {} ran {:?}, {} ran {:?}",
+                src,
+                Reference.name(),
+                expected,
+                runner.name(),
+                run
+            ));
+        }
+    }
+    Ok(expected)
+}
+
+/// A plain interpreter over the source text, with a jump table.
+pub struct Reference;
+
+impl Runner for Reference {
+    fn name(&self) -> &'static str {
+        "reference"
+    }
+
+    fn run(&self, src: &str, budget: Budget) -> Result<Run, String> {
+        let code = src.as_bytes();
+        let mut jump = vec![0; code.len()];
+        let mut open = Vec::new();
+        for (i, &c) in code.iter().enumerate() {
+            match c {
+                b'[' => open.push(i),
+                b']' => {
+                    let j = open.pop().ok_or("unmatched ]")?;
+                    (jump[i], jump[j]) = (j, i);
+                }
+                _ => {}
+            }
+        }
+        if !open.is_empty() {
+            return Err("unmatched [".into());
+        }
+
+        let mut tape: HashMap<i64, u8> = HashMap::new();
+        let (mut pc, mut dp, mut steps, mut output) = (0, 0i64, 0, Vec::new());
+        let end = loop {
+            if output.len() >= budget.output {
+                break RunEnd::OutputLimit;
+            }
+            if steps >= budget.steps {
+                break RunEnd::StepCap;
+            }
+            let Some(&c) = code.get(pc) else { break RunEnd::Halted };
+            let cell = tape.entry(dp).or_insert(0);
+            match c {
+                b'>' => dp = dp.saturating_add(1),
+                b'<' => dp = dp.saturating_sub(1),
+                b'+' => *cell = cell.wrapping_add(1),
+                b'-' => *cell = cell.wrapping_sub(1),
+                b'.' => output.push(*cell),
+                b',' => break RunEnd::InputUnsupported,
+                // past the `]` / back to just after the `[`
+                b'[' if *cell == 0 => pc = jump[pc],
+                b']' if *cell != 0 => pc = jump[pc],
+                _ => {}
+            }
+            pc += 1;
+            steps += 1;
+        };
+        Ok(Run { output, steps, end })
+    }
+}
+
+/// `interp::Interpreter`.
+pub struct Concrete;
+
+impl Runner for Concrete {
+    fn name(&self) -> &'static str {
+        "interpreter"
+    }
+
+    fn run(&self, src: &str, budget: Budget) -> Result<Run, String> {
+        let mut interp = Interpreter::from_source(src)?.with_step_cap(budget.steps);
+        let end = interp.run_to_limit(budget.output).map_err(|e| e.to_string())?;
+        Ok(Run {
+            output: interp.output().to_vec(),
+            steps: interp.steps(),
+            end,
+        })
+    }
+}
+
+/// The search's stepping, from `SearchNode::initial`, expanding each hole
+/// into the program's own production.
+pub struct Guided;
+
+impl Runner for Guided {
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    fn run(&self, src: &str, budget: Budget) -> Result<Run, String> {
+        let program = parse_program(src)?;
+        let set = InstrSet::parse("><+-.,[]")?;
+        let mut node = SearchNode::initial();
+        let end = loop {
+            // Against an empty target, all output is overflow.
+            if node.overflow.len() >= budget.output {
+                break RunEnd::OutputLimit;
+            }
+            if node.steps >= budget.steps {
+                break RunEnd::StepCap;
+            }
+            let mut outcome = step_once(&node, &[], &set, AdvancePolicy::Search)
+                .map_err(|e| e.to_string())?;
+            if let StepOutcome::Expanded(mut children) = outcome {
+                let chosen = at_hole(&node.root, &program, node.pc_at)
+                    .map(|sub| production(&set, sub))
+                    .ok_or_else(|| format!("the search grew {}, not a prefix of {}", node.root, src))?;
+                outcome = children.swap_remove(chosen);
+            }
+            match outcome {
+                StepOutcome::Advanced(next) => node = next,
+                StepOutcome::Halted(_) => break RunEnd::Halted,
+                StepOutcome::Pruned(PruneReason::Input) => break RunEnd::InputUnsupported,
+                StepOutcome::Pruned(reason) => return Err(reason.describe().into()),
+                StepOutcome::Expanded(_) => return Err("expanded a hole twice".into()),
+            }
+        };
+        Ok(Run {
+            output: node.overflow,
+            steps: node.steps,
+            end,
+        })
+    }
+}
+
+/// The subtree of `program` in the place of the hole at position `at` of
+/// `partial`, if `partial` is `program` with some subtrees left as holes.
+fn at_hole<'a>(partial: &ProgramNode, program: &'a ProgramNode, at: u32) -> Option<&'a ProgramNode> {
+    match (&partial.kind, &program.kind) {
+        (PKind::Hole, _) => (at == 0).then_some(program),
+        (PKind::Instr(a, p), PKind::Instr(b, q)) if a == b && at > 0 => at_hole(p, q, at - 1),
+        (PKind::Loop { body: p, next: p2 }, PKind::Loop { body: q, next: q2 }) if at > 0 => {
+            if at <= p.size {
+                at_hole(p, q, at - 1)
+            } else {
+                at_hole(p2, q2, at - 1 - p.size)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The index of the expansion that starts like `sub`, in the order of
+/// `StepOutcome::Expanded`.
+fn production(set: &InstrSet, sub: &ProgramNode) -> usize {
+    match &sub.kind {
+        PKind::Instr(i, _) => 1 + set.instrs.iter().position(|x| x == i).unwrap(),
+        PKind::Loop { .. } => 1 + set.instrs.len(),
+        PKind::Empty | PKind::Hole => 0,
+    }
+}
@@ -0,0 +1,133 @@
+//! Targets written out as decimal text: the numbers 1, 2, 10 as the bytes
+//! of "1 2 10", for programs that print a sequence the way a person reads
+//! it rather than as raw byte values.
+//!
+//! `encode` turns numbers into such a target, a separator byte between
+//! each two (none after the last). `decode` reads output back into
+//! numbers, and says where it stops looking like them.
+//!
+//! ```
+//! use bf_search::digits::{decode, encode, parse_separator};
+//!
+//! assert_eq!(encode(&[1, 2, 3, 4], b' '), b"1 2 3 4");
+//! assert_eq!(encode(&[0, 10, 255, 1000], b','), b"0,10,255,1000");
+//! assert_eq!(encode(&[1, 2], b'\n'), b"1\n2");
+//! assert_eq!(encode(&[7], b' '), b"7");
+//!
+//! let sep = |s| parse_separator(s).unwrap();
+//! assert_eq!([sep("space"), sep("comma"), sep("newline"), sep("tab")], *b" ,\n\t");
+//! assert_eq!([sep(" "), sep(";"), sep("\\n")], *b" ;\n");
+//! assert!(parse_separator("7").is_err() && parse_separator("ab").is_err());
+//!
+//! // round trip, however many digits
+//! for numbers in [vec![0], vec![9, 10, 99, 100], vec![12345, 0, 7]] {
+//!     for sep in *b" ,\n" {
+//!         let back = decode(&encode(&numbers, sep), sep);
+//!         assert_eq!((back.numbers, back.open, back.stray), (numbers.clone(), true, None));
+//!     }
+//! }
+//!
+//! // extrapolated output: cut off mid-number, or turning into something else
+//! let text = decode(b"1 2 3 4 5 6 1", b' ');
+//! assert_eq!((text.numbers, text.open, text.stray), (vec![1, 2, 3, 4, 5, 6, 1], true, None));
+//! let text = decode(b"1 2 3 4 ", b' ');
+//! assert_eq!((text.numbers, text.open, text.stray), (vec![1, 2, 3, 4], false, None));
+//! let text = decode(b"1,2,3,4\x05\x06", b',');
+//! assert_eq!((text.numbers, text.open, text.stray), (vec![1, 2, 3, 4], false, Some(7)));
+//! // the last target number running on: "1 2 3" printed as "1 2 34"
+//! assert_eq!(decode(b"1 2 34", b' ').numbers[..3], [1, 2, 34]);
+//! assert_eq!(decode(b"1 2  3", b' ').stray, Some(4)); // two separators
+//! assert_eq!(decode(b"1 2 3 4", b',').stray, Some(1)); // the wrong one
+//! assert_eq!(decode(b"8 9 010", b' ').stray, Some(5)); // "0" is a whole number
+//! assert_eq!(decode(b" 1", b' ').stray, Some(0));
+//! assert_eq!(decode(b"99999999999999999999", b' ').stray, Some(19)); // past u64
+//! ```
+
+/// The bytes of `numbers` in decimal, with `sep` between each two.
+pub fn encode(numbers: &[u64], sep: u8) -> Vec<u8> {
+    let mut text = Vec::new();
+    for (i, n) in numbers.iter().enumerate() {
+        if i > 0 {
+            text.push(sep);
+        }
+        text.extend_from_slice(n.to_string().as_bytes());
+    }
+    text
+}
+
+/// Output read back as numbers by `decode`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decoded {
+    pub numbers: Vec<u64>,
+    /// Whether the text ends inside the last number, which more output
+    /// could still extend.
+    pub open: bool,
+    /// Where the text stops being numbers and separators: the first byte
+    /// that fits neither. Everything before it is in `numbers`.
+    pub stray: Option<usize>,
+}
+
+/// Read `text` as decimal numbers separated by single `sep` bytes, up to
+/// the first byte that does not fit (see `Decoded::stray`). A number does
+/// not start with 0 unless it is 0, as `encode` writes them.
+pub fn decode(text: &[u8], sep: u8) -> Decoded {
+    let mut numbers = Vec::new();
+    let mut current: Option<u64> = None; // the number being read
+    for (i, &b) in text.iter().enumerate() {
+        let next = match (b, current) {
+            (b'0'..=b'9', Some(0)) => None,
+            (b'0'..=b'9', n) => {
+                let digit = (b - b'0') as u64;
+                n.unwrap_or(0).checked_mul(10).and_then(|n| n.checked_add(digit))
+            }
+            (_, Some(n)) if b == sep => {
+                numbers.push(n);
+                current = None;
+                continue;
+            }
+            _ => None,
+        };
+        match next {
+            Some(n) => current = Some(n),
+            None => {
+                numbers.extend(current);
+                return Decoded {
+                    numbers,
+                    open: false,
+                    stray: Some(i),
+                };
+            }
+        }
+    }
+    numbers.extend(current);
+    Decoded {
+        numbers,
+        open: current.is_some(),
+        stray: None,
+    }
+}
+
+/// A separator byte given by name (`space`, `comma`, `newline`, `tab`), as
+/// an escape (`\n`, `\t`), or as a single ASCII character other than a
+/// digit.
+pub fn parse_separator(s: &str) -> Result<u8, String> {
+    let sep = match s {
+        "space" => b' ',
+        "comma" => b',',
+        "newline" | "\\n" => b'\n',
+        "tab" | "\\t" => b'\t',
+        _ => match s.as_bytes() {
+            [b] if b.is_ascii() => *b,
+            _ => {
+                return Err(format!(
+                    "expected space, comma, newline, tab or one character, got '{}'",
+                    s
+                ))
+            }
+        },
+    };
+    if sep.is_ascii_digit() {
+        return Err(format!("a digit cannot separate numbers, got '{}'", s));
+    }
+    Ok(sep)
+}
@@ -0,0 +1,324 @@
+//! Translation of concrete programs into C and Rust source.
+//!
+//! `ops` collapses runs of `+`/`-` and of `>`/`<` into one addition or move
+//! each, and `emit` walks the result, asking a `Backend` for each line. The
+//! tape has `TAPE_LEN` cells and the pointer starts in the
+//! middle, so a program that moves left of its start (which the search's
+//! unbounded tape allows) runs the same as long as it stays within
+//! `TAPE_LEN / 2` cells of it. `,` ends the program, as in the interpreter,
+//! and holes count as `Empty`.
+//!
+//! The generated C prints what the interpreter does (see `to_rust` for
+//! the same check of the Rust backend):
+//!
+//! ```
+//! use bf_search::emit::to_c;
+//! use bf_search::interp::Interpreter;
+//! use bf_search::parse::parse_program;
+//! use std::process::Command;
+//!
+//! let dir = std::env::temp_dir().join(format!("bf_search-emit-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir).unwrap();
+//! for (i, src) in ["++++[>+++[>++<-]<-]>>.", "-.<<+.>>[-]++++[->++<]>.,+."].iter().enumerate() {
+//!     let program = parse_program(src).unwrap();
+//!     let mut interp = Interpreter::new(program.clone()).with_step_cap(10_000);
+//!     assert!(interp.run_to_limit(usize::MAX).unwrap().stopped());
+//!
+//!     let (c, exe) = (dir.join(format!("{}.c", i)), dir.join(format!("{}.out", i)));
+//!     std::fs::write(&c, to_c(&program)).unwrap();
+//!     let mut cc = Command::new("cc");
+//!     cc.args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-o"]).arg(&exe).arg(&c);
+//!     let Ok(built) = cc.status() else {
+//!         break; // no C compiler here
+//!     };
+//!     assert!(built.success());
+//!     assert_eq!(Command::new(&exe).output().unwrap().stdout, interp.output());
+//! }
+//! std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use crate::{Instr, PKind, ProgramNode, Shared};
+
+/// Cells on the generated programs' tape.
+pub const TAPE_LEN: usize = 30_000;
+
+/// One statement of a collapsed program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add(u8),   // to the current cell, wrapping; never 0
+    Move(i64), // the pointer; never 0
+    Output,
+    Halt, // `,`; always last in its block
+    Loop(Vec<Op>),
+}
+
+/// `program` with its runs collapsed and its dead code left out: runs
+/// that cancel out (letting the instructions on either side meet), what
+/// follows a `,` in its block, and the additions and moves right before a
+/// `,` or the end of the program, which nothing reads.
+///
+/// ```
+/// use bf_search::emit::{ops, Op};
+/// use bf_search::parse::parse_program;
+///
+/// let ops = |src| ops(&parse_program(src).unwrap());
+/// assert_eq!(ops("+++--->><<<.+><+[-]"), [Op::Move(-1), Op::Output, Op::Add(2), Op::Loop(vec![Op::Add(255)])]);
+/// assert_eq!(ops("+.[>+,.].>+"), [Op::Add(1), Op::Output, Op::Loop(vec![Op::Halt]), Op::Output]);
+/// assert_eq!(ops(">,+."), []);
+/// ```
+pub fn ops(program: &ProgramNode) -> Vec<Op> {
+    let mut ops = block(program);
+    while let Some(Op::Add(_) | Op::Move(_) | Op::Halt) = ops.last() {
+        ops.pop();
+    }
+    ops
+}
+
+/// `ops` for a block, up to the `Empty` ending it.
+fn block(program: &ProgramNode) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let mut cur = program;
+    // Walks the `next` chain in a loop, so only loop nesting recurses.
+    loop {
+        let op = match &cur.kind {
+            PKind::Hole | PKind::Empty => return ops,
+            PKind::Instr(i, next) => {
+                cur = next;
+                match i {
+                    Instr::Inc => Op::Add(1),
+                    Instr::Dec => Op::Add(255),
+                    Instr::IncPtr => Op::Move(1),
+                    Instr::DecPtr => Op::Move(-1),
+                    Instr::Output => Op::Output,
+                    Instr::Input => Op::Halt,
+                }
+            }
+            PKind::Loop { body, next } => {
+                cur = next;
+                Op::Loop(block(body))
+            }
+        };
+        if op == Op::Halt {
+            while let Some(Op::Add(_) | Op::Move(_)) = ops.last() {
+                ops.pop();
+            }
+            ops.push(op);
+            return ops;
+        }
+        push(&mut ops, op);
+    }
+}
+
+/// Append `op`, merging it into the run before it.
+fn push(ops: &mut Vec<Op>, op: Op) {
+    match (ops.last_mut(), &op) {
+        (Some(Op::Add(a)), Op::Add(b)) => *a = a.wrapping_add(*b),
+        (Some(Op::Move(a)), Op::Move(b)) => *a += b,
+        _ => return ops.push(op),
+    }
+    if let Some(Op::Add(0) | Op::Move(0)) = ops.last() {
+        ops.pop();
+    }
+}
+
+/// Whether `ops` or a loop in them has an op for which `f` holds.
+pub fn any_op(ops: &[Op], f: fn(&Op) -> bool) -> bool {
+    ops.iter().any(|op| f(op) || matches!(op, Op::Loop(body) if any_op(body, f)))
+}
+
+/// A language `emit` translates into. Statements come without indentation
+/// or line break; `emit` indents them by 4 spaces per loop level, starting
+/// at 1.
+pub trait Backend {
+    /// Everything before the first statement; `source` is the Brainfuck
+    /// text of the program and `ops` what `emit` goes on to translate.
+    fn header(&self, source: &str, ops: &[Op]) -> String;
+    /// Everything after the last statement.
+    fn footer(&self, ops: &[Op]) -> String;
+    /// Add `k` to the current cell, wrapping.
+    fn add(&self, k: u8) -> String;
+    /// Move the pointer `d` cells to the right (left if negative).
+    fn shift(&self, d: i64) -> String;
+    fn output(&self) -> String;
+    /// End the program.
+    fn halt(&self) -> String;
+    /// The line opening a loop that runs while the current cell is nonzero.
+    fn loop_open(&self) -> String;
+    fn loop_close(&self) -> String {
+        "}".to_string()
+    }
+}
+
+/// `program` translated by `backend`.
+pub fn emit(program: &Shared<ProgramNode>, backend: &impl Backend) -> String {
+    let ops = ops(program);
+    let mut out = backend.header(&ProgramNode::to_bf_string(program), &ops);
+    emit_block(&ops, 1, backend, &mut out);
+    out.push_str(&backend.footer(&ops));
+    out
+}
+
+fn emit_block(ops: &[Op], depth: usize, backend: &impl Backend, out: &mut String) {
+    let indent = "    ".repeat(depth);
+    for op in ops {
+        let line = match op {
+            Op::Add(k) => backend.add(*k),
+            Op::Move(d) => backend.shift(*d),
+            Op::Output => backend.output(),
+            Op::Halt => backend.halt(),
+            Op::Loop(body) => {
+                out.push_str(&format!("{}{}\n", indent, backend.loop_open()));
+                emit_block(body, depth + 1, backend, out);
+                backend.loop_close()
+            }
+        };
+        out.push_str(&format!("{}{}\n", indent, line));
+    }
+}
+
+/// C99: a `main` printing the output with `putchar`.
+pub struct C;
+
+impl Backend for C {
+    fn header(&self, source: &str, ops: &[Op]) -> String {
+        let mut c = format!("/* {} */\n#include <stdio.h>\n\n", source);
+        // Every op left reads the tape; with none, the tape and pointer
+        // would draw unused-variable warnings.
+        if !ops.is_empty() {
+            c.push_str(&format!("static unsigned char tape[{}];\n\n", TAPE_LEN));
+        }
+        c.push_str("int main(void)\n{\n");
+        if !ops.is_empty() {
+            c.push_str(&format!("    unsigned char *p = tape + {};\n", TAPE_LEN / 2));
+        }
+        c
+    }
+
+    fn footer(&self, _ops: &[Op]) -> String {
+        "    return 0;\n}\n".to_string()
+    }
+
+    fn add(&self, k: u8) -> String {
+        match k {
+            1..=128 => format!("*p += {};", k),
+            _ => format!("*p -= {};", k.wrapping_neg()),
+        }
+    }
+
+    fn shift(&self, d: i64) -> String {
+        match d {
+            1.. => format!("p += {};", d),
+            _ => format!("p -= {};", -d),
+        }
+    }
+
+    fn output(&self) -> String {
+        "putchar(*p);".to_string()
+    }
+
+    fn halt(&self) -> String {
+        "return 0;".to_string()
+    }
+
+    fn loop_open(&self) -> String {
+        "while (*p) {".to_string()
+    }
+}
+
+/// Rust: a standalone `fn generated() -> Vec<u8>` returning the output.
+/// Pointer moves past either end of the tape panic.
+pub struct Rust;
+
+impl Backend for Rust {
+    fn header(&self, source: &str, ops: &[Op]) -> String {
+        let mut r = format!("// {}\nfn generated() -> Vec<u8> {{\n", source);
+        // Declared as they are used, so that the function compiles
+        // without warnings.
+        let mutable = |f| if any_op(ops, f) { "mut " } else { "" };
+        if !ops.is_empty() {
+            let tape = mutable(|op| matches!(op, Op::Add(_)));
+            let p = mutable(|op| matches!(op, Op::Move(_)));
+            r.push_str(&format!("    let {}tape = vec![0u8; {}];\n", tape, TAPE_LEN));
+            r.push_str(&format!("    let {}p: usize = {};\n", p, TAPE_LEN / 2));
+        }
+        let out = mutable(|op| *op == Op::Output);
+        r.push_str(&format!("    let {}out = Vec::new();\n", out));
+        r
+    }
+
+    fn footer(&self, _ops: &[Op]) -> String {
+        "    out\n}\n".to_string()
+    }
+
+    fn add(&self, k: u8) -> String {
+        match k {
+            1..=128 => format!("tape[p] = tape[p].wrapping_add({});", k),
+            _ => format!("tape[p] = tape[p].wrapping_sub({});", k.wrapping_neg()),
+        }
+    }
+
+    fn shift(&self, d: i64) -> String {
+        match d {
+            1.. => format!("p += {};", d),
+            _ => format!("p -= {};", -d),
+        }
+    }
+
+    fn output(&self) -> String {
+        "out.push(tape[p]);".to_string()
+    }
+
+    fn halt(&self) -> String {
+        "return out;".to_string()
+    }
+
+    fn loop_open(&self) -> String {
+        "while tape[p] != 0 {".to_string()
+    }
+}
+
+/// A self-contained C99 program running `program`.
+///
+/// ```
+/// use bf_search::emit::to_c;
+/// use bf_search::parse::parse_program;
+///
+/// let c = to_c(&parse_program("--[>+<-]>.").unwrap());
+/// assert!(c.contains("    *p -= 2;\n    while (*p) {\n        p += 1;\n        *p += 1;\n"));
+/// ```
+pub fn to_c(program: &Shared<ProgramNode>) -> String {
+    emit(program, &C)
+}
+
+/// `fn generated() -> Vec<u8>`, returning what `program` prints. It
+/// compiles without warnings, and returns what the interpreter prints:
+///
+/// ```
+/// use bf_search::emit::to_rust;
+/// use bf_search::interp::Interpreter;
+/// use bf_search::parse::parse_program;
+/// use std::process::Command;
+///
+/// let dir = std::env::temp_dir().join(format!("bf_search-emit-rust-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// for (i, src) in ["++++[>+++[>++<-]<-]>>.", "-.<<+.>>[-]++++[->++<]>.,+.", ">>", "+[,]"].iter().enumerate() {
+///     let program = parse_program(src).unwrap();
+///     let mut interp = Interpreter::new(program.clone()).with_step_cap(10_000);
+///     assert!(interp.run_to_limit(usize::MAX).unwrap().stopped());
+///
+///     let main = "fn main() {\n    std::io::Write::write_all(&mut std::io::stdout(), &generated()).unwrap();\n}\n";
+///     let (rs, exe) = (dir.join(format!("{}.rs", i)), dir.join(format!("{}.out", i)));
+///     std::fs::write(&rs, to_rust(&program) + main).unwrap();
+///     let mut rustc = Command::new("rustc");
+///     rustc.args(["--edition", "2021", "-D", "warnings", "-o"]).arg(&exe).arg(&rs);
+///     let Ok(built) = rustc.status() else {
+///         break; // no rustc on the PATH
+///     };
+///     assert!(built.success());
+///     assert_eq!(Command::new(&exe).output().unwrap().stdout, interp.output());
+/// }
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn to_rust(program: &Shared<ProgramNode>) -> String {
+    emit(program, &Rust)
+}
@@ -0,0 +1,145 @@
+//! Which instruction printed each output byte: the column of the `.` in the
+//! program's source, and how many times each loop around it had gone
+//! round by then, to see how a solution builds its output.
+//!
+//! `explain` re-runs a concrete program with that bookkeeping; the
+//! `Explanation` prints as an annotated listing, the program with a `^`
+//! under each `.` that printed something, a column ruler, and one line
+//! per byte. Loop passes count from 1, outermost loop first.
+//!
+//! ```
+//! use bf_search::explain::explain;
+//! use bf_search::parse::parse_program;
+//! use bf_search::Tape;
+//!
+//! // two nested loops, each going round twice
+//! let program = parse_program("++[>++[>+.<-]<-]").unwrap();
+//! let explanation = explain(&program, Tape::default(), 1000, 4).unwrap();
+//! assert_eq!(
+//!     explanation.to_string(),
+//!     "++[>++[>+.<-]<-]
+//!          ^
+//! 0    5    10   15
+//! byte 0 (value 1) ← '.' at column 9, loop iterations [1, 1]
+//! byte 1 (value 2) ← '.' at column 9, loop iterations [1, 2]
+//! byte 2 (value 3) ← '.' at column 9, loop iterations [2, 1]
+//! byte 3 (value 4) ← '.' at column 9, loop iterations [2, 2]
+//! "
+//! );
+//!
+//! // two loops one after the other, and a `.` outside both
+//! let program = parse_program("+[.-]++[.-].").unwrap();
+//! let explanation = explain(&program, Tape::default(), 1000, 10).unwrap();
+//! let lines: Vec<String> = explanation.to_string().lines().map(String::from).collect();
+//! assert_eq!(lines[1], "  ^     ^  ^");
+//! assert_eq!(lines[3..], [
+//!     "byte 0 (value 1) ← '.' at column 2, loop iterations [1]",
+//!     "byte 1 (value 2) ← '.' at column 8, loop iterations [1]",
+//!     "byte 2 (value 1) ← '.' at column 8, loop iterations [2]",
+//!     "byte 3 (value 0) ← '.' at column 11, outside any loop",
+//! ]);
+//! assert!(explanation.halted);
+//!
+//! // a step cap ends the run early, and with it the listing
+//! let program = parse_program("+[.]").unwrap();
+//! let explanation = explain(&program, Tape::default(), 8, 100).unwrap();
+//! assert_eq!(explanation.bytes.len(), 3);
+//! assert_eq!(explanation.bytes[2].iterations, [3]);
+//! assert!(!explanation.halted);
+//! ```
+
+use crate::interp::{Interpreter, StepEvent};
+use crate::{ProgramNode, SearchError, Shared, Tape};
+use std::fmt;
+
+/// One output byte and where it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Emission {
+    pub value: u8,
+    pub column: usize, // of the `.` in the program's source
+    /// The pass each enclosing loop was on, outermost first, from 1.
+    pub iterations: Vec<u64>,
+}
+
+/// A program and the origin of each byte it printed, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub code: String,
+    pub bytes: Vec<Emission>,
+    pub halted: bool, // stopped on its own, rather than at the limit or the cap
+}
+
+/// Run `program` on `tape` for its first `limit` output bytes, or until it
+/// stops or has taken `step_cap` steps, noting where each byte came from.
+pub fn explain(
+    program: &Shared<ProgramNode>,
+    tape: Tape,
+    step_cap: u64,
+    limit: usize,
+) -> Result<Explanation, SearchError> {
+    let columns = program.columns();
+    let mut interp = Interpreter::new(program.clone()).with_tape(tape).with_step_cap(step_cap);
+    let mut passes: Vec<u64> = Vec::new(); // of the loops the pc is in
+    let mut bytes = Vec::new();
+    let mut halted = false;
+    while bytes.len() < limit {
+        let column = columns.get(interp.pc_at() as usize).copied().unwrap_or(0);
+        let nonzero = interp.cell(interp.dp()) != 0;
+        match interp.step()? {
+            StepEvent::Stepped { instr: '[' } if nonzero => passes.push(1),
+            StepEvent::Stepped { instr: ']' } if nonzero => *passes.last_mut().unwrap() += 1,
+            StepEvent::Stepped { instr: ']' } => {
+                passes.pop();
+            }
+            StepEvent::Stepped { .. } => {}
+            StepEvent::Output(value) => bytes.push(Emission {
+                value,
+                column,
+                iterations: passes.clone(),
+            }),
+            StepEvent::Halted | StepEvent::InputUnsupported => {
+                halted = true;
+                break;
+            }
+            StepEvent::StepCapReached => break,
+        }
+    }
+    Ok(Explanation {
+        code: program.to_string(),
+        bytes,
+        halted,
+    })
+}
+
+/// The annotated listing.
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.code)?;
+        let mut markers = vec![b' '; self.code.len()];
+        for byte in &self.bytes {
+            markers[byte.column] = b'^';
+        }
+        let markers = String::from_utf8(markers).unwrap();
+        writeln!(f, "{}", markers.trim_end())?;
+        // a column number every 5 columns, where the previous one leaves room
+        let mut ruler = String::new();
+        for col in (0..self.code.len()).step_by(5) {
+            if ruler.len() <= col {
+                ruler.push_str(&format!("{:1$}{2}", "", col - ruler.len(), col));
+            }
+        }
+        writeln!(f, "{}", ruler)?;
+        for (i, byte) in self.bytes.iter().enumerate() {
+            let loops = match &byte.iterations[..] {
+                [] => "outside any loop".to_string(),
+                passes => format!("loop iterations {:?}", passes),
+            };
+            writeln!(
+                f,
+                "byte {} (value {}) ← '.' at column {}, {}",
+                i, byte.value, byte.column, loops
+            )?;
+        }
+        Ok(())
+    }
+}
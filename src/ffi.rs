@@ -0,0 +1,155 @@
+//! C interface (feature `ffi`), declared in `include/bf_search.h`. Only
+//! plain C types cross the boundary; every function tolerates NULL.
+
+use crate::{InstrSet, SearchConfig, Searcher, Solution, StopReason};
+use std::ffi::{c_char, c_int, CStr};
+use std::ptr;
+
+pub const BF_SEARCH_OK: c_int = 0;
+pub const BF_SEARCH_EXHAUSTED: c_int = 1;
+pub const BF_SEARCH_BUDGET: c_int = 2;
+pub const BF_SEARCH_ERR_NULL: c_int = -1;
+pub const BF_SEARCH_ERR_BUFFER: c_int = -2;
+pub const BF_SEARCH_ERR_INTERNAL: c_int = -3;
+
+#[repr(C)]
+pub struct BfSearchConfig {
+    pub beta: f64,
+    pub gamma: f64,
+    pub max_steps: u64,
+    pub instr_set: *const c_char, // NUL-terminated, or NULL for all commands
+    pub golf: c_int,
+    pub max_expansions: u64, // 0 = unlimited
+}
+
+#[repr(C)]
+pub struct BfSolutionInfo {
+    pub code_len: usize, // without the NUL
+    pub min_len: u32,
+    pub steps: u64,
+    pub score: f64,
+}
+
+/// Opaque to C.
+pub struct BfSearch {
+    searcher: Searcher,
+    pending: Option<Solution>, // found, but the caller's buffer was too small
+}
+
+/// # Safety
+/// `cfg` is NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn bf_search_config_default(cfg: *mut BfSearchConfig) {
+    if cfg.is_null() {
+        return;
+    }
+    let d = SearchConfig::new(vec![0]).build().unwrap();
+    cfg.write(BfSearchConfig {
+        beta: d.beta(),
+        gamma: d.gamma(),
+        max_steps: d.max_steps(),
+        instr_set: ptr::null(),
+        golf: 0,
+        max_expansions: 0,
+    });
+}
+
+/// # Safety
+/// `target` must point to `len` bytes; `cfg` is NULL (defaults) or a valid
+/// config whose `instr_set` is NULL or NUL-terminated. Returns NULL for
+/// invalid arguments.
+#[no_mangle]
+pub unsafe extern "C" fn bf_search_new(
+    target: *const u8,
+    len: usize,
+    cfg: *const BfSearchConfig,
+) -> *mut BfSearch {
+    if target.is_null() {
+        return ptr::null_mut();
+    }
+    let target = std::slice::from_raw_parts(target, len).to_vec();
+    let mut builder = SearchConfig::new(target);
+    if let Some(cfg) = cfg.as_ref() {
+        builder = builder
+            .beta(cfg.beta)
+            .gamma(cfg.gamma)
+            .max_steps(cfg.max_steps)
+            .golf(cfg.golf != 0)
+            .max_expansions((cfg.max_expansions > 0).then_some(cfg.max_expansions));
+        if !cfg.instr_set.is_null() {
+            let set = CStr::from_ptr(cfg.instr_set).to_str().ok();
+            match set.map(InstrSet::parse) {
+                Some(Ok(set)) => builder = builder.instr_set(set),
+                _ => return ptr::null_mut(),
+            }
+        }
+    }
+    match builder.build() {
+        Ok(config) => Box::into_raw(Box::new(BfSearch {
+            searcher: Searcher::new(config),
+            pending: None,
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Find the next solution and copy its code, NUL-terminated, into `buf`.
+/// If `buflen` is too small, returns `BF_SEARCH_ERR_BUFFER` with
+/// `out->code_len` set; the next call returns the same solution.
+///
+/// # Safety
+/// `handle` comes from `bf_search_new`; `buf` has room for `buflen` bytes;
+/// `out` is NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn bf_search_next_solution(
+    handle: *mut BfSearch,
+    buf: *mut c_char,
+    buflen: usize,
+    out: *mut BfSolutionInfo,
+) -> c_int {
+    let Some(search) = handle.as_mut() else {
+        return BF_SEARCH_ERR_NULL;
+    };
+    let solution = match search.pending.take() {
+        Some(s) => s,
+        None => match search.searcher.next_solution() {
+            Some(s) => s,
+            None => {
+                return match search.searcher.stop_reason() {
+                    Some(StopReason::Budget) => BF_SEARCH_BUDGET,
+                    Some(StopReason::Aborted(_)) => BF_SEARCH_ERR_INTERNAL,
+                    _ => BF_SEARCH_EXHAUSTED,
+                }
+            }
+        },
+    };
+    if let Some(out) = out.as_mut() {
+        *out = BfSolutionInfo {
+            code_len: solution.code.len(),
+            min_len: solution.min_len,
+            steps: solution.steps,
+            score: solution.score,
+        };
+    }
+    if buf.is_null() || buflen <= solution.code.len() {
+        search.pending = Some(solution);
+        return if buf.is_null() {
+            BF_SEARCH_ERR_NULL
+        } else {
+            BF_SEARCH_ERR_BUFFER
+        };
+    }
+    let code = solution.code.as_bytes();
+    ptr::copy_nonoverlapping(code.as_ptr(), buf.cast::<u8>(), code.len());
+    *buf.add(code.len()) = 0;
+    BF_SEARCH_OK
+}
+
+/// # Safety
+/// `handle` comes from `bf_search_new` (or is NULL) and is not used again.
+#[no_mangle]
+pub unsafe extern "C" fn bf_search_free(handle: *mut BfSearch) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
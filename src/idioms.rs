@@ -0,0 +1,57 @@
+//! Idioms: partial programs that seed the frontier next to the empty one
+//! (see `SearchConfigBuilder::idioms`), so that shapes people write all the
+//! time need not be rediscovered a command at a time on every run.
+//!
+//! A template is written like any partial program, with `?` holes (see
+//! `parse`). An idiom file holds one per line; `#` starts a comment, and
+//! blank lines are skipped:
+//!
+//! ```
+//! use bf_search::idioms::{builtin, parse_idioms};
+//!
+//! let idioms = parse_idioms("# copy cell 0 to cell 1\n[->+<]?\n\n++++[->?]?  # 4 times\n").unwrap();
+//! let codes: Vec<String> = idioms.iter().map(|p| p.to_string()).collect();
+//! assert_eq!(codes, ["[->+<]?", "++++[->?]?"]);
+//! assert_eq!(parse_idioms("+?\n+?-\n").unwrap_err(), "line 2: '?' at byte 1 is not the last command of its block");
+//! assert!(builtin().iter().any(|p| p.to_string() == "[-]?"));
+//! ```
+
+use crate::Program;
+
+/// The built-in templates: clearing, moving and copying cells (of use on a
+/// preloaded tape), and counted loops adding to the next cell, the
+/// scaffold of a multiplication.
+pub const BUILTIN: &[&str] = &[
+    "[-]?",
+    "[->+<]?",
+    "[>+<-]?",
+    "[->+>+<<]?",
+    "[->++<]?",
+    "[->+++<]?",
+    "++[->?]?",
+    "+++[->?]?",
+    "++++[->?]?",
+    "+++++[->?]?",
+    "++++++[->?]?",
+    "+++++++[->?]?",
+    "++++++++[->?]?",
+    "++++++++++[->?]?",
+];
+
+/// `BUILTIN`, parsed.
+pub fn builtin() -> Vec<Program> {
+    BUILTIN.iter().map(|src| src.parse().expect("built-in idioms parse")).collect()
+}
+
+/// The templates of an idiom file, in order.
+pub fn parse_idioms(text: &str) -> Result<Vec<Program>, String> {
+    let mut idioms = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        idioms.push(line.parse().map_err(|e| format!("line {}: {}", i + 1, e))?);
+    }
+    Ok(idioms)
+}
@@ -0,0 +1,314 @@
+//! Stateful interpreter for concrete programs, one instruction at a time.
+//!
+//! Wraps the search's `SearchNode`/`exec_known_step` machinery, so it runs
+//! exactly the semantics the search assumes: an unbounded tape of wrapping
+//! bytes (blank unless given with `with_tape`), and `,` unsupported
+//! (execution stops there).
+
+use crate::parse::parse_program;
+use crate::{
+    child_at, node_at, step_in_place, unentered_len, InPlace, Instr, LoopFrame, LoopStack, PKind,
+    ProgramNode, SearchError, SearchNode, Shared, Tape,
+};
+use serde::{Deserialize, Serialize};
+
+/// What one call to `Interpreter::step` did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepEvent {
+    Stepped { instr: char }, // executed anything but `.`; `[` and `]` included
+    Output(u8),
+    Halted,           // at the end of the program; nothing executed
+    InputUnsupported, // at a `,`; nothing executed
+    StepCapReached,
+}
+
+/// Why `Interpreter::run_to_limit` returned.
+///
+/// ```
+/// use bf_search::interp::{Interpreter, RunEnd};
+///
+/// let run = |src: &str| Interpreter::from_source(src).unwrap().with_step_cap(100).run_to_limit(2);
+/// assert_eq!(run("+.+."), Ok(RunEnd::OutputLimit));
+/// assert_eq!(run("+."), Ok(RunEnd::Halted));
+/// assert_eq!(run("+.,."), Ok(RunEnd::InputUnsupported));
+/// assert_eq!(run("+[]"), Ok(RunEnd::StepCap));
+/// assert!(RunEnd::Halted.stopped() && !RunEnd::StepCap.stopped());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunEnd {
+    Halted,           // at the end of the program
+    InputUnsupported, // at a `,`
+    OutputLimit,      // printed the requested bytes; may still be running
+    StepCap,          // ran out of steps before either
+}
+
+impl RunEnd {
+    /// The program stopped on its own.
+    pub fn stopped(self) -> bool {
+        matches!(self, RunEnd::Halted | RunEnd::InputUnsupported)
+    }
+}
+
+/// A concrete program being executed.
+///
+/// ```
+/// use bf_search::interp::{Interpreter, RunEnd, StepEvent};
+///
+/// let mut it = Interpreter::from_source("++[>++<-]>.").unwrap();
+/// for _ in 0..3 {
+///     it.step().unwrap(); // + + [
+/// }
+/// assert_eq!((it.dp(), it.cell(0)), (0, 2));
+/// assert_eq!(it.step(), Ok(StepEvent::Stepped { instr: '>' }));
+/// assert_eq!(it.run_to_limit(1), Ok(RunEnd::OutputLimit));
+/// assert_eq!(it.output(), &[4]);
+/// assert_eq!(it.step(), Ok(StepEvent::Halted));
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Interpreter {
+    node: SearchNode,
+    step_cap: Option<u64>,
+}
+
+impl Interpreter {
+    pub fn new(program: Shared<ProgramNode>) -> Interpreter {
+        Interpreter {
+            node: SearchNode::concrete(program),
+            step_cap: None,
+        }
+    }
+
+    pub fn from_source(src: &str) -> Result<Interpreter, String> {
+        parse_program(src).map(Interpreter::new)
+    }
+
+    /// Carry a search node's run over to the program it would be reported
+    /// as, `state.root.concretize_solution()`, so that it continues where
+    /// the search left it instead of starting over. The output so far is
+    /// `state.output(target)`.
+    ///
+    /// Holes act as `Empty`s and the loops left out of the program are
+    /// passed over, as in a fresh run; the step count still includes the
+    /// `[` the search executed for each of those. `None` when the search
+    /// is inside one of them (it has just entered it), which a fresh run
+    /// never is.
+    ///
+    /// ```
+    /// use bf_search::interp::{Interpreter, RunEnd};
+    /// use bf_search::{exec_known_step, Program, SearchNode};
+    ///
+    /// // the run of a partial program once it has printed `target`
+    /// let at_target = |src: &str, target: &[u8]| {
+    ///     let p: Program = src.parse().unwrap();
+    ///     let mut node = SearchNode::concrete(p.root);
+    ///     while node.correct < target.len() {
+    ///         node = exec_known_step(node, target).unwrap().advanced().unwrap();
+    ///     }
+    ///     node
+    /// };
+    ///
+    /// // ~130k steps to print the first byte
+    /// let slow = at_target("-[>-[-]<-]>+.+.?", &[1]);
+    /// let mut fresh = Interpreter::new(slow.root.concretize_solution()).with_step_cap(10_000);
+    /// assert_eq!(fresh.run_to_limit(2), Ok(RunEnd::StepCap));
+    /// let mut resumed = Interpreter::resume(&slow, &[1]).unwrap();
+    /// resumed = resumed.with_step_cap(slow.steps + 10_000);
+    /// assert_eq!(resumed.run_to_limit(2), Ok(RunEnd::OutputLimit));
+    /// assert_eq!(resumed.output(), &[1, 2]);
+    ///
+    /// // the same output and end as a fresh run, holes and all
+    /// for (src, target) in [("++[.-?]?", &[2][..]), ("+.[?]+.[?]", &[1]), ("+[.>?]+.?", &[1])] {
+    ///     let node = at_target(src, target);
+    ///     let mut fresh = Interpreter::new(node.root.concretize_solution()).with_step_cap(1000);
+    ///     let mut resumed = Interpreter::resume(&node, target).unwrap().with_step_cap(1000);
+    ///     assert_eq!(resumed.run_to_limit(10), fresh.run_to_limit(10));
+    ///     assert_eq!(resumed.output(), fresh.output());
+    /// }
+    ///
+    /// // just inside a loop the reported program leaves out
+    /// let inside = at_target("+.[?]", &[1]);
+    /// let inside = exec_known_step(inside, &[1]).unwrap().advanced().unwrap();
+    /// assert!(Interpreter::resume(&inside, &[1]).is_none());
+    /// ```
+    pub fn resume(state: &SearchNode, target: &[u8]) -> Option<Interpreter> {
+        let program = state.root.concretize_solution();
+        let mut frames = Vec::new();
+        for frame in state.loop_stack.iter() {
+            if matches!(frame.body.kind, PKind::Hole) {
+                return None;
+            }
+            let at = solution_at(&state.root, frame.at)?;
+            let PKind::Loop { body, next } = &node_at(&program, at)?.kind else {
+                return None;
+            };
+            frames.push(LoopFrame {
+                at,
+                body: body.clone(),
+                next: next.clone(),
+            });
+        }
+        let pc_at = solution_at(&state.root, state.pc_at)?;
+        let node = SearchNode {
+            pc: node_at(&program, pc_at)?,
+            pc_at,
+            root: program,
+            loop_stack: frames.into_iter().rev().collect::<LoopStack>(),
+            dp: state.dp,
+            tape: state.tape.clone(),
+            steps: state.steps,
+            last_output: state.last_output,
+            correct: 0,
+            overflow: state.output(target),
+            dead_len: 0,
+            case_correct: 0,
+        };
+        Some(Interpreter { node, step_cap: None })
+    }
+
+    /// Run on `tape` rather than on blank cells; for an interpreter that
+    /// has not started.
+    ///
+    /// ```
+    /// use bf_search::interp::Interpreter;
+    ///
+    /// let mut it = Interpreter::from_source(".>.+.").unwrap().with_tape("3 9".parse().unwrap());
+    /// it.run_to_limit(3).unwrap();
+    /// assert_eq!(it.output(), &[3, 9, 10]);
+    /// ```
+    pub fn with_tape(mut self, tape: Tape) -> Interpreter {
+        self.node.tape = tape;
+        self
+    }
+
+    /// Stop with `StepEvent::StepCapReached` once `cap` steps have run.
+    pub fn with_step_cap(mut self, cap: u64) -> Interpreter {
+        self.step_cap = Some(cap);
+        self
+    }
+
+    /// The instruction the next `step` executes (`[`/`]` for loop moves),
+    /// or `None` once the program has ended.
+    pub fn next_instr(&self) -> Option<char> {
+        match &self.node.pc.kind {
+            PKind::Instr(i, _) => Some(i.to_char()),
+            PKind::Loop { .. } => Some('['),
+            PKind::Empty if !self.node.loop_stack.is_empty() => Some(']'),
+            PKind::Empty | PKind::Hole => None,
+        }
+    }
+
+    /// The position of the node the next `step` executes; its place in the
+    /// source is `ProgramNode::columns`.
+    pub fn pc_at(&self) -> u32 {
+        self.node.pc_at
+    }
+
+    /// Execute one instruction, stepping the interpreter's own state. Never
+    /// fails on a concrete program.
+    pub fn step(&mut self) -> Result<StepEvent, SearchError> {
+        if self.step_cap.is_some_and(|cap| self.node.steps >= cap) {
+            return Ok(StepEvent::StepCapReached);
+        }
+        let Some(instr) = self.next_instr() else {
+            return Ok(StepEvent::Halted);
+        };
+        if let PKind::Instr(Instr::Input, _) = self.node.pc.kind {
+            return Ok(StepEvent::InputUnsupported);
+        }
+        // In place: a clone per step would copy the whole output.
+        match step_in_place(&mut self.node, &[]) {
+            InPlace::Advanced => {}
+            // Not against an empty target with `,` caught above.
+            InPlace::Halted | InPlace::Pruned(_) => return Ok(StepEvent::Halted),
+        }
+        Ok(match instr {
+            '.' => StepEvent::Output(*self.node.overflow.last().unwrap()),
+            _ => StepEvent::Stepped { instr },
+        })
+    }
+
+    /// Step until `limit` output bytes exist, the program stops, or the step
+    /// cap is reached, and say which.
+    pub fn run_to_limit(&mut self, limit: usize) -> Result<RunEnd, SearchError> {
+        while self.node.overflow.len() < limit {
+            match self.step()? {
+                StepEvent::Halted => return Ok(RunEnd::Halted),
+                StepEvent::InputUnsupported => return Ok(RunEnd::InputUnsupported),
+                StepEvent::StepCapReached => return Ok(RunEnd::StepCap),
+                StepEvent::Stepped { .. } | StepEvent::Output(_) => {}
+            }
+        }
+        Ok(RunEnd::OutputLimit)
+    }
+
+    /// Step until the program stops or the step cap is reached. The output
+    /// limit is what the steps left could print, one byte each, so with a
+    /// cap it never cuts the run short; without one the run may not end.
+    ///
+    /// ```
+    /// use bf_search::interp::{Interpreter, RunEnd};
+    ///
+    /// let run = |src: &str| Interpreter::from_source(src).unwrap().with_step_cap(100).run_to_halt();
+    /// assert_eq!(run("+.+."), Ok(RunEnd::Halted));
+    /// assert_eq!(run("+[.]"), Ok(RunEnd::StepCap));
+    /// ```
+    pub fn run_to_halt(&mut self) -> Result<RunEnd, SearchError> {
+        let limit = match self.step_cap {
+            Some(cap) => {
+                let left = usize::try_from(cap.saturating_sub(self.node.steps)).unwrap_or(usize::MAX);
+                self.node.overflow.len().saturating_add(left).saturating_add(1)
+            }
+            None => usize::MAX,
+        };
+        self.run_to_limit(limit)
+    }
+
+    pub fn dp(&self) -> i64 {
+        self.node.dp
+    }
+
+    pub fn cell(&self, i: i64) -> u8 {
+        self.node.get_cell(i)
+    }
+
+    /// Indices of the cells that may be nonzero, in no particular order.
+    pub fn touched_cells(&self) -> impl Iterator<Item = i64> + '_ {
+        self.node.tape.nonzero().map(|(i, _)| i)
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.node.steps
+    }
+
+    pub fn output(&self) -> &[u8] {
+        // run against an empty target, so all of it is overflow
+        &self.node.overflow
+    }
+
+    pub fn into_output(self) -> Vec<u8> {
+        self.node.overflow
+    }
+}
+
+/// Where the node at position `at` of a search tree is in its
+/// `concretize_solution()`: holes turn into `Empty`s in place, and a loop
+/// whose body is a hole is left out, its next standing for it. `None` for
+/// such a body, which has no place there.
+fn solution_at(root: &ProgramNode, mut at: u32) -> Option<u32> {
+    let (mut node, mut out) = (root, 0);
+    while at > 0 {
+        let (child, into_body, rest) = child_at(node, at)?;
+        out += match &node.kind {
+            PKind::Loop { body, .. } if matches!(body.kind, PKind::Hole) => {
+                if into_body {
+                    return None;
+                }
+                0
+            }
+            PKind::Loop { body, .. } if !into_body => 1 + body.size - unentered_len(body),
+            _ => 1,
+        };
+        (node, at) = (child, rest);
+    }
+    Some(out)
+}
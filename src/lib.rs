@@ -0,0 +1,1307 @@
+//! Brainfuck program search via lazy partial-program expansion.
+//!
+//! A partial program is a `ProgramNode` tree whose `Hole`s stand for any
+//! subprogram. `step_once` runs the interpreter one step on a `SearchNode`,
+//! expanding the hole under the pc into every production when it needs the
+//! next instruction; `Searcher` drives those steps best-first by score and
+//! yields the programs that reproduce a target byte sequence.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod archive;
+pub mod arena;
+pub mod brute;
+pub mod census;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dashboard;
+pub mod differential;
+pub mod digits;
+pub mod emit;
+pub mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod idioms;
+pub mod intern;
+pub mod interp;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod monitor;
+pub mod near;
+pub mod pareto;
+pub mod parse;
+pub mod portfolio;
+#[cfg(feature = "python")]
+mod python;
+pub mod record;
+pub mod search;
+pub mod serve;
+pub mod simplify;
+pub mod snapshot;
+pub mod tape;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use search::{
+    Callbacks, Case, ConfigError, Dedup, Expansion, Frontier, PruneCounts, PruneReason, SearchConfig, SearchConfigBuilder, SearchStats,
+    Searcher, Solution, StopReason,
+};
+pub use tape::Tape;
+
+use intern::Interner;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// Pointer shared between program trees: `Rc` by default, `Arc` with the
+/// `sync` feature, which makes the search state `Send` at some cost in
+/// single-threaded speed.
+#[cfg(not(feature = "sync"))]
+pub type Shared<T> = std::rc::Rc<T>;
+#[cfg(feature = "sync")]
+pub type Shared<T> = std::sync::Arc<T>;
+
+#[cfg(feature = "sync")]
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<ProgramNode>();
+    assert_send::<SearchNode>();
+    assert_send::<Searcher>();
+    assert_send::<Solution>();
+    assert_send::<Program>();
+    assert_send::<interp::Interpreter>();
+};
+
+/// A broken invariant of a partial program or search state. The search
+/// skips the node it occurred in; see `Searcher::run`.
+///
+/// ```
+/// use bf_search::{node_at, step_once, AdvancePolicy, InstrSet, LoopFrame, Program, SearchError, SearchNode};
+///
+/// let set = InstrSet::parse("+.[]").unwrap();
+/// let p: Program = "+[?]?".parse().unwrap(); // '+' 0, '[' 1, its body 2, the hole after it 3
+/// let at = |pc_at| SearchNode { pc_at, pc: node_at(&p.root, pc_at).unwrap(), ..SearchNode::concrete(p.root.clone()) };
+/// let step = |node: &SearchNode| step_once(node, &[1], &set, AdvancePolicy::Search).err();
+///
+/// assert_eq!(step(&at(3)), None);
+/// // a hole the pc is not on
+/// assert_eq!(step(&SearchNode { pc_at: 0, ..at(3) }), Some(SearchError::HoleNotFound(0)));
+/// // a frame for a loop the pc is not inside
+/// let mut node = at(3);
+/// let (body, next) = (node_at(&p.root, 2).unwrap(), node_at(&p.root, 3).unwrap());
+/// node.loop_stack.push(LoopFrame { at: 1, body, next });
+/// assert_eq!(step(&node), Some(SearchError::DanglingFrame(1)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchError {
+    HoleNotFound(u32),  // `replace_hole` target is not a hole of the tree
+    DanglingFrame(u32), // a loop frame's loop is not around the pc
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchError::HoleNotFound(at) => write!(f, "no hole at node #{} of the program", at),
+            SearchError::DanglingFrame(at) => {
+                write!(f, "loop #{} on the loop stack is not around the pc", at)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instr {
+    IncPtr,
+    DecPtr,
+    Inc,
+    Dec,
+    Output,
+    Input,
+}
+
+impl Instr {
+    pub fn all() -> &'static [Instr] {
+        &[
+            Instr::IncPtr,
+            Instr::DecPtr,
+            Instr::Inc,
+            Instr::Dec,
+            Instr::Output,
+            Instr::Input,
+        ]
+    }
+
+    pub fn from_char(c: char) -> Option<Instr> {
+        Instr::all().iter().copied().find(|i| i.to_char() == c)
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Instr::IncPtr => '>',
+            Instr::DecPtr => '<',
+            Instr::Inc => '+',
+            Instr::Dec => '-',
+            Instr::Output => '.',
+            Instr::Input => ',',
+        }
+    }
+}
+
+/// The productions hole expansion may use: a subset of `I`, plus `[P];P`
+/// if loops are allowed.
+#[derive(Clone, Debug)]
+pub struct InstrSet {
+    pub instrs: Vec<Instr>, // in Instr::all() order, for deterministic expansion
+    pub loops: bool,
+}
+
+impl InstrSet {
+    pub fn parse(s: &str) -> Result<InstrSet, String> {
+        let mut allowed = Vec::new();
+        let mut loops = false;
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            match c {
+                '[' | ']' => loops = true,
+                _ => match Instr::from_char(c) {
+                    Some(i) => allowed.push(i),
+                    None => return Err(format!("'{}' is not a Brainfuck command", c)),
+                },
+            }
+        }
+        let instrs: Vec<Instr> = Instr::all()
+            .iter()
+            .copied()
+            .filter(|i| allowed.contains(i))
+            .collect();
+        if instrs.is_empty() && !loops {
+            return Err("instruction set must not be empty".into());
+        }
+        Ok(InstrSet { instrs, loops })
+    }
+
+    pub fn contains(&self, i: Instr) -> bool {
+        self.instrs.contains(&i)
+    }
+
+    pub fn to_bf_string(&self) -> String {
+        let mut s: String = self.instrs.iter().map(|i| i.to_char()).collect();
+        if self.loops {
+            s.push_str("[]");
+        }
+        s
+    }
+
+    /// Why no program over this set, run on `tape`, can produce `target`,
+    /// if that is evident without searching.
+    ///
+    /// ```
+    /// use bf_search::{InstrSet, Tape};
+    ///
+    /// let set = InstrSet::parse("><.").unwrap();
+    /// assert!(set.unsolvable_reason(&[0, 3], &Tape::new()).is_some());
+    /// assert!(set.unsolvable_reason(&[0, 3], &"0 3".parse().unwrap()).is_none());
+    /// ```
+    pub fn unsolvable_reason(&self, target: &[u8], tape: &Tape) -> Option<String> {
+        if !self.contains(Instr::Output) {
+            return Some("without '.' nothing can be output".into());
+        }
+        if !self.contains(Instr::Inc) && !self.contains(Instr::Dec) {
+            let held = |b: u8| b == 0 || tape.nonzero().any(|(_, v)| v == b);
+            if let Some(b) = target.iter().find(|&&b| !held(b)) {
+                let cells = if tape.is_blank() { "every cell stays 0" } else { "no cell changes" };
+                return Some(format!(
+                    "without '+' or '-' {}, but the target contains {}",
+                    cells, b
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// A node of a partial program. Nodes carry nothing but their structure,
+/// so one node can stand at several places, in one tree or across many (see
+/// `intern`); a place in a tree is the node's position in preorder, a
+/// loop's body before its next (see `node_at`).
+#[derive(Clone)]
+pub struct ProgramNode {
+    pub kind: PKind,
+    pub min_len: u32, // minimal possible length of any instantiation of this P
+    pub holes: u32,   // number of holes in this P
+    pub size: u32,    // nodes in this P, itself included
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum PKind {
+    Hole,
+    Empty,
+    Instr(Instr, Shared<ProgramNode>), // I;P
+    Loop {
+        body: Shared<ProgramNode>, // [P];P
+        next: Shared<ProgramNode>,
+    },
+}
+
+impl ProgramNode {
+    pub fn hole() -> Shared<ProgramNode> {
+        Shared::new(ProgramNode::with_kind(PKind::Hole))
+    }
+    pub fn empty() -> Shared<ProgramNode> {
+        Shared::new(ProgramNode::with_kind(PKind::Empty))
+    }
+    pub fn instr(i: Instr, next: Shared<ProgramNode>) -> Shared<ProgramNode> {
+        Shared::new(ProgramNode::with_kind(PKind::Instr(i, next)))
+    }
+    pub fn looped(body: Shared<ProgramNode>, next: Shared<ProgramNode>) -> Shared<ProgramNode> {
+        Shared::new(ProgramNode::with_kind(PKind::Loop { body, next }))
+    }
+
+    /// The node of `kind`, with the counts taken from its children.
+    pub(crate) fn with_kind(kind: PKind) -> ProgramNode {
+        let (min_len, holes, size) = match &kind {
+            PKind::Hole => (0, 1, 1),
+            PKind::Empty => (0, 0, 1),
+            PKind::Instr(_, next) => (1 + next.min_len, next.holes, 1 + next.size),
+            PKind::Loop { body, next } => (
+                2 + body.min_len + next.min_len,
+                body.holes + next.holes,
+                1 + body.size + next.size,
+            ),
+        };
+        ProgramNode {
+            kind,
+            min_len,
+            holes,
+            size,
+        }
+    }
+
+    /// Copy with every hole replaced by `Empty`, so every node keeps its
+    /// position.
+    pub fn concretize_min(&self) -> Shared<ProgramNode> {
+        self.concretize(false)
+    }
+
+    /// `concretize_min` for a search node that printed the target, leaving
+    /// out the loops whose body is still a hole: execution never got into
+    /// such a body (it would have expanded the hole), so the loop was
+    /// skipped every time it ran, and dropping it changes no output. A
+    /// plain concretization would print each of them as a no-op `[]`.
+    ///
+    /// ```
+    /// use bf_search::Program;
+    ///
+    /// let p: Program = ".[?]+[-?]?".parse().unwrap();
+    /// assert_eq!(p.root.concretize_min().to_string(), ".[]+[-]");
+    /// assert_eq!(p.root.concretize_solution().to_string(), ".+[-]");
+    /// assert_eq!(p.root.concretize_solution().min_len, 5);
+    /// ```
+    pub fn concretize_solution(&self) -> Shared<ProgramNode> {
+        self.concretize(true)
+    }
+
+    fn concretize(&self, skip_unentered_loops: bool) -> Shared<ProgramNode> {
+        // Post-order over an explicit stack: a node is rebuilt once its
+        // children (body first, then next) are on `done`.
+        let mut work = vec![(self, false)];
+        let mut done: Vec<Shared<ProgramNode>> = Vec::new();
+        while let Some((node, children_done)) = work.pop() {
+            match &node.kind {
+                PKind::Hole | PKind::Empty => done.push(ProgramNode::empty()),
+                PKind::Instr(i, next) => {
+                    if children_done {
+                        let next = done.pop().unwrap();
+                        done.push(ProgramNode::instr(*i, next));
+                    } else {
+                        work.push((node, true));
+                        work.push((next, false));
+                    }
+                }
+                PKind::Loop { body, next } => {
+                    if skip_unentered_loops && matches!(body.kind, PKind::Hole) {
+                        work.push((next, false)); // stands in for the loop
+                    } else if children_done {
+                        let next = done.pop().unwrap();
+                        let body = done.pop().unwrap();
+                        done.push(ProgramNode::looped(body, next));
+                    } else {
+                        work.push((node, true));
+                        work.push((next, false));
+                        work.push((body, false));
+                    }
+                }
+            }
+        }
+        done.pop().unwrap()
+    }
+
+    pub fn to_bf_string(root: &Shared<ProgramNode>) -> String {
+        root.to_string()
+    }
+
+    /// Where each node is in `to_string()`, by position: the column of its
+    /// instruction, of `[` for a loop, of `]` for the end of a loop body,
+    /// and one past the last character for the end of the program. Holes
+    /// are at their `?`.
+    ///
+    /// ```
+    /// use bf_search::Program;
+    ///
+    /// let p: Program = "+[->?]".parse().unwrap();
+    /// // positions as in `Program`'s example: '+' '[' '-' '>' '?' end
+    /// assert_eq!(p.root.columns(), [0, 1, 2, 3, 4, 6]);
+    /// let p: Program = "[.]".parse().unwrap();
+    /// assert_eq!(p.root.columns(), [0, 1, 2, 3]); // `[`, `.`, `]` and the end
+    /// ```
+    pub fn columns(&self) -> Vec<usize> {
+        // Walk as `Display` writes, counting characters; that is preorder
+        let mut columns = Vec::with_capacity(self.size as usize);
+        let mut open = Vec::new();
+        let mut node = self;
+        let mut col = 0;
+        loop {
+            columns.push(col);
+            match &node.kind {
+                PKind::Instr(_, next) => {
+                    col += 1;
+                    node = next;
+                }
+                PKind::Loop { body, next } => {
+                    col += 1;
+                    open.push(next);
+                    node = body;
+                }
+                PKind::Hole | PKind::Empty => {
+                    if let PKind::Hole = node.kind {
+                        col += 1;
+                    }
+                    let Some(next) = open.pop() else {
+                        return columns;
+                    };
+                    col += 1;
+                    node = next;
+                }
+            }
+        }
+    }
+}
+
+/// Programs shorter than this are dropped recursively: a node lies at
+/// most `min_len` levels below the root, so the recursion is bounded.
+const DEEP_DROP: u32 = 1 << 10;
+
+/// Unlinks the `next` chain of a long program iteratively, so that dropping
+/// it does not recurse once per instruction (loop bodies still recurse, once
+/// per level of nesting).
+impl Drop for ProgramNode {
+    fn drop(&mut self) {
+        if self.min_len < DEEP_DROP {
+            return;
+        }
+        let mut kind = std::mem::replace(&mut self.kind, PKind::Empty);
+        loop {
+            let next = match kind {
+                PKind::Instr(_, next) | PKind::Loop { next, .. } => next,
+                PKind::Hole | PKind::Empty => return,
+            };
+            if next.min_len < DEEP_DROP {
+                return;
+            }
+            let Some(mut next) = Shared::into_inner(next) else {
+                return; // still shared
+            };
+            kind = std::mem::replace(&mut next.kind, PKind::Empty);
+        }
+    }
+}
+
+/// Brainfuck source, with holes as `?`.
+impl fmt::Display for ProgramNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Follow the `next` chain; the loops we are inside wait on `open`.
+        let mut open = Vec::new();
+        let mut node = self;
+        loop {
+            match &node.kind {
+                PKind::Instr(i, next) => {
+                    f.write_char(i.to_char())?;
+                    node = next;
+                }
+                PKind::Loop { body, next } => {
+                    f.write_char('[')?;
+                    open.push(next);
+                    node = body;
+                }
+                PKind::Hole | PKind::Empty => {
+                    if let PKind::Hole = node.kind {
+                        f.write_char('?')?;
+                    }
+                    let Some(next) = open.pop() else {
+                        return Ok(());
+                    };
+                    f.write_char(']')?;
+                    node = next;
+                }
+            }
+        }
+    }
+}
+
+/// One node per line with its position and `min_len`; a sequence stays at
+/// one indentation level, loop bodies are indented under their loop.
+impl fmt::Debug for ProgramNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut open = Vec::new(); // as in `Display`; its length is the depth
+        let mut node = self;
+        let mut at = 0; // preorder, as `Display` walks
+        loop {
+            write!(f, "{:1$}#{2} ", "", open.len() * 2, at)?;
+            at += 1;
+            match &node.kind {
+                PKind::Instr(i, next) => {
+                    writeln!(f, "'{}' min_len {}", i.to_char(), node.min_len)?;
+                    node = next;
+                }
+                PKind::Loop { body, next } => {
+                    writeln!(f, "[ min_len {}", node.min_len)?;
+                    open.push(next);
+                    node = body;
+                }
+                PKind::Hole | PKind::Empty => {
+                    match node.kind {
+                        PKind::Hole => writeln!(f, "?")?,
+                        _ => writeln!(f, "end")?,
+                    }
+                    match open.pop() {
+                        Some(next) => node = next,
+                        None => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A parsed (possibly partial) program.
+///
+/// ```
+/// use bf_search::Program;
+///
+/// let p: Program = "+[->?]".parse().unwrap();
+/// assert_eq!(p.to_string(), "+[->?]");
+/// assert_eq!((p.root.min_len, p.root.size), (5, 6));
+/// assert_eq!(format!("{:?}", p.root), "#0 '+' min_len 5\n#1 [ min_len 4\n  #2 '-' min_len 2\n  #3 '>' min_len 1\n  #4 ?\n#5 end\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Program {
+    pub root: Shared<ProgramNode>,
+}
+
+impl FromStr for Program {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Program, String> {
+        let root = parse::parse_partial(src)?;
+        Ok(Program { root })
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.root.fmt(f)
+    }
+}
+
+/// Copy of `root` with the hole at position `at` replaced; unchanged
+/// subtrees are shared, including the other places of a hole node that
+/// stands at several.
+///
+/// ```
+/// use bf_search::{replace_hole, Program, ProgramNode, SearchError};
+///
+/// let p: Program = "+?".parse().unwrap();
+/// let filled = replace_hole(&p.root, 1, ProgramNode::empty()).unwrap();
+/// assert_eq!(filled.to_string(), "+");
+/// let err = replace_hole(&p.root, 0, ProgramNode::empty()).err();
+/// assert_eq!(err, Some(SearchError::HoleNotFound(0)));
+/// ```
+pub fn replace_hole(
+    root: &Shared<ProgramNode>,
+    at: u32,
+    replacement: Shared<ProgramNode>,
+) -> Result<Shared<ProgramNode>, SearchError> {
+    let path = path_to_hole(root, at).ok_or(SearchError::HoleNotFound(at))?;
+    Ok(rebuild_path(&path, replacement, &mut Interner::disabled(), |_, _| {}))
+}
+
+/// The child of `node` at position `at` within it (0 being `node` itself),
+/// with whether that child is its loop body, and `at` within the child.
+fn child_at(node: &ProgramNode, at: u32) -> Option<(&Shared<ProgramNode>, bool, u32)> {
+    if at == 0 || at >= node.size {
+        return None;
+    }
+    match &node.kind {
+        PKind::Instr(_, next) => Some((next, false, at - 1)),
+        PKind::Loop { body, .. } if at <= body.size => Some((body, true, at - 1)),
+        PKind::Loop { body, next } => Some((next, false, at - 1 - body.size)),
+        PKind::Hole | PKind::Empty => None,
+    }
+}
+
+/// The ancestors of the hole at position `at`, root first, each with
+/// whether the path continues into its loop body (rather than its next).
+fn path_to_hole(root: &ProgramNode, at: u32) -> Option<Vec<(&ProgramNode, bool)>> {
+    let mut path = Vec::new();
+    let (mut cur, mut at) = (root, at);
+    while at > 0 {
+        let (child, into_body, rest) = child_at(cur, at)?;
+        path.push((cur, into_body));
+        (cur, at) = (child, rest);
+    }
+    matches!(cur.kind, PKind::Hole).then_some(path)
+}
+
+/// Rebuild the ancestors in `path` (from `path_to_hole`) bottom-up around
+/// `replacement`; the branches off the path stay shared. `visit` sees each
+/// new node with its depth (the replacement's is `path.len()`).
+fn rebuild_path(
+    path: &[(&ProgramNode, bool)],
+    replacement: Shared<ProgramNode>,
+    interner: &mut Interner,
+    mut visit: impl FnMut(usize, &Shared<ProgramNode>),
+) -> Shared<ProgramNode> {
+    let mut new = replacement;
+    visit(path.len(), &new);
+    for (depth, &(anc, into_body)) in path.iter().enumerate().rev() {
+        new = interner.with_child(anc, into_body, new);
+        visit(depth, &new);
+    }
+    new
+}
+
+/// The node at position `at` of `root`, if any: 0 is the root, and a node
+/// comes before its loop body, which comes before its next. Found by
+/// descending along `size`s, so in as many steps as the node is deep.
+///
+/// Like the other walks over a program, this one follows `next` chains in a
+/// loop, so programs of any length are fine; only loop nesting costs stack:
+///
+/// ```
+/// use bf_search::parse::parse_program;
+/// use bf_search::simplify::simplify;
+/// use bf_search::{node_at, replace_hole, Instr, PKind, ProgramNode};
+///
+/// const N: u32 = 100_000;
+/// // `+` * N, then `[-]`, then a hole
+/// let mut root = ProgramNode::hole();
+/// root = ProgramNode::looped(ProgramNode::instr(Instr::Dec, ProgramNode::empty()), root);
+/// for _ in 0..N {
+///     root = ProgramNode::instr(Instr::Inc, root);
+/// }
+///
+/// let src = ProgramNode::to_bf_string(&root);
+/// assert_eq!(src.len(), N as usize + 4);
+/// assert!(src.ends_with("+[-]?"));
+/// assert_eq!(format!("{:?}", root).lines().count(), N as usize + 4);
+/// assert_eq!(root.concretize_min().to_string(), src[..src.len() - 1]);
+/// assert!(matches!(node_at(&root, N + 1).unwrap().kind, PKind::Instr(Instr::Dec, _)));
+/// assert!(node_at(&root, N + 4).is_none());
+///
+/// let filled = replace_hole(&root, N + 3, ProgramNode::instr(Instr::Output, ProgramNode::empty())).unwrap();
+/// assert!(filled.to_string().ends_with("[-]."));
+///
+/// let parsed = parse_program(&"+-".repeat(N as usize)).unwrap();
+/// assert_eq!(parsed.min_len, 2 * N);
+/// assert_eq!(simplify(&parsed).min_len, 0);
+/// ```
+pub fn node_at(root: &Shared<ProgramNode>, at: u32) -> Option<Shared<ProgramNode>> {
+    let (mut node, mut at) = (root, at);
+    while at > 0 {
+        (node, _, at) = child_at(node, at)?;
+    }
+    Some(node.clone())
+}
+
+/// An entered loop: its position, where `]` jumps back to (position
+/// `at + 1`), and where it exits to (`at + 1 + body.size`).
+///
+/// Holes are only expanded at the pc, and the pc lies inside the body of
+/// every loop on the stack. So `next` is never rebuilt while its frame is
+/// live, and `body` always is: `step_once` swaps in the rebuilt body on
+/// each expansion, and reports a frame whose loop is not around the pc as
+/// `SearchError::DanglingFrame`.
+#[derive(Clone)]
+pub struct LoopFrame {
+    pub at: u32,
+    pub body: Shared<ProgramNode>,
+    pub next: Shared<ProgramNode>,
+}
+
+/// Persistent stack of loop frames, innermost on top. Clones share their
+/// frames, so cloning a search node costs the same at any loop depth.
+#[derive(Clone, Default)]
+pub struct LoopStack(Option<Shared<LoopLink>>);
+
+#[derive(Clone)]
+struct LoopLink {
+    frame: LoopFrame,
+    below: LoopStack,
+}
+
+impl LoopStack {
+    pub fn new() -> LoopStack {
+        LoopStack(None)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn last(&self) -> Option<&LoopFrame> {
+        self.0.as_ref().map(|link| &link.frame)
+    }
+
+    pub fn push(&mut self, frame: LoopFrame) {
+        let below = std::mem::take(self);
+        self.0 = Some(Shared::new(LoopLink { frame, below }));
+    }
+
+    pub fn pop(&mut self) -> Option<LoopFrame> {
+        let link = Shared::unwrap_or_clone(self.0.take()?);
+        *self = link.below;
+        Some(link.frame)
+    }
+
+    /// Frames from the innermost loop outwards.
+    pub fn iter(&self) -> impl Iterator<Item = &LoopFrame> {
+        std::iter::successors(self.0.as_deref(), |link| link.below.0.as_deref())
+            .map(|link| &link.frame)
+    }
+}
+
+/// Frames are pushed in order, so the last one ends up on top.
+impl FromIterator<LoopFrame> for LoopStack {
+    fn from_iter<T: IntoIterator<Item = LoopFrame>>(iter: T) -> LoopStack {
+        let mut stack = LoopStack::new();
+        for frame in iter {
+            stack.push(frame);
+        }
+        stack
+    }
+}
+
+/// The largest β or γ a `SearchConfig` accepts. Below it the score of
+/// any node (at most `u32::MAX` long, `u64::MAX` steps) stays within
+/// `SCORE_LIMIT`, so no score in a search is ever clamped.
+pub const MAX_COEFFICIENT: f64 = 1e6;
+
+/// Program nodes under `root` (itself included) reachable only through
+/// nodes no one else points to, a reference from `pc` aside; the rest of
+/// the tree is shared and paid for elsewhere.
+fn owned_nodes(root: &Shared<ProgramNode>, pc: &Shared<ProgramNode>) -> usize {
+    let owned = |node: &&Shared<ProgramNode>| Shared::strong_count(node) - Shared::ptr_eq(node, pc) as usize <= 1;
+    let mut count = 0;
+    let mut stack: Vec<_> = Some(root).filter(owned).into_iter().collect();
+    while let Some(node) = stack.pop() {
+        count += 1;
+        let children = match &node.kind {
+            PKind::Instr(_, next) => [Some(next), None],
+            PKind::Loop { body, next } => [Some(body), Some(next)],
+            PKind::Hole | PKind::Empty => [None, None],
+        };
+        stack.extend(children.into_iter().flatten().filter(owned));
+    }
+    count
+}
+
+/// Scores are clamped into ±2⁵³, beyond which `f64` no longer tells whole
+/// numbers apart; a score that is not a number counts as `-SCORE_LIMIT`.
+pub const SCORE_LIMIT: f64 = 9_007_199_254_740_992.0;
+
+#[derive(Clone)]
+pub struct SearchNode {
+    pub root: Shared<ProgramNode>,      // partial program AST
+    pub pc: Shared<ProgramNode>,        // P-subtree to execute next
+    pub pc_at: u32, // position of `pc` in `root` (see `node_at`)
+    pub loop_stack: LoopStack, // for matching ']' semantics
+    pub dp: i64,
+    pub tape: Tape,
+    pub steps: u64,
+    pub last_output: u64, // `steps` when the last byte was output (0 before any)
+    pub correct: usize, // number of correct output bytes (matching prefix)
+    pub overflow: Vec<u8>, // output past the end of the target
+    pub dead_len: u32, // length of the unentered loops the pc can no longer reach
+    pub case_correct: usize, // bytes right on the other cases at the last check (see `Case`)
+}
+
+impl SearchNode {
+    pub fn initial() -> SearchNode {
+        let root = ProgramNode::hole();
+        SearchNode {
+            root: root.clone(),
+            pc: root,
+            pc_at: 0,
+            loop_stack: LoopStack::new(),
+            dp: 0,
+            tape: Tape::new(),
+            steps: 0,
+            last_output: 0,
+            correct: 0,
+            overflow: Vec::new(),
+            dead_len: 0,
+            case_correct: 0,
+        }
+    }
+
+    /// The same state on `tape` instead; for a node that has not run yet.
+    pub fn with_tape(mut self, tape: Tape) -> SearchNode {
+        self.tape = tape;
+        self
+    }
+
+    /// Interpreter state at the start of a concrete (hole-free) program.
+    pub fn concrete(root: Shared<ProgramNode>) -> SearchNode {
+        SearchNode {
+            root: root.clone(),
+            pc: root,
+            pc_at: 0,
+            loop_stack: LoopStack::new(),
+            dp: 0,
+            tape: Tape::new(),
+            steps: 0,
+            last_output: 0,
+            correct: 0,
+            overflow: Vec::new(),
+            dead_len: 0,
+            case_correct: 0,
+        }
+    }
+
+    /// Bytes output so far, `target` being the one searched for.
+    pub fn output(&self, target: &[u8]) -> Vec<u8> {
+        let mut out = target[..self.correct].to_vec();
+        out.extend_from_slice(&self.overflow);
+        out
+    }
+
+    /// At the end of the program, outside every loop.
+    pub fn halted(&self) -> bool {
+        matches!(self.pc.kind, PKind::Empty) && self.loop_stack.is_empty()
+    }
+
+    /// Approximate bytes this node keeps alive in the frontier: its own
+    /// fields, the tape, the output past the target, the loop stack, and
+    /// the program nodes no other reference shares (those rebuilt when its
+    /// hole was filled). Cheap enough to take on every push; see
+    /// `SearchConfigBuilder::max_memory`.
+    ///
+    /// ```
+    /// use bf_search::SearchNode;
+    ///
+    /// let base = SearchNode::initial().footprint();
+    /// let mut node = SearchNode::initial();
+    /// node.tape.set(1_000, 1); // a sparse cell
+    /// let one = node.footprint();
+    /// assert!(one > base);
+    /// node.tape.set(2_000, 1);
+    /// let two = node.footprint();
+    /// assert!(two > one);
+    /// node.overflow = vec![0; 100];
+    /// assert_eq!(node.footprint(), two + 100);
+    ///
+    /// // a program the node alone holds counts; one shared with `code` does not
+    /// let code: bf_search::Program = "+++[->+<]?".parse().unwrap();
+    /// let shared = SearchNode::concrete(code.root.clone());
+    /// let owned = SearchNode::concrete("+++[->+<]?".parse::<bf_search::Program>().unwrap().root);
+    /// assert!(owned.footprint() > shared.footprint());
+    /// ```
+    pub fn footprint(&self) -> usize {
+        let ast = owned_nodes(&self.root, &self.pc) * (std::mem::size_of::<ProgramNode>() + 2 * std::mem::size_of::<usize>());
+        std::mem::size_of::<SearchNode>()
+            + self.tape.footprint()
+            + self.overflow.len()
+            + self.loop_stack.len() * std::mem::size_of::<LoopLink>()
+            + ast
+    }
+
+    pub fn get_cell(&self, idx: i64) -> u8 {
+        self.tape.get(idx)
+    }
+
+    /// `root.min_len` without the dead syntax: the `[` `]` of every loop
+    /// whose body is still a hole, once the pc is outside every loop around
+    /// it. Such a loop was skipped each time it ran and can never run again,
+    /// so it is left out of solutions (see `concretize_solution`); a skipped
+    /// loop inside a loop still running may yet be entered, and counts.
+    ///
+    /// ```
+    /// use bf_search::{exec_known_step, Program, SearchNode};
+    ///
+    /// let run = |src: &str, steps: usize| {
+    ///     let p: Program = src.parse().unwrap();
+    ///     let mut node = SearchNode::concrete(p.root);
+    ///     for _ in 0..steps {
+    ///         node = exec_known_step(node, &[]).unwrap().advanced().unwrap();
+    ///     }
+    ///     (node.root.min_len, node.live_len())
+    /// };
+    /// assert_eq!(run("[?]+?", 0), (3, 3));
+    /// assert_eq!(run("[?]+?", 1), (3, 1)); // skipped at the top level
+    /// // `>[?]<` inside a loop running once: dead when the loop exits
+    /// assert_eq!(run("+[>[?]<-]?", 6), (8, 8));
+    /// assert_eq!(run("+[>[?]<-]?", 7), (8, 6));
+    /// // an entered loop's body is no longer a hole
+    /// assert_eq!(run("+[-]?", 4), (4, 4));
+    /// ```
+    pub fn live_len(&self) -> u32 {
+        self.root.min_len - self.dead_len
+    }
+
+    /// `correct − β·min_len − γ·log2(steps + 1)`, clamped into
+    /// ±`SCORE_LIMIT`; `case_correct` counts as correct bytes too. Never
+    /// more for fewer correct bytes, a longer program or more steps,
+    /// whatever the coefficients:
+    ///
+    /// ```
+    /// use bf_search::{Program, SearchNode, MAX_COEFFICIENT, SCORE_LIMIT};
+    ///
+    /// let coefficients = [0.0, 1e-300, 0.5, 1.0, MAX_COEFFICIENT, f64::MAX, f64::INFINITY];
+    /// let correct = [0, 1, 1 << 40, usize::MAX];
+    /// let lens = [0, 1, 200];
+    /// let steps = [0, 1, 1 << 53, u64::MAX - 1, u64::MAX];
+    /// let node = |c: usize, len: usize, s: u64| {
+    ///     let p: Program = format!("{}?", "+".repeat(len)).parse().unwrap();
+    ///     SearchNode { correct: c, steps: s, ..SearchNode::concrete(p.root) }
+    /// };
+    /// for beta in coefficients {
+    ///     for gamma in coefficients {
+    ///         let score = |c, len, s| {
+    ///             let score = node(c, len, s).score(beta, gamma);
+    ///             assert!(score.is_finite() && score.abs() <= SCORE_LIMIT);
+    ///             score
+    ///         };
+    ///         for w in correct.windows(2) {
+    ///             assert!(score(w[0], 1, 1) <= score(w[1], 1, 1));
+    ///         }
+    ///         for w in lens.windows(2) {
+    ///             assert!(score(1, w[0], 1) >= score(1, w[1], 1));
+    ///         }
+    ///         for w in steps.windows(2) {
+    ///             assert!(score(1, 1, w[0]) >= score(1, 1, w[1]));
+    ///         }
+    ///     }
+    /// }
+    /// // 0 · ∞ is not a number
+    /// assert_eq!(node(0, 0, 0).score(f64::INFINITY, 0.0), -SCORE_LIMIT);
+    /// ```
+    pub fn score(&self, beta: f64, gamma: f64) -> f64 {
+        self.checked_score(false, beta, gamma).0
+    }
+
+    /// `score` with `live_len` for the length term.
+    pub fn live_score(&self, beta: f64, gamma: f64) -> f64 {
+        self.checked_score(true, beta, gamma).0
+    }
+
+    /// `score` (or `live_score`), and whether it had to be clamped.
+    pub(crate) fn checked_score(&self, live: bool, beta: f64, gamma: f64) -> (f64, bool) {
+        let len = if live { self.live_len() } else { self.root.min_len };
+        let correct = self.correct as f64 + self.case_correct as f64;
+        checked_score(correct, len, self.steps, beta, gamma)
+    }
+
+    pub fn score_breakdown(&self, beta: f64, gamma: f64) -> String {
+        format!(
+            "{} - {:.3} * {} - {:.3} * log2({} + 1) = {:.3}",
+            self.correct + self.case_correct,
+            beta,
+            self.root.min_len,
+            gamma,
+            self.steps,
+            self.score(beta, gamma)
+        )
+    }
+}
+
+/// The score of a state from its parts; see `SearchNode::checked_score`.
+pub(crate) fn checked_score(correct: f64, len: u32, steps: u64, beta: f64, gamma: f64) -> (f64, bool) {
+    // `steps + 1` would overflow at `u64::MAX`
+    let steps_term = steps as f64 + 1.0;
+    let score = correct - beta * len as f64 - gamma * steps_term.log2();
+    if score.is_nan() {
+        (-SCORE_LIMIT, true)
+    } else {
+        let clamped = score.clamp(-SCORE_LIMIT, SCORE_LIMIT);
+        (clamped, clamped != score)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum AdvancePolicy {
+    Search,     // expand holes and step
+    #[allow(dead_code)]
+    NoExpand,   // for demo/extrapolation: do not expand; treat holes as halt
+}
+
+/// What one interpreter step on a `SearchNode` did.
+#[derive(Clone)]
+pub enum StepOutcome {
+    /// Executed one instruction, or the jump of a `[` or `]`.
+    Advanced(SearchNode),
+    /// Expanded the hole at the pc: one outcome per production, in the
+    /// order `Empty`, the set's instructions, `[P];P` (never `Expanded`
+    /// again). The productions other than `Empty` have run their first
+    /// step; `Empty` inside a loop comes as `Advanced`, its `]` still to run.
+    Expanded(Vec<StepOutcome>),
+    /// At the end of the program, outside every loop; nothing executed.
+    /// Under `AdvancePolicy::NoExpand`, a hole at the pc halts too.
+    Halted(SearchNode),
+    /// Output a byte differing from the target, or reached a `,`.
+    Pruned(PruneReason),
+}
+
+impl StepOutcome {
+    /// The node after an `Advanced` step.
+    pub fn advanced(self) -> Option<SearchNode> {
+        match self {
+            StepOutcome::Advanced(node) => Some(node),
+            _ => None,
+        }
+    }
+}
+
+/// One step of `node` under `policy`; see `StepOutcome`.
+///
+/// ```
+/// use bf_search::{step_once, AdvancePolicy, InstrSet, Program, PruneReason, SearchNode, StepOutcome};
+///
+/// let set = InstrSet::parse("+.[]").unwrap();
+/// let at = |src: &str| {
+///     let p: Program = src.parse().unwrap();
+///     SearchNode::concrete(p.root)
+/// };
+/// let step = |node: &SearchNode| step_once(node, &[1], &set, AdvancePolicy::Search).unwrap();
+///
+/// let StepOutcome::Expanded(outcomes) = step(&at("?")) else { panic!() };
+/// let kinds: Vec<_> = outcomes
+///     .iter()
+///     .map(|o| match o {
+///         StepOutcome::Advanced(n) => format!("advanced to {}", n.root),
+///         StepOutcome::Halted(n) => format!("halted as {:?}", n.root.to_string()),
+///         StepOutcome::Pruned(r) => r.describe().to_string(),
+///         StepOutcome::Expanded(_) => unreachable!(),
+///     })
+///     .collect();
+/// assert_eq!(kinds, ["halted as \"\"", "advanced to +?", "output mismatch", "advanced to [?]?"]);
+///
+/// assert!(matches!(step(&at("+")), StepOutcome::Advanced(_)));
+/// assert!(matches!(step(&at("")), StepOutcome::Halted(_)));
+/// assert!(matches!(step(&at(".")), StepOutcome::Pruned(PruneReason::Mismatch)));
+/// assert!(matches!(step(&at(",")), StepOutcome::Pruned(PruneReason::Input)));
+/// let held = step_once(&at("?"), &[1], &set, AdvancePolicy::NoExpand).unwrap();
+/// assert!(matches!(held, StepOutcome::Halted(_)));
+/// ```
+pub fn step_once(
+    node: &SearchNode,
+    target: &[u8],
+    instr_set: &InstrSet,
+    policy: AdvancePolicy,
+) -> Result<StepOutcome, SearchError> {
+    step_once_with(node, target, instr_set, policy, &mut Interner::disabled())
+}
+
+/// `step_once`, building the nodes of an expansion through `interner` so
+/// that children share them with other search nodes.
+pub fn step_once_with(
+    node: &SearchNode,
+    target: &[u8],
+    instr_set: &InstrSet,
+    policy: AdvancePolicy,
+    interner: &mut Interner,
+) -> Result<StepOutcome, SearchError> {
+    let PKind::Hole = node.pc.kind else {
+        // Known node: execute one instruction step or loop movement
+        return exec_step(node.clone(), target);
+    };
+    if let AdvancePolicy::NoExpand = policy {
+        // Do not expand holes in demo mode; treat as halt.
+        return Ok(StepOutcome::Halted(node.clone()));
+    }
+    // Expand: Empty, I;P, [P];P (restricted to the allowed productions),
+    // all substituted at the same site.
+    let site = HoleSite::new(node)?;
+    let mut outcomes = Vec::new();
+
+    // 1) Empty: no step executed. Outside loops the program has halted
+    // there (a premature halt unless the target is complete).
+    let child = site.fill(interner.empty(), interner);
+    outcomes.push(if child.loop_stack.is_empty() {
+        StepOutcome::Halted(child)
+    } else {
+        StepOutcome::Advanced(child)
+    });
+
+    // 2) For each instruction: I;P, then execute it
+    for &i in &instr_set.instrs {
+        let next_p = interner.hole();
+        let replacement = interner.instr(i, next_p);
+        // pc points to the replaced P-subtree (I;P)
+        let child = site.fill(replacement, interner);
+        outcomes.push(exec_step(child, target)?);
+    }
+
+    // 3) Loop: [P];P, then execute the '['
+    if instr_set.loops {
+        let (body, next) = (interner.hole(), interner.hole());
+        let replacement = interner.looped(body, next);
+        let child = site.fill(replacement, interner);
+        outcomes.push(exec_step(child, target)?);
+    }
+
+    debug_assert!(
+        outcomes.iter().all(|o| match o {
+            StepOutcome::Advanced(child) | StepOutcome::Halted(child) => pc_in_place(child),
+            _ => true,
+        }),
+        "an expansion of {} lost track of the pc",
+        node.root
+    );
+    Ok(StepOutcome::Expanded(outcomes))
+}
+
+/// `pc` is the node at `pc_at`, and each frame's body and next are at the
+/// places its `at` gives them.
+fn pc_in_place(node: &SearchNode) -> bool {
+    let is_at = |at, want: &Shared<ProgramNode>| {
+        node_at(&node.root, at).is_some_and(|n| Shared::ptr_eq(&n, want))
+    };
+    is_at(node.pc_at, &node.pc)
+        && node
+            .loop_stack
+            .iter()
+            .all(|f| is_at(f.at + 1, &f.body) && is_at(f.at + 1 + f.body.size, &f.next))
+}
+
+/// The hole under a node's pc, located once so that every production can be
+/// substituted into it by rebuilding just the ancestors. Each loop frame's
+/// body is one of those ancestors (or the hole itself) and is swapped for
+/// its rebuilt copy; see `LoopFrame`.
+struct HoleSite<'a> {
+    node: &'a SearchNode,
+    path: Vec<(&'a ProgramNode, bool)>,
+    frames: Vec<(LoopFrame, usize)>, // innermost first, with the depth of the body
+}
+
+impl<'a> HoleSite<'a> {
+    fn new(node: &'a SearchNode) -> Result<HoleSite<'a>, SearchError> {
+        let path = path_to_hole(&node.root, node.pc_at).ok_or(SearchError::HoleNotFound(node.pc_at))?;
+        // The positions of the path's nodes, rising from the root's 0
+        let mut at = Vec::with_capacity(path.len());
+        let mut pos = 0;
+        for &(anc, into_body) in &path {
+            at.push(pos);
+            pos += match (&anc.kind, into_body) {
+                (PKind::Loop { body, .. }, false) => 1 + body.size,
+                _ => 1,
+            };
+        }
+        let frames = node
+            .loop_stack
+            .iter()
+            .map(|frame| {
+                // the loop is on the path, and the path goes into its body
+                at.binary_search(&frame.at)
+                    .ok()
+                    .filter(|&d| path[d].1)
+                    .map(|d| (frame.clone(), d + 1))
+                    .ok_or(SearchError::DanglingFrame(frame.at))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(HoleSite { node, path, frames })
+    }
+
+    /// The node with `replacement` in place of the hole, as its new pc.
+    fn fill(&self, replacement: Shared<ProgramNode>, interner: &mut Interner) -> SearchNode {
+        let mut frames = self.frames.clone();
+        let root = rebuild_path(&self.path, replacement.clone(), interner, |depth, new| {
+            for (frame, _) in frames.iter_mut().filter(|(_, d)| *d == depth) {
+                frame.body = new.clone();
+            }
+        });
+        let node = self.node;
+        SearchNode {
+            root,
+            pc: replacement,
+            pc_at: node.pc_at,
+            loop_stack: frames.into_iter().rev().map(|(frame, _)| frame).collect(),
+            dp: node.dp,
+            tape: node.tape.clone(),
+            steps: node.steps,
+            last_output: node.last_output,
+            correct: node.correct,
+            overflow: node.overflow.clone(),
+            dead_len: node.dead_len,
+            case_correct: node.case_correct,
+        }
+    }
+}
+
+/// One step of a node whose pc is not a hole (at a hole it halts).
+pub fn exec_known_step(node: SearchNode, target: &[u8]) -> Result<StepOutcome, SearchError> {
+    exec_step(node, target)
+}
+
+/// Length of the `[` `]` of the loops in `root` whose body is a hole.
+fn unentered_len(root: &ProgramNode) -> u32 {
+    let mut len = 0;
+    let mut work = vec![root];
+    while let Some(node) = work.pop() {
+        if node.holes == 0 {
+            continue;
+        }
+        match &node.kind {
+            PKind::Instr(_, next) => work.push(next),
+            PKind::Loop { body, next } => {
+                if matches!(body.kind, PKind::Hole) {
+                    len += 2;
+                } else {
+                    work.push(body);
+                }
+                work.push(next);
+            }
+            PKind::Hole | PKind::Empty => {}
+        }
+    }
+    len
+}
+
+fn exec_step(mut node: SearchNode, target: &[u8]) -> Result<StepOutcome, SearchError> {
+    Ok(match step_in_place(&mut node, target) {
+        InPlace::Advanced => StepOutcome::Advanced(node),
+        InPlace::Halted => StepOutcome::Halted(node),
+        InPlace::Pruned(reason) => StepOutcome::Pruned(reason),
+    })
+}
+
+/// What `step_in_place` did; see `StepOutcome`.
+pub(crate) enum InPlace {
+    Advanced,
+    Halted,
+    Pruned(PruneReason), // the node is left mid-step
+}
+
+/// `exec_step` on `node` itself.
+pub(crate) fn step_in_place(node: &mut SearchNode, target: &[u8]) -> InPlace {
+    // Execute one interpreter step for nodes where pc is not a Hole,
+    // or already expanded in caller.
+    //
+    // Prune if:
+    // - Outputs mismatch target prefix
+    // - ',' encountered (no input supported): prune branch
+    //
+    // Halt cases:
+    // - pc is Empty and loop_stack empty => halts (no child)
+    // - NoExpand policy isn't handled here; this function is called from Search mode.
+    //
+    // Steps count includes '[' and ']' virtual steps.
+    match &node.pc.kind {
+        PKind::Empty => {
+            // Either end-of-program or end-of-loop-body (']' action)
+            if node.loop_stack.is_empty() {
+                // Program halts
+                // The caller decides whether that was premature.
+                InPlace::Halted
+            } else {
+                // Execute ']' step
+                node.steps = node.steps.saturating_add(1);
+                let cur = node.get_cell(node.dp);
+                if cur != 0 {
+                    // Jump back into body start; stay in same loop
+                    let frame = node.loop_stack.last().unwrap();
+                    node.pc = frame.body.clone();
+                    node.pc_at = frame.at + 1;
+                } else {
+                    // Exit loop; leaving the outermost one, the loops its
+                    // body skipped are behind the pc for good
+                    let frame = node.loop_stack.pop().unwrap();
+                    if node.loop_stack.is_empty() {
+                        node.dead_len += unentered_len(&frame.body);
+                    }
+                    node.pc_at = frame.at + 1 + frame.body.size;
+                    node.pc = frame.next;
+                }
+                InPlace::Advanced
+            }
+        }
+        PKind::Instr(i, next) => {
+            node.steps = node.steps.saturating_add(1);
+            match i {
+                Instr::IncPtr => {
+                    node.dp = node.dp.saturating_add(1);
+                }
+                Instr::DecPtr => {
+                    node.dp = node.dp.saturating_sub(1);
+                }
+                Instr::Inc => {
+                    let v = node.get_cell(node.dp).wrapping_add(1);
+                    node.tape.set(node.dp, v);
+                }
+                Instr::Dec => {
+                    let v = node.get_cell(node.dp).wrapping_sub(1);
+                    node.tape.set(node.dp, v);
+                }
+                Instr::Output => {
+                    // Every byte so far matched, so the output is
+                    // target[..correct] followed by the overflow.
+                    let v = node.get_cell(node.dp);
+                    if node.correct < target.len() {
+                        if v != target[node.correct] {
+                            return InPlace::Pruned(PruneReason::Mismatch);
+                        }
+                        node.correct += 1;
+                    } else {
+                        node.overflow.push(v);
+                    }
+                    node.last_output = node.steps;
+                }
+                Instr::Input => {
+                    // No input supported
+                    return InPlace::Pruned(PruneReason::Input);
+                }
+            }
+            node.pc = next.clone();
+            node.pc_at += 1;
+            InPlace::Advanced
+        }
+        PKind::Loop { body, next } => {
+            // Execute '[' step
+            node.steps = node.steps.saturating_add(1);
+            let cur = node.get_cell(node.dp);
+            if cur == 0 {
+                // Skip loop
+                if node.loop_stack.is_empty() && matches!(body.kind, PKind::Hole) {
+                    node.dead_len += 2;
+                }
+                node.pc_at += 1 + body.size;
+                node.pc = next.clone();
+            } else {
+                // Enter loop: push frame and set pc to body
+                node.loop_stack.push(LoopFrame {
+                    at: node.pc_at,
+                    body: body.clone(),
+                    next: next.clone(),
+                });
+                node.pc_at += 1;
+                node.pc = body.clone();
+            }
+            InPlace::Advanced
+        }
+        PKind::Hole => {
+            // Should be expanded by caller
+            InPlace::Halted
+        }
+    }
+}
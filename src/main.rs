@@ -1,13 +1,47 @@
-use clap::Parser;
-use im::HashMap as ImHashMap;
-use ordered_float::NotNan;
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashSet};
-use std::io::{self, Write};
-use std::rc::Rc;
+// The command-line tool; the browser build (wasm32) is the library alone.
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
+
+mod cli;
+
+use bf_search::archive::Archive;
+use bf_search::census::Census;
+use bf_search::digits;
+use bf_search::explain::explain;
+use bf_search::idioms;
+use bf_search::interp::{Interpreter, RunEnd};
+use bf_search::monitor::SnapshotFile;
+use bf_search::near;
+use bf_search::pareto::ParetoFront;
+use bf_search::parse::parse_program;
+use bf_search::portfolio::{parse_portfolio, Portfolio, Weights};
+use bf_search::record::{Divergence, Recorder, Replay};
+use bf_search::{
+    Callbacks, Case, ConfigError, Dedup, Expansion, Frontier, InstrSet, Program, ProgramNode,
+    PruneCounts, PruneReason, SearchConfig, SearchError, Searcher, Shared, Solution, Tape,
+};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use cli::{brute, compare, config, emit, interrupt, prompt, serve, simplify, trace, tui};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Exit code when a --replay run differs from its log.
+const EXIT_DIVERGED: i32 = 1;
+/// Exit code when the search was interrupted (Ctrl+C) before finishing.
+const EXIT_STOPPED: i32 = 3;
+/// Exit code when the search or an interpreter run hit a broken internal invariant.
+const EXIT_INTERNAL: i32 = 4;
 
 #[derive(Parser, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Provide the target as a hex string (e.g., "00010203" or "00 01 02 03")
     #[arg(short = 'x', long = "hex", value_name = "HEX")]
     hex: Option<String>,
@@ -18,8 +52,7 @@ struct Args {
         value_name = "BYTE",
         value_parser = clap::value_parser!(u8),
         num_args = 1..,
-        value_delimiter = ',',
-        required_unless_present = "hex"
+        value_delimiter = ','
     )]
     bytes: Vec<u8>,
 
@@ -39,477 +72,564 @@ struct Args {
     #[arg(long = "max-steps", default_value_t = 1_000_000)]
     max_steps: u64,
 
+    /// Set aside nodes past --max-steps instead of dropping them; if the
+    /// search runs dry before the first solution, multiply --max-steps by
+    /// FACTOR and queue the ones that fit again
+    #[arg(long = "retry-relaxed", value_name = "FACTOR")]
+    retry_relaxed: Option<f64>,
+
+    /// With --retry-relaxed: also relax after N expansions without a solution
+    #[arg(long = "relax-after", value_name = "N", requires = "retry_relaxed")]
+    relax_after: Option<u64>,
+
+    /// With --retry-relaxed: set aside at most N nodes; later ones are dropped
+    #[arg(long = "max-parked", value_name = "N", default_value_t = 100_000)]
+    max_parked: usize,
+
+    /// Drop nodes that ran more than R steps since their last output byte
+    #[arg(long = "max-steps-per-byte", value_name = "R")]
+    max_steps_per_byte: Option<u64>,
+
     /// Safety cap on steps when running the concrete solution for display
     #[arg(long = "demo-steps", default_value_t = 1_000_000)]
     demo_steps: u64,
-}
 
-#[derive(Clone, Copy, Debug)]
-enum Instr {
-    IncPtr,
-    DecPtr,
-    Inc,
-    Dec,
-    Output,
-    Input,
-}
-
-impl Instr {
-    fn all() -> &'static [Instr] {
-        &[
-            Instr::IncPtr,
-            Instr::DecPtr,
-            Instr::Inc,
-            Instr::Dec,
-            Instr::Output,
-            Instr::Input,
+    /// Allowed commands, e.g. "+-." for straight-line single-cell programs.
+    /// `[` (or `]`) allows loops.
+    #[arg(long = "instr-set", value_name = "CMDS", default_value = "><+-.,[]")]
+    instr_set: String,
+
+    /// Cells every program starts on instead of zeros: values from cell 0
+    /// on ("10 0 3"), or single cells ("cell[-2]=7"), or both
+    #[arg(long = "tape-init", value_name = "CELLS", conflicts_with = "emit")]
+    tape_init: Option<String>,
+
+    /// Function synthesis: one (initial tape, output) pair the program must
+    /// satisfy, e.g. "tape=2;out=4"; repeat for more pairs. The first takes
+    /// the place of the target and --tape-init
+    #[arg(
+        long = "case",
+        value_name = "CASE",
+        conflicts_with_all = ["bytes", "hex", "tape_init", "targets", "emit"]
+    )]
+    case: Vec<String>,
+
+    /// With several --case pairs: also check branches against the other
+    /// pairs every K steps, not only once they print the first pair's output
+    #[arg(long = "case-check-steps", value_name = "K")]
+    case_check_steps: Option<u64>,
+
+    /// Also start from the built-in idiom templates and those in FILE, one
+    /// per line with `?` holes (`builtin`: the built-in ones alone; see "Idioms")
+    #[arg(long = "idioms", value_name = "FILE")]
+    idioms: Option<PathBuf>,
+
+    /// Search near PROGRAM (source, or a file holding it) instead of from
+    /// scratch: start from its variants with parts cut out (see "Near a program")
+    #[arg(long = "near", value_name = "PROGRAM")]
+    near: Option<String>,
+
+    /// With --near: how many parts of the program a variant may cut
+    #[arg(long = "holes", value_name = "K", default_value_t = 1)]
+    holes: usize,
+
+    /// Search for the target numbers as decimal text ("1 2 3") rather than
+    /// as raw bytes: the digits of each, --digits-sep between them
+    #[arg(long = "as-digits", conflicts_with = "case")]
+    as_digits: bool,
+
+    /// With --as-digits: the byte between numbers; space, comma, newline,
+    /// tab, or any one character but a digit
+    #[arg(long = "digits-sep", value_name = "SEP", default_value = "space")]
+    digits_sep: String,
+
+    /// When stdin is not a terminal: stop after this many solutions
+    /// (default: 1, or unlimited with --auto-continue-secs)
+    #[arg(long = "solutions", value_name = "N")]
+    solutions: Option<usize>,
+
+    /// When stdin is not a terminal: keep searching for more solutions for
+    /// this many seconds after the first one, then exit
+    #[arg(long = "auto-continue-secs", value_name = "S")]
+    auto_continue_secs: Option<u64>,
+
+    /// Stop once SECS seconds pass without a new solution after one has
+    /// been found (time at the prompt does not count)
+    #[arg(long = "solution-gap", value_name = "SECS", conflicts_with = "tui")]
+    solution_gap: Option<f64>,
+
+    /// Stop once N expansions pass without a new solution after one has
+    /// been found
+    #[arg(long = "solution-gap-expansions", value_name = "N", conflicts_with_all = ["tui", "portfolio"])]
+    solution_gap_expansions: Option<u64>,
+
+    /// Golf mode: after each solution, only search for strictly shorter ones
+    #[arg(long = "golf")]
+    golf: bool,
+
+    /// Drop every branch whose program grows past L instructions
+    #[arg(long = "max-len", value_name = "L")]
+    max_len: Option<u32>,
+
+    /// Search every program up to --max-len to the end and count those
+    /// printing the target instead of reporting them (see "Counting")
+    #[arg(
+        long = "count-only",
+        requires = "max_len",
+        conflicts_with_all = [
+            "golf", "pareto_only", "optimize", "require_halt", "idioms", "near", "retry_relaxed",
+            "max_memory", "portfolio", "targets", "tui", "record", "replay",
         ]
-    }
+    )]
+    count_only: bool,
 
-    fn to_char(self) -> char {
-        match self {
-            Instr::IncPtr => '>',
-            Instr::DecPtr => '<',
-            Instr::Inc => '+',
-            Instr::Dec => '-',
-            Instr::Output => '.',
-            Instr::Input => ',',
-        }
-    }
-}
-
-#[derive(Clone)]
-struct ProgramNode {
-    nid: u32, // stable node id
-    kind: PKind,
-    min_len: u32, // minimal possible length of any instantiation of this P
-}
-
-#[derive(Clone)]
-enum PKind {
-    Hole,
-    Empty,
-    Instr(Instr, Rc<ProgramNode>), // I;P
-    Loop {
-        body: Rc<ProgramNode>, // [P];P
-        next: Rc<ProgramNode>,
-    },
-}
-
-impl ProgramNode {
-    fn hole_with_id(id: u32) -> Rc<ProgramNode> {
-        Rc::new(ProgramNode {
-            nid: id,
-            kind: PKind::Hole,
-            min_len: 0,
-        })
-    }
-    fn empty_with_id(id: u32) -> Rc<ProgramNode> {
-        Rc::new(ProgramNode {
-            nid: id,
-            kind: PKind::Empty,
-            min_len: 0,
-        })
-    }
-    fn instr_with_id(id: u32, i: Instr, next: Rc<ProgramNode>) -> Rc<ProgramNode> {
-        Rc::new(ProgramNode {
-            nid: id,
-            kind: PKind::Instr(i, next.clone()),
-            min_len: 1 + next.min_len,
-        })
-    }
-    fn loop_with_id(id: u32, body: Rc<ProgramNode>, next: Rc<ProgramNode>) -> Rc<ProgramNode> {
-        Rc::new(ProgramNode {
-            nid: id,
-            kind: PKind::Loop {
-                body: body.clone(),
-                next: next.clone(),
-            },
-            min_len: 2 + body.min_len + next.min_len,
-        })
-    }
-
-    fn concretize_min(&self) -> Rc<ProgramNode> {
-        match &self.kind {
-            PKind::Hole => ProgramNode::empty_with_id(self.nid),
-            PKind::Empty => ProgramNode::empty_with_id(self.nid),
-            PKind::Instr(i, next) => {
-                ProgramNode::instr_with_id(self.nid, *i, next.concretize_min())
-            }
-            PKind::Loop { body, next } => {
-                ProgramNode::loop_with_id(
-                    self.nid,
-                    body.concretize_min(),
-                    next.concretize_min(),
-                )
-            }
-        }
-    }
+    /// With --count-only: also print each program counted
+    #[arg(long = "list", requires = "count_only")]
+    list: bool,
 
-    fn to_bf_string(root: &Rc<ProgramNode>) -> String {
-        let mut s = String::new();
-        fn rec(node: &Rc<ProgramNode>, out: &mut String) {
-            match &node.kind {
-                PKind::Hole => {
-                    // In a concrete program we shouldn't have holes. If any, treat as end.
-                }
-                PKind::Empty => {}
-                PKind::Instr(i, next) => {
-                    out.push(i.to_char());
-                    rec(next, out);
-                }
-                PKind::Loop { body, next } => {
-                    out.push('[');
-                    rec(body, out);
-                    out.push(']');
-                    rec(next, out);
-                }
-            }
-        }
-        rec(root, &mut s);
-        s
-    }
+    /// Only report programs that halt right after printing the target
+    #[arg(long = "exact-halt")]
+    exact_halt: bool,
+
+    /// Only report programs that halt within --demo-steps steps
+    #[arg(long = "require-halt")]
+    require_halt: bool,
+
+    /// Only report solutions no earlier one is both as short as and as
+    /// fast as (in steps to the target)
+    #[arg(long = "pareto-only")]
+    pareto_only: bool,
+
+    /// After the first solution, keep searching for faster ones: report
+    /// only those extending the Pareto front, drop programs more than
+    /// --optimize-slack longer than the shortest (see "Optimizing steps")
+    #[arg(long = "optimize", value_name = "CRITERION", value_enum)]
+    optimize: Option<OptimizeArg>,
+
+    /// With --optimize: how much longer than the shortest solution a
+    /// faster one may be
+    #[arg(long = "optimize-slack", value_name = "N", default_value_t = 0)]
+    optimize_slack: u32,
+
+    /// Frontier order: exact best-first (heap), or scores rounded into
+    /// buckets of --bucket-width, each expanded in insertion order
+    #[arg(long = "frontier", value_enum, default_value_t = FrontierArg::Heap)]
+    frontier: FrontierArg,
+
+    /// With --frontier bucket: the score range one bucket covers
+    #[arg(long = "bucket-width", value_name = "W", default_value_t = 0.01)]
+    bucket_width: f64,
+
+    /// Share identical program nodes between search states: less memory on
+    /// long runs, slower expansion
+    #[arg(long = "intern")]
+    intern: bool,
+
+    /// Keep the frontier under about BYTES, evicting its worst-scoring
+    /// nodes when it grows past them (see "Memory")
+    #[arg(long = "max-memory", value_name = "BYTES")]
+    max_memory: Option<usize>,
+
+    /// After each pop, expand the best child directly for up to K
+    /// expansions instead of queueing it (0: plain best-first)
+    #[arg(long = "rollout", value_name = "K", default_value_t = 0)]
+    rollout: u32,
+
+    /// When two solutions are the same: same source text, or same output
+    /// (the target plus --extra bytes, within --demo-steps) and halting
+    #[arg(long = "dedup", value_enum, default_value_t = DedupArg::Behavior)]
+    dedup: DedupArg,
+
+    /// Check each new search node for a solution when it is generated
+    /// rather than when it reaches the front of the queue
+    #[arg(long = "eager")]
+    eager: bool,
+
+    /// Leave skipped loops that can never run again out of the length
+    /// term of the score
+    #[arg(long = "live-length")]
+    live_length: bool,
+
+    /// Also print each solution translated into LANG (see "emit")
+    #[arg(long = "emit", value_name = "LANG", value_enum)]
+    emit: Option<emit::Lang>,
+
+    /// Also print which `.` printed each target byte of a solution, and on
+    /// which pass of its loops (see "Explaining solutions")
+    #[arg(long = "explain")]
+    explain: bool,
+
+    /// Search each target listed in FILE (one per line: decimal bytes, or hex
+    /// after a `hex:` prefix) and print a summary table
+    #[arg(long = "targets", value_name = "FILE", conflicts_with_all = ["hex", "bytes"])]
+    targets: Option<PathBuf>,
+
+    /// With --targets: stop searching a target after this many expansions
+    #[arg(long = "per-target-expansions", value_name = "N")]
+    per_target_expansions: Option<u64>,
+
+    /// With --targets: stop searching a target after this many seconds
+    #[arg(long = "per-target-secs", value_name = "S")]
+    per_target_secs: Option<f64>,
+
+    /// Run one search per weight setting, taking turns, e.g.
+    /// "b=1,g=1;b=0.3,g=2" (see "Portfolio")
+    #[arg(
+        long = "portfolio",
+        value_name = "SPEC",
+        conflicts_with_all = ["targets", "tui", "record", "replay", "stats_file"]
+    )]
+    portfolio: Option<String>,
+
+    /// With --portfolio: expansions each member makes per turn
+    #[arg(long = "portfolio-slice", value_name = "N", default_value_t = 1000)]
+    portfolio_slice: u64,
+
+    /// With --portfolio: stop after N expansions over all members
+    #[arg(long = "portfolio-budget", value_name = "N")]
+    portfolio_budget: Option<u64>,
+
+    /// Config file providing defaults for the options above
+    /// (default: ~/.config/bf_search.toml if it exists)
+    #[arg(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Keep a JSON snapshot of the search's progress in FILE, rewritten
+    /// every --stats-interval seconds (see "Monitoring")
+    #[arg(long = "stats-file", value_name = "FILE")]
+    stats_file: Option<PathBuf>,
+
+    /// With --stats-file: seconds between snapshots
+    #[arg(long = "stats-interval", value_name = "S", default_value_t = 5.0)]
+    stats_interval: f64,
+
+    /// Skip solutions found by earlier runs of the target, listed in FILE,
+    /// and append the new ones to it (see "Archive")
+    #[arg(long = "archive", value_name = "FILE", conflicts_with = "tui")]
+    archive: Option<PathBuf>,
+
+    /// List the solutions of the target in the --archive file, then exit
+    #[arg(long = "archive-list", conflicts_with = "targets")]
+    archive_list: bool,
+
+    /// Log every expansion of the search to FILE (see "Record and replay")
+    #[arg(long = "record", value_name = "FILE", conflicts_with_all = ["replay", "targets"])]
+    record: Option<PathBuf>,
+
+    /// Rerun a search logged with --record (same target and options),
+    /// checking each expansion against FILE; stops at the first difference
+    #[arg(long = "replay", value_name = "FILE", conflicts_with = "targets")]
+    replay: Option<PathBuf>,
+
+    /// Show the search on a full-screen dashboard instead of printing each
+    /// solution (needs a terminal)
+    #[arg(long = "tui", conflicts_with_all = ["targets", "record", "replay"])]
+    tui: bool,
+
+    /// Also report the candidates --require-halt suppresses
+    #[arg(long = "verbose")]
+    verbose: bool,
+
+    /// Print the effective configuration and where each value came from, then exit
+    #[arg(long = "print-config")]
+    print_config: bool,
+
+    /// Also queue the search state in FILE (JSON, as `snapshot` writes it)
+    /// past every check; for testing the search's guards
+    #[cfg(feature = "testing")]
+    #[arg(long = "inject", value_name = "FILE", hide = true, conflicts_with_all = ["targets", "portfolio", "tui"])]
+    inject: Option<PathBuf>,
 }
 
-fn replace_hole(root: &Rc<ProgramNode>, target_id: u32, replacement: Rc<ProgramNode>) -> Rc<ProgramNode> {
-    fn rec(cur: &Rc<ProgramNode>, tid: u32, rep: &Rc<ProgramNode>) -> (Rc<ProgramNode>, bool) {
-        match &cur.kind {
-            PKind::Hole => {
-                if cur.nid == tid {
-                    (rep.clone(), true)
-                } else {
-                    (cur.clone(), false)
-                }
-            }
-            PKind::Empty => (cur.clone(), false),
-            PKind::Instr(i, next) => {
-                let (new_next, chg) = rec(next, tid, rep);
-                if chg {
-                    // preserve this node's id
-                    (
-                        ProgramNode::instr_with_id(cur.nid, *i, new_next),
-                        true,
-                    )
-                } else {
-                    (cur.clone(), false)
-                }
-            }
-            PKind::Loop { body, next } => {
-                let (new_body, chg_b) = rec(body, tid, rep);
-                let (new_next, chg_n) = rec(next, tid, rep);
-                if chg_b || chg_n {
-                    (
-                        ProgramNode::loop_with_id(cur.nid, new_body, new_next),
-                        true,
-                    )
-                } else {
-                    (cur.clone(), false)
-                }
-            }
-        }
-    }
-    let (new_root, changed) = rec(root, target_id, &replacement);
-    if !changed {
-        panic!("Hole id {} not found in AST", target_id);
-    }
-    new_root
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum FrontierArg {
+    Heap,
+    Bucket,
 }
 
-fn find_by_id(root: &Rc<ProgramNode>, target_id: u32) -> Option<Rc<ProgramNode>> {
-    fn dfs(n: &Rc<ProgramNode>, tid: u32) -> Option<Rc<ProgramNode>> {
-        if n.nid == tid {
-            return Some(n.clone());
-        }
-        match &n.kind {
-            PKind::Hole | PKind::Empty => None,
-            PKind::Instr(_, next) => dfs(next, tid),
-            PKind::Loop { body, next } => dfs(body, tid).or_else(|| dfs(next, tid)),
-        }
-    }
-    dfs(root, target_id)
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum OptimizeArg {
+    Steps,
 }
 
-#[derive(Clone)]
-struct LoopFrame {
-    body_id: u32,
-    next_id: u32,
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum DedupArg {
+    String,
+    Behavior,
 }
 
-#[derive(Clone)]
-struct SearchNode {
-    root: Rc<ProgramNode>,      // partial program AST
-    pc: Rc<ProgramNode>,        // P-subtree to execute next
-    loop_stack: Vec<LoopFrame>, // for matching ']' semantics
-    dp: i64,
-    tape: ImHashMap<i64, u8>,
-    steps: u64,
-    outputs: Vec<u8>,
-    correct: usize, // number of correct output bytes (matching prefix)
-    next_id: u32, // generator for fresh node ids (holes and new nodes)
+#[derive(clap::Subcommand, Debug, Clone)]
+enum Command {
+    /// Try every program up to a length and list those printing the target
+    Brute(brute::BruteArgs),
+    /// Run two programs and show where their outputs first differ
+    Compare(compare::CompareArgs),
+    /// Translate a program into C or Rust
+    Emit(emit::EmitArgs),
+    /// Answer JSON-RPC requests on stdin, one per line
+    Serve(serve::ServeArgs),
+    /// Apply semantics-preserving peephole rewrites to a program
+    Simplify(simplify::SimplifyArgs),
+    /// Step through a program interactively
+    Trace(trace::TraceArgs),
 }
 
-impl SearchNode {
-    fn initial() -> SearchNode {
-        let root = ProgramNode::hole_with_id(0);
-        SearchNode {
-            root: root.clone(),
-            pc: root,
-            loop_stack: Vec::new(),
-            dp: 0,
-            tape: ImHashMap::new(),
-            steps: 0,
-            outputs: Vec::new(),
-            correct: 0,
-            next_id: 1,
+#[derive(Clone, Copy)]
+enum StopReason {
+    Exhausted,
+    Quit,
+    SolutionLimit,
+    AutoContinueElapsed,
+    SolutionGap,
+    Budget,
+    Interrupted,
+    ReplayEnd,
+    Diverged,
+    Aborted(SearchError),
+}
+
+fn print_summary(outcome: &SearchOutcome, target: &[u8]) {
+    let searcher = &outcome.searcher;
+    let stats = searcher.stats();
+    let config = searcher.config();
+    println!();
+    println!("Search summary:");
+    let why = match outcome.reason {
+        StopReason::Exhausted if stats.solutions == 0 => &exhausted_reason(&stats.pruned),
+        StopReason::Exhausted => "search space exhausted",
+        StopReason::Quit => "quit by user",
+        StopReason::SolutionLimit => "requested number of solutions found",
+        StopReason::AutoContinueElapsed => "auto-continue time elapsed",
+        StopReason::SolutionGap => "no new solution within the --solution-gap",
+        StopReason::Budget => "search budget used up",
+        StopReason::Interrupted => "interrupted (Ctrl+C)",
+        StopReason::ReplayEnd => "end of the --replay log",
+        StopReason::Diverged => "the run diverged from the --replay log",
+        StopReason::Aborted(_) => "aborted by an internal error",
+    };
+    println!("  Stopped       : {}", why);
+    if let StopReason::Aborted(e) = outcome.reason {
+        println!("  Error         : {}", e);
+    }
+    println!(
+        "  Expansions    : {} ({} nodes pushed) in {:.2?}",
+        stats.expansions,
+        stats.pushed,
+        stats.started.elapsed()
+    );
+    println!("  Solutions     : {}", stats.solutions);
+    if config.max_memory().is_some() {
+        println!(
+            "  Frontier      : {} open node(s), about {} (peak {})",
+            searcher.frontier_len(),
+            mib(stats.memory),
+            mib(stats.peak_memory)
+        );
+    } else {
+        println!("  Frontier      : {} open node(s)", searcher.frontier_len());
+    }
+    if stats.pruned.total() > 0 {
+        let counts: Vec<String> = stats
+            .pruned
+            .iter()
+            .map(|(reason, n)| format!("{} {}", n, reason.describe()))
+            .collect();
+        println!("  Pruned        : {}", counts.join(", "));
+    }
+    if stats.parked > 0 {
+        println!(
+            "  Set aside     : {} node(s) past the step cap, {} revived over {} relaxation(s) (--max-steps now {})",
+            stats.parked,
+            stats.revived,
+            stats.relaxations,
+            searcher.config().max_steps()
+        );
+    }
+    if stats.clamped > 0 {
+        println!(
+            "  Clamped       : {} score(s) out of range (internal error), ordered as ties",
+            stats.clamped
+        );
+    }
+    if stats.non_halting > 0 {
+        println!(
+            "  Non-halting   : {} candidate(s) still running after --demo-steps, not reported",
+            stats.non_halting
+        );
+    }
+    if stats.duplicates > 0 {
+        println!(
+            "  Duplicates    : {} variant(s) behaving like a reported solution",
+            stats.duplicates
+        );
+    }
+    if stats.dominated > 0 {
+        println!(
+            "  Dominated     : {} solution(s) no shorter and no faster than a reported one, not reported",
+            stats.dominated
+        );
+    }
+    if stats.case_failures > 0 {
+        println!(
+            "  Case fails    : {} program(s) printing the target but failing another --case, not reported",
+            stats.case_failures
+        );
+    }
+    if stats.errors > 0 {
+        println!(
+            "  Skipped       : {} node(s) after internal errors",
+            stats.errors
+        );
+    }
+    if stats.unverified > 0 {
+        println!(
+            "  Unverified    : {} program(s) not reproducing the target (internal error)",
+            stats.unverified
+        );
+    }
+    match &outcome.log {
+        Some(LogResult::Recorded(path)) => println!(
+            "  Recorded      : {} expansion(s) to {}",
+            stats.expansions,
+            path.display()
+        ),
+        Some(LogResult::Replayed(Ok(n))) => {
+            println!("  Replay        : all {} logged expansion(s) matched", n)
         }
+        Some(LogResult::Replayed(Err(d))) => println!("  Replay        : diverged at {}", d),
+        None => {}
     }
-
-    fn get_cell(&self, idx: i64) -> u8 {
-        *self.tape.get(&idx).unwrap_or(&0)
+    if let Some((path, n)) = &outcome.archived {
+        println!("  Archived      : {} new solution(s) to {}", n, path.display());
     }
-
-    fn set_cell(mut tape: ImHashMap<i64, u8>, idx: i64, val: u8) -> ImHashMap<i64, u8> {
-        if val == 0 {
-            tape.remove(&idx);
+    if let (true, Some((_, len))) = (config.golf(), &outcome.shortest) {
+        if let StopReason::Exhausted = outcome.reason {
+            let within = match config.max_steps_per_byte() {
+                Some(_) => "--max-steps and --max-steps-per-byte",
+                None => "--max-steps",
+            };
+            println!(
+                "  Golf          : best length {}; no shorter program exists (within {})",
+                len, within
+            );
         } else {
-            tape.insert(idx, val);
+            println!(
+                "  Golf          : best length {}; shorter programs not ruled out",
+                len
+            );
         }
-        tape
     }
-
-    fn score(&self, beta: f64, gamma: f64) -> f64 {
-        let len = self.root.min_len as f64;
-        let steps_term = (self.steps + 1) as f64;
-        (self.correct as f64) - beta * len - gamma * steps_term.log2()
+    if config.optimize_steps().is_some() {
+        let entries = searcher.front().entries();
+        if let (Some(shortest), Some(fastest)) = (entries.first(), entries.last()) {
+            for (name, e) in [("Shortest", shortest), ("Fastest", fastest)] {
+                println!("  {:<14}: {} ({} instructions, {} steps)", name, e.code, e.min_len, e.steps);
+            }
+        }
+    }
+    print_front(searcher.front());
+    if let (StopReason::Interrupted, Some(b)) = (outcome.reason, searcher.best()) {
+        println!(
+            "Best partial program so far ({}/{} bytes correct):",
+            b.correct,
+            target.len()
+        );
+        println!("  Program: {}", ProgramNode::to_bf_string(&b.root));
+        println!("  Score  : {}", b.score_breakdown(config.beta(), config.gamma()));
     }
 }
 
-// For the priority queue
-struct HeapItem {
-    score: NotNan<f64>,
-    seq: u64, // tie-breaker for deterministic ordering
-    node: SearchNode,
-}
-
-impl PartialEq for HeapItem {
-    fn eq(&self, other: &Self) -> bool {
-        self.score == other.score && self.seq == other.seq
+/// Under --as-digits: `output` as text, and read back as numbers, those
+/// of `target` then the extrapolated ones, noting where the text stops
+/// being numbers.
+fn print_numbers(output: &[u8], target: &[u8], sep: u8) {
+    println!("TEXT : {}", to_text(output));
+    let given = digits::decode(target, sep).numbers;
+    let read = digits::decode(output, sep);
+    let shown: Vec<String> = read.numbers.iter().map(u64::to_string).collect();
+    let (head, tail) = shown.split_at(given.len().min(shown.len()));
+    println!(
+        "NUMS : {} | {}{}",
+        head.join(" "),
+        tail.join(" "),
+        if read.open { " ..." } else { "" }
+    );
+    // The last target number may run on into more digits: "1 2 3" as "1 2 34".
+    let last = given.len().checked_sub(1).and_then(|i| read.numbers.get(i));
+    if let (Some(want), Some(got)) = (given.last(), last) {
+        if want != got {
+            println!(
+                "The last target number runs on: {} is printed as {}",
+                want, got
+            );
+        }
+    }
+    match read.stray {
+        Some(at) => println!(
+            "Stops being numbers at byte {} ({}), after {} number(s) past the target",
+            at,
+            to_text(&output[at..at + 1]),
+            tail.len()
+        ),
+        None => println!("{} number(s) past the target", tail.len()),
     }
 }
-impl Eq for HeapItem {}
 
-impl PartialOrd for HeapItem {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// The Pareto front as a table, shortest first.
+fn print_front(front: &ParetoFront) {
+    if front.is_empty() {
+        return;
     }
-}
-impl Ord for HeapItem {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Max-heap by score, then by smaller seq first
-        match self.score.cmp(&other.score) {
-            Ordering::Equal => self.seq.cmp(&other.seq).reverse(),
-            ord => ord,
-        }
+    println!("Pareto front of length vs steps to the target:");
+    println!("  {:>6}  {:>10}  Program", "Length", "Steps");
+    for entry in front.entries() {
+        println!("  {:>6}  {:>10}  {}", entry.min_len, entry.steps, entry.code);
     }
 }
 
-#[derive(Clone, Copy)]
-enum AdvancePolicy {
-    Search,     // expand holes and step
-    #[allow(dead_code)]
-    NoExpand,   // for demo/extrapolation: do not expand; treat holes as halt
-}
-
-fn step_once(
-    node: &SearchNode,
-    target: &[u8],
-    policy: AdvancePolicy,
-) -> Vec<SearchNode> {
-    // Returns 0..N next states (children) after advancing one interpreter step
-    // under the requested policy. Pruned branches return empty.
-    // Note: when policy == NoExpand, encountering a hole halts (no child).
-    let mut results = Vec::new();
-
-    match &node.pc.kind {
-        PKind::Hole => {
-            let cur_id = node.pc.nid;
-            if let AdvancePolicy::NoExpand = policy {
-                // Do not expand holes in demo mode; treat as halt.
-                // If hasn't produced full target, it's premature halt (prune by caller).
-                return results;
-            }
-            // Expand: Empty, I;P, [P];P
-            // 1) Empty
-            {
-                let replacement = ProgramNode::empty_with_id(cur_id);
-                let new_root = replace_hole(&node.root, cur_id, replacement.clone());
-                let mut child = node.clone();
-                child.root = new_root.clone();
-                child.pc = replacement;
-                // No step executed (halt). Parent loop_stack unchanged.
-                // Will be interpreted by caller as a halt/no-progress node.
-                // If premature halt: pruned later; otherwise a solution.
-                results.push(child);
-            }
-
-            // 2) For each instruction: I;P
-            for &i in Instr::all() {
-                let new_hole_id = node.next_id;
-                let next_p = ProgramNode::hole_with_id(new_hole_id);
-                let replacement = ProgramNode::instr_with_id(cur_id, i, next_p.clone());
-                let new_root = replace_hole(&node.root, cur_id, replacement.clone());
-                // pc should point to the replaced P-subtree (replacement)
-                let mut child = node.clone();
-                child.root = new_root;
-                child.pc = replacement; // start at I;P
-                child.next_id = new_hole_id + 1;
-
-                // Now execute one step on this child
-                let mut stepped = exec_known_step(child, target);
-                results.append(&mut stepped);
+/// Why a search ran dry without a solution, judged by what pruned its
+/// branches: the constraints of the problem, or limits that could be raised.
+fn exhausted_reason(pruned: &PruneCounts) -> String {
+    let mut reasons = pruned.iter();
+    if let (Some((only, _)), None) = (reasons.next(), reasons.next()) {
+        let why = match only {
+            PruneReason::Mismatch => "every branch printed a wrong byte",
+            PruneReason::Input => {
+                "all branches were pruned by the input-instruction rule \u{2014} the target may require `,` support"
             }
-
-            // 3) Loop: [P];P
-            {
-                let hid1 = node.next_id;
-                let hid2 = node.next_id + 1;
-                let body = ProgramNode::hole_with_id(hid1);
-                let next = ProgramNode::hole_with_id(hid2);
-                let replacement = ProgramNode::loop_with_id(cur_id, body.clone(), next.clone());
-                let new_root = replace_hole(&node.root, cur_id, replacement.clone());
-                let mut child = node.clone();
-                child.root = new_root;
-                child.pc = replacement;
-                child.next_id = hid2 + 1;
-
-                // Execute one step for '['
-                let mut stepped = exec_known_step(child, target);
-                results.append(&mut stepped);
-            }
-        }
-        _ => {
-            // Known node: execute one instruction step or loop movement
-            let mut stepped = exec_known_step(node.clone(), target);
-            if !stepped.is_empty() {
-                results.append(&mut stepped);
-            } else {
-                // Could be halt at Empty outside loops; nothing to add.
+            PruneReason::PrematureHalt => "every branch halted before printing the whole target",
+            PruneReason::StepLimit => "every branch ran past --max-steps; raising it may find a solution",
+            PruneReason::StepsPerByte => {
+                "every branch ran past --max-steps-per-byte; raising it may find a solution"
             }
-        }
+            PruneReason::ExtraOutput => "every branch printed past the target",
+            PruneReason::GolfBound => "no branch was shorter than the golf bound",
+            PruneReason::OtherCase => "every branch failed one of the other --case pairs",
+            PruneReason::MemoryCap => "every branch was evicted under --max-memory",
+            PruneReason::LengthCap => "every branch grew past --max-len",
+        };
+        return format!("no solution: {}", why);
     }
-
-    results
-}
-
-fn exec_known_step(mut node: SearchNode, target: &[u8]) -> Vec<SearchNode> {
-    // Execute one interpreter step for nodes where pc is not a Hole,
-    // or already expanded in caller. Return either:
-    // - empty vec: halted or pruned
-    // - vec with one child: advanced
-    //
-    // Prune if:
-    // - Outputs mismatch target prefix
-    // - ',' encountered (no input supported): prune branch
-    //
-    // Halt cases:
-    // - pc is Empty and loop_stack empty => halts (no child)
-    // - NoExpand policy isn't handled here; this function is called from Search mode.
-    //
-    // Steps count includes '[' and ']' virtual steps.
-    let mut out = Vec::new();
-
-    match &node.pc.kind {
-        PKind::Empty => {
-            // Either end-of-program or end-of-loop-body (']' action)
-            if node.loop_stack.is_empty() {
-                // Program halts
-                // No child produced; caller will check if it's premature.
-                return out;
-            } else {
-                // Execute ']' step
-                node.steps = node.steps.saturating_add(1);
-                let top = node.loop_stack.last().cloned().unwrap();
-                let cur = node.get_cell(node.dp);
-                if cur != 0 {
-                    // Jump back into body start; stay in same loop
-                    if let Some(p) = find_by_id(&node.root, top.body_id) {
-                        node.pc = p;
-                    } else {
-                        return out; // body not found, halt
-                    }
-                } else {
-                    // Exit loop
-                    node.loop_stack.pop();
-                    if let Some(p) = find_by_id(&node.root, top.next_id) {
-                        node.pc = p;
-                    } else {
-                        return out; // next not found, halt
-                    }
-                }
-                out.push(node);
-                return out;
-            }
-        }
-        PKind::Instr(i, next) => {
-            node.steps = node.steps.saturating_add(1);
-            match i {
-                Instr::IncPtr => {
-                    node.dp = node.dp.saturating_add(1);
-                }
-                Instr::DecPtr => {
-                    node.dp = node.dp.saturating_sub(1);
-                }
-                Instr::Inc => {
-                    let v = node.get_cell(node.dp).wrapping_add(1);
-                    node.tape = SearchNode::set_cell(node.tape.clone(), node.dp, v);
-                }
-                Instr::Dec => {
-                    let v = node.get_cell(node.dp).wrapping_sub(1);
-                    node.tape = SearchNode::set_cell(node.tape.clone(), node.dp, v);
-                }
-                Instr::Output => {
-                    let v = node.get_cell(node.dp);
-                    node.outputs.push(v);
-                    let idx = node.outputs.len() - 1;
-                    if idx < target.len() && v != target[idx] {
-                        // Mismatch => prune
-                        return out;
-                    }
-                    if idx < target.len() {
-                        node.correct = idx + 1;
-                    }
-                }
-                Instr::Input => {
-                    // No input supported; prune this branch
-                    return out;
-                }
-            }
-            node.pc = next.clone();
-            out.push(node);
-            return out;
-        }
-        PKind::Loop { body, next } => {
-            // Execute '[' step
-            node.steps = node.steps.saturating_add(1);
-            let cur = node.get_cell(node.dp);
-            if cur == 0 {
-                // Skip loop
-                node.pc = next.clone();
-            } else {
-                // Enter loop: push frame and set pc to body
-                node.loop_stack.push(LoopFrame {
-                    body_id: body.nid,
-                    next_id: next.nid,
-                });
-                node.pc = body.clone();
-            }
-            out.push(node);
-            return out;
-        }
-        PKind::Hole => {
-            // Should be expanded by caller
-            return out;
-        }
+    let limits: Vec<&str> = [
+        (PruneReason::StepLimit, "--max-steps"),
+        (PruneReason::StepsPerByte, "--max-steps-per-byte"),
+        (PruneReason::MemoryCap, "--max-memory"),
+        (PruneReason::LengthCap, "--max-len"),
+    ]
+    .into_iter()
+    .filter(|&(reason, _)| pruned.get(reason) > 0)
+    .map(|(_, flag)| flag)
+    .collect();
+    if limits.is_empty() {
+        "search space exhausted without finding a solution".to_string()
+    } else {
+        format!(
+            "no solution within {}; raising the limit may find one",
+            limits.join(" and ")
+        )
     }
 }
 
@@ -518,7 +638,7 @@ fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, String> {
         .chars()
         .filter(|c| c.is_ascii_hexdigit())
         .collect::<String>();
-    if filtered.len() % 2 != 0 {
+    if !filtered.len().is_multiple_of(2) {
         return Err("Hex string must have an even number of hex digits".into());
     }
     let mut out = Vec::with_capacity(filtered.len() / 2);
@@ -531,6 +651,11 @@ fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, String> {
     Ok(out)
 }
 
+/// `bytes` as a quoted string, escaping what is not printable ASCII.
+fn to_text(bytes: &[u8]) -> String {
+    format!("\"{}\"", bytes.escape_ascii())
+}
+
 fn to_dec(bytes: &[u8]) -> String {
     let mut s = String::new();
     for (i, b) in bytes.iter().enumerate() {
@@ -543,53 +668,54 @@ fn to_dec(bytes: &[u8]) -> String {
     s
 }
 
-fn run_concrete_to_limit(
-    root: Rc<ProgramNode>,
-    limit: usize,
-    step_cap: u64,
-) -> (Vec<u8>, u64, bool) {
-    // Run concrete (no holes) program until:
-    // - output length == limit, or
-    // - halt, or
-    // - step_cap reached
-    //
-    // Returns (outputs, steps, halted_flag)
-    let mut node = SearchNode {
-        root: root.clone(),
-        pc: root.clone(),
-        loop_stack: Vec::new(),
-        dp: 0,
-        tape: ImHashMap::new(),
-        steps: 0,
-        outputs: Vec::new(),
-        correct: 0,
-        next_id: 0,
+/// A program given on the command line: the path of an existing file, or
+/// else the program text itself. Returns the source text and the parse.
+fn program_arg(arg: &str) -> Result<(String, Shared<ProgramNode>), String> {
+    let src = if Path::new(arg).is_file() {
+        std::fs::read_to_string(arg).map_err(|e| format!("{}: {}", arg, e))?
+    } else {
+        arg.to_string()
     };
+    let root = parse_program(&src)?;
+    Ok((src, root))
+}
 
-    loop {
-        if node.outputs.len() >= limit {
-            return (node.outputs, node.steps, false);
-        }
-        if node.steps >= step_cap {
-            return (node.outputs, node.steps, false);
-        }
-        let children = exec_known_step(node.clone(), &[]);
-        if children.is_empty() {
-            // Halted
-            return (node.outputs, node.steps, true);
+fn main() {
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    let matches = Args::command().get_matches_from(&argv);
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    match &args.command {
+        Some(Command::Brute(c)) => std::process::exit(brute::run(c)),
+        Some(Command::Compare(c)) => std::process::exit(compare::run(c)),
+        Some(Command::Emit(c)) => std::process::exit(emit::run(c)),
+        Some(Command::Serve(c)) => std::process::exit(serve::run(c)),
+        Some(Command::Simplify(c)) => std::process::exit(simplify::run(c)),
+        Some(Command::Trace(c)) => std::process::exit(trace::run(c)),
+        None => {}
+    }
+    let provenance = match config::resolve(&mut args, &matches, &argv) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Invalid config: {}", e);
+            std::process::exit(2);
         }
-        node = children.into_iter().next().unwrap();
+    };
+    if args.print_config {
+        provenance.print();
+        return;
     }
-}
 
-fn main() {
-    let args = Args::parse();
     // Input preference: decimal bytes (positional). If --hex is provided, use it.
     let target: Vec<u8> = if let Some(hexstr) = args.hex.as_deref() {
         match parse_hex_bytes(hexstr) {
             Ok(v) => v,
             Err(e) => {
-                eprintln!("Invalid hex input: {}", e);
+                match (provenance.source_of("hex"), &provenance.path) {
+                    (config::Source::File, Some(p)) => {
+                        eprintln!("Invalid hex input (key `hex` in {}): {}", p.display(), e)
+                    }
+                    _ => eprintln!("Invalid hex input: {}", e),
+                }
                 std::process::exit(2);
             }
         }
@@ -597,123 +723,1192 @@ fn main() {
         args.bytes.clone()
     };
 
+    let instr_set = match InstrSet::parse(&args.instr_set) {
+        Ok(set) => set,
+        Err(e) => {
+            eprintln!("Invalid --instr-set: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let tape_init = match args.tape_init.as_deref().unwrap_or("").parse::<Tape>() {
+        Ok(tape) => tape,
+        Err(e) => {
+            eprintln!("Invalid --tape-init: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let mut idiom_set = match args.idioms.as_deref().map(load_idioms) {
+        Some(Ok(idioms)) => idioms,
+        Some(Err(e)) => {
+            eprintln!("Invalid --idioms: {}", e);
+            std::process::exit(2);
+        }
+        None => Vec::new(),
+    };
+    if args.near.is_some() && args.holes == 0 {
+        eprintln!("Invalid --holes: must be at least 1");
+        std::process::exit(2);
+    }
+    if let Some(arg) = &args.near {
+        match program_arg(arg) {
+            Ok((_, root)) => idiom_set.extend(near::variants(&Program { root }, args.holes)),
+            Err(e) => {
+                eprintln!("Invalid --near: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    if args.as_digits {
+        if let Err(e) = digits::parse_separator(&args.digits_sep) {
+            eprintln!("Invalid --digits-sep: {}", e);
+            std::process::exit(2);
+        }
+    }
+    let target = args.encode_target(target);
+    let portfolio = match args.portfolio.as_deref().map(parse_portfolio) {
+        Some(Ok(weights)) => Some(weights),
+        Some(Err(e)) => {
+            eprintln!("Invalid --portfolio: {}", e);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let mut cases = Vec::new();
+    for spec in &args.case {
+        match spec.parse::<Case>() {
+            Ok(case) => cases.push(case),
+            Err(e) => {
+                eprintln!("Invalid --case '{}': {}", spec, e);
+                std::process::exit(2);
+            }
+        }
+    }
+    // The first pair is searched like a target on an initial tape.
+    let (target, tape_init) = if cases.is_empty() {
+        (target, tape_init)
+    } else {
+        let first = cases.remove(0);
+        (first.target, first.tape)
+    };
+
+    if !(args.stats_interval > 0.0 && args.stats_interval.is_finite()) {
+        eprintln!("Invalid --stats-interval: must be a positive number of seconds");
+        std::process::exit(2);
+    }
+    if args.solution_gap.is_some_and(|s| !(s > 0.0 && s.is_finite())) {
+        eprintln!("Invalid --solution-gap: must be a positive number of seconds");
+        std::process::exit(2);
+    }
+    if args.solution_gap_expansions == Some(0) {
+        eprintln!("Invalid --solution-gap-expansions: must be at least 1");
+        std::process::exit(2);
+    }
+
+    interrupt::install();
+
+    if let Some(path) = args.targets.clone() {
+        std::process::exit(run_batch(&args, &instr_set, &tape_init, &idiom_set, &path));
+    }
+
     if target.is_empty() {
         eprintln!("Target sequence must not be empty. Provide decimal bytes (0..=255), e.g.:");
         eprintln!("  bf_search 0 1 2 3");
         std::process::exit(2);
     }
+    if args.archive_list {
+        std::process::exit(list_archive(args.archive.as_deref(), &target));
+    }
+
+    let budget = Budget::default();
+    let search_config = match args.search_config(&target, &instr_set, &tape_init, &idiom_set, &cases, &budget) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Invalid parameters: {}", e);
+            std::process::exit(2);
+        }
+    };
 
     println!("Target length: {} bytes", target.len());
+    if args.as_digits {
+        println!("Target text: {}", to_text(&target));
+    }
+    print_parameters(&args, &instr_set, &tape_init);
+    if !cases.is_empty() {
+        println!("Other cases: {}", cases.len());
+        for case in &cases {
+            println!("  {}", case);
+        }
+    }
+    if let Some(reason) = instr_set.unsolvable_reason(&target, &tape_init) {
+        println!("Unsolvable with this instruction set: {}.", reason);
+        return;
+    }
+    if args.count_only {
+        std::process::exit(run_census(&args, search_config));
+    }
+    if args.tui {
+        if !(io::stdin().is_terminal() && io::stdout().is_terminal()) {
+            eprintln!("--tui needs a terminal on stdin and stdout");
+            std::process::exit(2);
+        }
+        let outcome = match tui::run(search_config, args.verbose, stats_file(&args)) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                eprintln!("Terminal error: {}", e);
+                std::process::exit(2);
+            }
+        };
+        if let Some((code, len)) = &outcome.shortest {
+            println!("Shortest solution ({} instructions): {}", len, code);
+        }
+        print_summary(&outcome, &target);
+        std::process::exit(exit_code(&outcome));
+    }
+    let mut policy = prompt::ContinuePolicy::new(
+        io::stdin().is_terminal(),
+        args.solutions,
+        args.auto_continue_secs,
+        args.solution_gap().is_set(),
+    );
+    if let Some(note) = policy.describe() {
+        println!("{}.", note);
+    }
+    let log = match open_log(&args, &target) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    match &log {
+        Some(SearchLog::Record(path, _)) => println!("Recording expansions to {}", path.display()),
+        Some(SearchLog::Replay(replay)) => {
+            println!("Replaying {} logged expansion(s)", replay.len())
+        }
+        None => {}
+    }
+    let archive = match args.archive.as_deref().map(|path| open_archive(path, &target)) {
+        Some(Ok(archive)) => Some(archive),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    println!("Press Ctrl+C to stop at any time.");
+
+    if let Some(weights) = portfolio {
+        std::process::exit(run_portfolio(&args, search_config, &weights, &mut policy, archive));
+    }
+    let outcome = search(&args, search_config, &mut policy, log, archive);
+    print_summary(&outcome, &target);
+    std::process::exit(exit_code(&outcome));
+}
+
+/// The process exit code after a single-target search.
+fn exit_code(outcome: &SearchOutcome) -> i32 {
+    if let Some(LogResult::Replayed(Err(_))) = outcome.log {
+        return EXIT_DIVERGED;
+    }
+    match outcome.reason {
+        StopReason::Interrupted => EXIT_STOPPED,
+        StopReason::Aborted(_) => EXIT_INTERNAL,
+        _ => 0,
+    }
+}
+
+fn print_parameters(args: &Args, instr_set: &InstrSet, tape_init: &Tape) {
     println!(
         "Scoring: score = correct - {:.3} * min_len - {:.3} * log2(steps + 1)",
         args.beta, args.gamma
     );
-    println!("Press Ctrl+C to stop at any time.");
+    println!("Instruction set: {}", instr_set.to_bf_string());
+    if !tape_init.is_blank() {
+        println!("Initial tape: {}", tape_init);
+    }
+    if let Some(path) = &args.idioms {
+        match path.to_str() {
+            Some("builtin") => println!("Idioms: the built-in templates"),
+            _ => println!("Idioms: the built-in templates and those in {}", path.display()),
+        }
+    }
+    if let Some(arg) = &args.near {
+        println!("Start: near {}, cutting up to {} part(s) of it", arg, args.holes);
+    }
+    if let Some(line) = args.solution_gap().describe() {
+        println!("{}", line);
+    }
+    if let Some(factor) = args.retry_relaxed {
+        let after = match args.relax_after {
+            Some(n) => format!(", or after {} expansions without one", n),
+            None => String::new(),
+        };
+        println!(
+            "Step cap: x{} when the search runs dry before a solution{} (up to {} nodes set aside)",
+            factor, after, args.max_parked
+        );
+    }
+    if let Some(r) = args.max_steps_per_byte {
+        println!("Output gaps: at most {} steps between bytes", r);
+    }
+    if args.exact_halt {
+        println!("Solutions: must halt right after the target");
+    }
+    if args.require_halt {
+        println!("Solutions: must halt within {} steps", args.demo_steps);
+    }
+    if args.pareto_only {
+        println!("Solutions: only those extending the Pareto front of length and steps");
+    }
+    if let Some(len) = args.max_len {
+        println!("Programs: at most {} instructions", len);
+    }
+    if args.optimize.is_some() {
+        println!(
+            "Solutions: after the first, only faster ones at most {} longer than the shortest",
+            args.optimize_slack
+        );
+    }
+    if args.frontier == FrontierArg::Bucket {
+        println!("Frontier: score buckets of width {}", args.bucket_width);
+    }
+    if args.intern {
+        println!("Program nodes: interned");
+    }
+    if let Some(bytes) = args.max_memory {
+        println!("Frontier: at most about {} bytes, worst nodes evicted past that", bytes);
+    }
+    if args.rollout > 0 {
+        println!("Rollouts: up to {} greedy expansions per pop", args.rollout);
+    }
+    if args.dedup == DedupArg::String {
+        println!("Solutions: told apart by source text");
+    }
+    if args.eager {
+        println!("Solutions: checked as soon as they are generated");
+    }
+    if args.live_length {
+        println!("Scoring: min_len counts only live code");
+    }
+    if let Some(path) = &args.stats_file {
+        println!("Progress: snapshot every {}s to {}", args.stats_interval, path.display());
+    }
+}
 
-    let mut heap = BinaryHeap::new();
-    let mut seq_counter: u64 = 0;
+/// --count-only: tally the solutions, printing each under --list.
+struct Counter {
+    census: Census,
+    list: bool,
+}
 
-    let start_node = SearchNode::initial();
-    let start_score = NotNan::new(start_node.score(args.beta, args.gamma)).unwrap();
-    heap.push(HeapItem {
-        score: start_score,
-        seq: seq_counter,
-        node: start_node,
-    });
-    seq_counter += 1;
+impl Callbacks for Counter {
+    fn on_solution(&mut self, solution: &Solution) -> ControlFlow<()> {
+        let halts = self.census.add(solution);
+        if self.list {
+            let note = if halts { "" } else { "  (still running at --max-steps)" };
+            println!("  {}{}", solution.code, note);
+        }
+        ControlFlow::Continue(())
+    }
 
-    let mut solutions_seen: HashSet<String> = HashSet::new();
-    let mut solution_index: usize = 0;
+    fn cancelled(&mut self) -> bool {
+        interrupt::requested()
+    }
+}
 
-    'search: loop {
-        let Some(HeapItem { node, .. }) = heap.pop() else {
-            println!("Search space exhausted without finding a solution.");
-            break;
-        };
+/// --count-only: run the search under --max-len until it runs dry and
+/// print what it went through. Returns the process exit code.
+fn run_census(args: &Args, config: SearchConfig) -> i32 {
+    println!("Counting every program printing the target; press Ctrl+C to stop early.");
+    let mut counter = Counter {
+        census: Census::new(&config),
+        list: args.list,
+    };
+    let mut searcher = Searcher::new(config);
+    let reason = searcher.run(&mut counter);
+    let stats = searcher.stats();
+    let census = &counter.census;
+    println!();
+    println!("Count summary:");
+    let why = match reason {
+        bf_search::StopReason::Exhausted => "every program up to --max-len searched",
+        bf_search::StopReason::Cancelled => "interrupted (Ctrl+C); the counts are partial",
+        bf_search::StopReason::Aborted(_) => "aborted by an internal error; the counts are partial",
+        _ => "search budget used up; the counts are partial",
+    };
+    println!("  Stopped       : {}", why);
+    if let bf_search::StopReason::Aborted(e) = reason {
+        println!("  Error         : {}", e);
+    }
+    println!(
+        "  Expansions    : {} ({} nodes pushed) in {:.2?}",
+        stats.expansions,
+        stats.pushed,
+        stats.started.elapsed()
+    );
+    println!(
+        "  Programs      : {} printing the target, {} of them halting within --max-steps",
+        census.programs(),
+        census.halting()
+    );
+    if stats.pruned.total() > 0 {
+        let counts: Vec<String> = stats
+            .pruned
+            .iter()
+            .map(|(reason, n)| format!("{} {}", n, reason.describe()))
+            .collect();
+        println!("  Pruned        : {}", counts.join(", "));
+    }
+    if stats.case_failures > 0 {
+        println!(
+            "  Case fails    : {} program(s) printing the target but failing another --case, not counted",
+            stats.case_failures
+        );
+    }
+    let most = census.lengths().map(|(_, n)| n).max().unwrap_or(0);
+    if most > 0 {
+        println!("Programs by length:");
+        println!("  {:>6}  {:>10}", "Length", "Programs");
+        for (len, n) in census.lengths() {
+            let bar = "#".repeat(((n * 40).div_ceil(most)) as usize);
+            println!("  {:>6}  {:>10}  {}", len, n, bar);
+        }
+    }
+    match reason {
+        bf_search::StopReason::Cancelled => EXIT_STOPPED,
+        bf_search::StopReason::Aborted(_) => EXIT_INTERNAL,
+        _ => 0,
+    }
+}
 
-        // If this node already matches the full target prefix, it's a solution.
-        if node.correct >= target.len() {
-            // Build a concrete minimal program by setting all holes to Empty
-            let concrete = node.root.concretize_min();
-            let code = ProgramNode::to_bf_string(&concrete);
+/// The --stats-file writer, if one was asked for.
+fn stats_file(args: &Args) -> Option<SnapshotFile> {
+    let interval = Duration::from_secs_f64(args.stats_interval);
+    args.stats_file.as_deref().map(|path| SnapshotFile::new(path, interval))
+}
 
-            if solutions_seen.contains(&code) {
-                // Already reported; continue search
-            } else {
-                solutions_seen.insert(code.clone());
-                solution_index += 1;
-                println!();
-                println!("Solution #{} found:", solution_index);
-                println!("Program length (inst): {}", concrete.min_len);
-                println!("Program (Brainfuck):");
-                println!("{}", code);
-
-                // Run the concrete program to show extrapolation
-                let show_limit = target.len() + args.extra;
-                let (outputs, steps, halted) =
-                    run_concrete_to_limit(concrete.clone(), show_limit, args.demo_steps);
-
-                println!();
-                println!(
-                    "Output (first {} bytes shown):",
-                    outputs.len().min(show_limit)
-                );
-                println!("DEC  : {}", to_dec(&outputs));
-                println!(
-                    "Interpreter steps during demo: {} (halted: {})",
-                    steps, halted
-                );
-
-                println!();
-                print!("Press Enter to search for the next different solution (or 'q' + Enter to quit): ");
-                io::stdout().flush().ok();
-                let mut line = String::new();
-                io::stdin().read_line(&mut line).ok();
-                if line.trim().eq_ignore_ascii_case("q") {
-                    break 'search;
-                }
+/// Limits on a single search; `None` means unlimited.
+#[derive(Clone, Copy, Default)]
+struct Budget {
+    expansions: Option<u64>,
+    time: Option<Duration>,
+}
+
+impl Args {
+    /// The --solution-gap limits.
+    fn solution_gap(&self) -> prompt::SolutionGap {
+        prompt::SolutionGap::new(self.solution_gap, self.solution_gap_expansions)
+    }
+
+    /// The --digits-sep byte under --as-digits (checked in `main`).
+    fn digits_sep(&self) -> Option<u8> {
+        self.as_digits
+            .then(|| digits::parse_separator(&self.digits_sep).ok())
+            .flatten()
+    }
+
+    /// The bytes to search for: under --as-digits, `target`'s numbers as
+    /// decimal text.
+    fn encode_target(&self, target: Vec<u8>) -> Vec<u8> {
+        match self.digits_sep() {
+            Some(sep) => digits::encode(&target.iter().map(|&n| n as u64).collect::<Vec<_>>(), sep),
+            None => target,
+        }
+    }
+
+    /// The search parameters given on the command line, for one target.
+    fn search_config(
+        &self,
+        target: &[u8],
+        instr_set: &InstrSet,
+        tape_init: &Tape,
+        idiom_set: &[Program],
+        cases: &[Case],
+        budget: &Budget,
+    ) -> Result<SearchConfig, ConfigError> {
+        SearchConfig::new(target.to_vec())
+            .beta(self.beta)
+            .gamma(self.gamma)
+            .max_steps(self.max_steps)
+            .max_steps_per_byte(self.max_steps_per_byte)
+            .retry_relaxed(self.retry_relaxed)
+            .relax_after(self.relax_after)
+            .max_parked(self.max_parked)
+            .instr_set(instr_set.clone())
+            .tape_init(tape_init.clone())
+            .cases(cases.to_vec())
+            .case_check_steps(self.case_check_steps)
+            .idioms(idiom_set.to_vec())
+            .from_scratch(self.near.is_none())
+            .golf(self.golf)
+            .max_len(self.max_len)
+            .exact_halt(self.exact_halt)
+            .require_halt(self.require_halt.then_some(self.demo_steps))
+            .pareto_only(self.pareto_only)
+            .optimize_steps(self.optimize.map(|OptimizeArg::Steps| self.optimize_slack))
+            .frontier(match self.frontier {
+                FrontierArg::Heap => Frontier::Heap,
+                FrontierArg::Bucket => Frontier::Bucket {
+                    width: self.bucket_width,
+                },
+            })
+            .intern(self.intern)
+            .max_memory(self.max_memory)
+            .rollout(self.rollout)
+            .dedup(match self.dedup {
+                // Counting tells programs apart by source alone.
+                _ if self.count_only => Dedup::Source,
+                DedupArg::String => Dedup::Source,
+                DedupArg::Behavior => Dedup::Behavior {
+                    extra: self.extra,
+                    max_steps: self.demo_steps,
+                },
+            })
+            .eager(self.eager)
+            .live_length(self.live_length)
+            .max_expansions(budget.expansions)
+            .time_limit(budget.time)
+            .build()
+    }
+}
+
+struct SearchOutcome {
+    reason: StopReason,
+    searcher: Searcher,
+    shortest: Option<(String, u32)>, // shortest reported solution and its length
+    log: Option<LogResult>,
+    archived: Option<(PathBuf, usize)>, // solutions appended to the --archive file
+}
+
+/// Where --record or --replay sends the search's expansions.
+enum SearchLog {
+    Record(PathBuf, Recorder<BufWriter<File>>),
+    Replay(Replay),
+}
+
+enum LogResult {
+    Recorded(PathBuf),
+    Replayed(Result<usize, Divergence>),
+}
+
+/// The --record file, created with its header, or the --replay log read in.
+fn open_log(args: &Args, target: &[u8]) -> Result<Option<SearchLog>, String> {
+    if let Some(path) = &args.record {
+        let file = File::create(path)
+            .map_err(|e| format!("Cannot create --record file {}: {}", path.display(), e))?;
+        let recorder = Recorder::new(BufWriter::new(file), target)
+            .map_err(|e| format!("Cannot write --record file {}: {}", path.display(), e))?;
+        return Ok(Some(SearchLog::Record(path.clone(), recorder)));
+    }
+    if let Some(path) = &args.replay {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read --replay file {}: {}", path.display(), e))?;
+        let replay = Replay::parse(&text, target)
+            .map_err(|e| format!("Invalid --replay file {}: {}", path.display(), e))?;
+        return Ok(Some(SearchLog::Replay(replay)));
+    }
+    Ok(None)
+}
+
+/// The --idioms templates: the built-in ones, and unless `path` is
+/// `builtin`, those in the file.
+fn load_idioms(path: &Path) -> Result<Vec<Program>, String> {
+    let mut templates = idioms::builtin();
+    if path != Path::new("builtin") {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let more = idioms::parse_idioms(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        templates.extend(more);
+    }
+    Ok(templates)
+}
+
+/// The --archive file's entries for `target`, with a warning for each line
+/// that cannot be read.
+fn open_archive(path: &Path, target: &[u8]) -> Result<Archive, String> {
+    let archive = Archive::open(path, target)
+        .map_err(|e| format!("Cannot read --archive file {}: {}", path.display(), e))?;
+    for line in archive.skipped() {
+        eprintln!("Warning: {}:{}: skipping a corrupted --archive entry", path.display(), line);
+    }
+    Ok(archive)
+}
+
+/// --archive-list: print what the archive knows of `target`. Returns the
+/// process exit code.
+fn list_archive(path: Option<&Path>, target: &[u8]) -> i32 {
+    let Some(path) = path else {
+        eprintln!("--archive-list needs --archive FILE");
+        return 2;
+    };
+    let archive = match open_archive(path, target) {
+        Ok(archive) => archive,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    println!(
+        "{} archived solution(s) of {} in {}",
+        archive.known().len(),
+        to_dec(target),
+        path.display()
+    );
+    if !archive.known().is_empty() {
+        println!("  {:>6}  {:>10}  Program", "Length", "Steps");
+    }
+    for entry in archive.known() {
+        println!("  {:>6}  {:>10}  {}", entry.min_len, entry.steps, entry.code);
+    }
+    0
+}
+
+/// Prints each solution and asks the continue policy whether to go on.
+struct Reporter<'a> {
+    args: &'a Args,
+    target: &'a [u8],
+    policy: &'a mut prompt::ContinuePolicy,
+    found: usize,
+    shortest: Option<(String, u32)>,
+    stopped: Option<StopReason>, // why `on_solution` broke off the search
+    steer: Option<prompt::Steer>, // ... or the prompt command to carry out
+    log: Option<SearchLog>,
+    stats: Option<SnapshotFile>,
+    archive: Option<Archive>,
+    tape_init: Tape, // for demo runs from the start
+    cases: Vec<Case>, // the other --case pairs
+    gap: prompt::SolutionGap,
+    expansions: u64, // seen by `on_expansion`, for the gap
+}
+
+impl Reporter<'_> {
+    /// Whether the run should end: `cancelled` also breaks it off when only
+    /// a --stats-file snapshot is due.
+    fn stopping(&self) -> bool {
+        let replayed = matches!(&self.log, Some(SearchLog::Replay(r)) if r.done());
+        interrupt::requested() || self.policy.expired() || self.gap.ran_out(self.expansions) || replayed
+    }
+
+    /// Write a --stats-file snapshot of `searcher`.
+    fn snapshot(&mut self, searcher: &Searcher) {
+        if let (Some(file), Some(path)) = (&mut self.stats, &self.args.stats_file) {
+            if let Some(e) = file.update(searcher) {
+                eprintln!("Warning: cannot write --stats-file {}: {}", path.display(), e);
             }
         }
+    }
 
-        // Otherwise, advance this node by one step
-        // Guard against runaway nodes
-        if node.steps > args.max_steps {
-            continue;
+    /// Ask the continue policy whether to go on after the latest solution.
+    fn decide(&mut self) -> ControlFlow<()> {
+        let decision = self.policy.after_solution(self.found, &mut io::stdin().lock());
+        // Measured from here, so that the time at the prompt does not count.
+        self.gap.reset(self.expansions);
+        if interrupt::requested() {
+            self.stopped = Some(StopReason::Interrupted);
+            return ControlFlow::Break(());
         }
+        match decision {
+            prompt::Decision::Continue => ControlFlow::Continue(()),
+            prompt::Decision::Stop(reason) => {
+                self.stopped = Some(reason);
+                ControlFlow::Break(())
+            }
+            prompt::Decision::Steer(steer) => {
+                self.steer = Some(steer);
+                ControlFlow::Break(())
+            }
+        }
+    }
+}
 
-        let children = step_once(&node, &target, AdvancePolicy::Search);
+impl Callbacks for Reporter<'_> {
+    fn on_solution(&mut self, solution: &Solution) -> ControlFlow<()> {
+        self.found += 1;
+        if self.shortest.as_ref().is_none_or(|(_, len)| solution.min_len < *len) {
+            self.shortest = Some((solution.code.clone(), solution.min_len));
+        }
+        if let Some(archive) = &mut self.archive {
+            archive.add(solution);
+        }
+        println!();
+        println!("Solution #{} found:", self.found);
+        println!("Program length (inst): {}", solution.min_len);
+        println!(
+            "Steps to the target  : {} ({})",
+            solution.steps,
+            if solution.on_front {
+                "extends the Pareto front"
+            } else {
+                "dominated by an earlier solution"
+            }
+        );
+        println!("Program (Brainfuck):");
+        println!("{}", solution.code);
+        if let Some(lang) = self.args.emit {
+            println!();
+            println!("Program ({:?}):", lang);
+            print!("{}", lang.translate(&solution.program));
+        }
 
-        for child in children {
-            // Prune premature halt:
-            // If child halted (i.e., step did nothing) we'd have an empty vec from exec_known_step.
-            // Here we only get children that advanced or are non-advancing branches
-            // from expansion with Empty; detect halting outside loops:
-            let halted = matches!(child.pc.kind, PKind::Empty) && child.loop_stack.is_empty();
+        // Continue the search's run to show extrapolation (under
+        // --require-halt, all the way to the halt); --demo-steps counts
+        // from where the search left it
+        let show_limit = self.target.len() + self.args.extra;
+        let mut interp = match &solution.state {
+            Some(state) => {
+                let cap = state.steps().saturating_add(self.args.demo_steps);
+                state.clone().with_step_cap(cap)
+            }
+            None => Interpreter::new(solution.program.clone())
+                .with_tape(self.tape_init.clone())
+                .with_step_cap(self.args.demo_steps),
+        };
+        let end = if self.args.require_halt {
+            interp.run_to_halt()
+        } else {
+            interp.run_to_limit(show_limit)
+        };
+        let halted = match &end {
+            Ok(RunEnd::Halted | RunEnd::InputUnsupported) => "true".to_string(),
+            Ok(RunEnd::OutputLimit) => "false".to_string(),
+            Ok(RunEnd::StepCap) => "false, non-halting (within budget)".to_string(),
+            Err(e) => format!("no, internal error: {}", e),
+        };
+        let (steps, mut outputs) = (interp.steps(), interp.into_output());
+        outputs.truncate(show_limit);
 
-            if halted && child.correct < target.len() {
-                // premature halt: prune
-                continue;
+        println!();
+        println!(
+            "Output (first {} bytes shown):",
+            outputs.len().min(show_limit)
+        );
+        println!("DEC  : {}", to_dec(&outputs));
+        if let Some(sep) = self.args.digits_sep() {
+            print_numbers(&outputs, self.target, sep);
+        }
+        println!(
+            "Interpreter steps during demo: {} (halted: {})",
+            steps, halted
+        );
+        if self.args.exact_halt {
+            let exact = end == Ok(RunEnd::Halted) && outputs.len() == self.target.len();
+            println!("Halts right after the target: {}", if exact { "yes" } else { "no" });
+        }
+        if self.args.explain {
+            // Run again from the start, counting loop passes on the way
+            let cap = solution.steps.saturating_add(self.args.demo_steps);
+            let tape = self.tape_init.clone();
+            println!();
+            println!("Explanation (target bytes):");
+            match explain(&solution.program, tape, cap, self.target.len()) {
+                Ok(explanation) => print!("{}", explanation),
+                Err(e) => println!("  Not available: internal error: {}", e),
             }
+        }
+        if !self.cases.is_empty() {
+            let first = Case {
+                tape: self.tape_init.clone(),
+                target: self.target.to_vec(),
+            };
+            println!();
+            println!("Cases (output within --demo-steps):");
+            for case in std::iter::once(&first).chain(&self.cases) {
+                let mut run = Interpreter::new(solution.program.clone())
+                    .with_tape(case.tape.clone())
+                    .with_step_cap(self.args.demo_steps);
+                let _ = run.run_to_limit(case.target.len());
+                let ok = run.output() == case.target;
+                println!("  {}  ->  {}  {}", case, to_dec(run.output()), if ok { "ok" } else { "FAIL" });
+            }
+        }
+
+        self.decide()
+    }
+
+    fn on_unverified(&mut self, solution: &Solution, output: &[u8]) {
+        eprintln!();
+        eprintln!("!!! INTERNAL CONSISTENCY ERROR: the search reported a solution whose");
+        eprintln!("!!! program does not reproduce the target; it is not counted.");
+        eprintln!("Program: {}", solution.code);
+        eprintln!("A: target, B: program output ({} steps)", solution.steps);
+        eprint!("{}", compare::render_aligned(self.target, output));
+    }
+
+    fn on_non_halting(&mut self, solution: &Solution) {
+        if self.args.verbose {
+            println!();
+            println!(
+                "Candidate suppressed by --require-halt, non-halting (within budget): {}",
+                solution.code
+            );
+        }
+    }
 
-            // If output mismatch already pruned in exec_known_step.
+    fn on_relax(&mut self, max_steps: u64, revived: usize) {
+        println!();
+        println!(
+            "No solution yet: relaxed --max-steps to {}, {} set-aside node(s) back in the search",
+            max_steps, revived
+        );
+    }
+
+    fn on_expansion(&mut self, expansion: &Expansion) {
+        self.expansions += 1;
+        match &mut self.log {
+            Some(SearchLog::Record(_, recorder)) => recorder.record(expansion),
+            Some(SearchLog::Replay(replay)) => replay.check(expansion),
+            None => {}
+        }
+    }
+
+    fn cancelled(&mut self) -> bool {
+        self.stopping() || self.stats.as_ref().is_some_and(SnapshotFile::due)
+    }
+}
 
-            if child.steps > args.max_steps {
-                continue;
+/// Run a search for `target`, reporting each new solution as it is found
+/// and asking `policy` whether to go on. Solutions in `archive` are skipped,
+/// and the new ones added to it.
+fn search(
+    args: &Args,
+    config: SearchConfig,
+    policy: &mut prompt::ContinuePolicy,
+    log: Option<SearchLog>,
+    archive: Option<Archive>,
+) -> SearchOutcome {
+    let target = config.target().to_vec();
+    let tape_init = config.tape_init().clone();
+    let cases = config.cases().to_vec();
+    let mut searcher = Searcher::new(config);
+    #[cfg(feature = "testing")]
+    if let Some(path) = &args.inject {
+        let json = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let node = bf_search::snapshot::from_json(&json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        searcher.inject(node);
+    }
+    if let Some(archive) = &archive {
+        archive.seed(&mut searcher);
+        println!(
+            "Archive: {} solution(s) known from earlier runs, not reported again",
+            archive.known().len()
+        );
+    }
+    let mut reporter = Reporter {
+        args,
+        target: &target,
+        policy,
+        found: 0,
+        shortest: None,
+        stopped: None,
+        steer: None,
+        log,
+        stats: stats_file(args),
+        archive,
+        tape_init,
+        cases,
+        gap: args.solution_gap(),
+        expansions: 0,
+    };
+
+    let reason = loop {
+        let reason = searcher.run(&mut reporter);
+        if reason == bf_search::StopReason::Cancelled && !reporter.stopping() {
+            reporter.snapshot(&searcher);
+            continue;
+        }
+        if reporter.steer.is_none() {
+            break reason;
+        }
+        // A command at the prompt broke off the run: carry it out and ask
+        // again, until the answer is to go on or to stop
+        while let Some(command) = reporter.steer.take() {
+            steer(&mut searcher, command);
+            let _ = reporter.decide();
+        }
+        if reporter.stopped.is_some() {
+            break reason;
+        }
+    };
+    let reason = match reason {
+        _ if interrupt::requested() => StopReason::Interrupted,
+        bf_search::StopReason::Stopped => reporter.stopped.unwrap_or(StopReason::Quit),
+        bf_search::StopReason::Cancelled => match &reporter.log {
+            Some(SearchLog::Replay(r)) if r.finish().is_err() => StopReason::Diverged,
+            Some(SearchLog::Replay(r)) if r.done() => StopReason::ReplayEnd,
+            _ if reporter.gap.ran_out(reporter.expansions) => StopReason::SolutionGap,
+            _ => StopReason::AutoContinueElapsed,
+        },
+        bf_search::StopReason::Budget => StopReason::Budget,
+        bf_search::StopReason::Exhausted => StopReason::Exhausted,
+        bf_search::StopReason::Aborted(e) => StopReason::Aborted(e),
+    };
+
+    if let (Some(file), Some(path)) = (reporter.stats.take(), &args.stats_file) {
+        if let Err(e) = file.finish(&searcher) {
+            eprintln!("Warning: cannot write --stats-file {}: {}", path.display(), e);
+        }
+    }
+    let log = match reporter.log {
+        Some(SearchLog::Record(path, recorder)) => {
+            if let Err(e) = recorder.finish() {
+                eprintln!("Cannot write --record file {}: {}", path.display(), e);
             }
+            Some(LogResult::Recorded(path))
+        }
+        Some(SearchLog::Replay(replay)) => Some(LogResult::Replayed(replay.finish())),
+        None => None,
+    };
+    let archived = reporter.archive.and_then(|mut archive| match archive.save() {
+        Ok(n) => Some((archive.path().to_path_buf(), n)),
+        Err(e) => {
+            eprintln!("Cannot write --archive file {}: {}", archive.path().display(), e);
+            None
+        }
+    });
+    SearchOutcome {
+        reason,
+        searcher,
+        shortest: reporter.shortest,
+        log,
+        archived,
+    }
+}
+
+/// Run a --portfolio search: one searcher per entry of `weights`, taking
+/// turns, each new solution reported as `search` does, with the member that
+/// found it; then a summary comparing the members. Returns the process exit
+/// code.
+fn run_portfolio(
+    args: &Args,
+    config: SearchConfig,
+    weights: &[Weights],
+    policy: &mut prompt::ContinuePolicy,
+    archive: Option<Archive>,
+) -> i32 {
+    let target = config.target().to_vec();
+    let mut portfolio = match Portfolio::new(&config, weights) {
+        Ok(p) => p.with_slice(args.portfolio_slice).with_budget(args.portfolio_budget),
+        Err(e) => {
+            eprintln!("Invalid --portfolio: {}", e);
+            return 2;
+        }
+    };
+    let budget = match args.portfolio_budget {
+        Some(n) => format!(", {} in all", n),
+        None => String::new(),
+    };
+    println!(
+        "Portfolio: {} member(s) taking turns of {} expansion(s){}",
+        weights.len(),
+        args.portfolio_slice,
+        budget
+    );
+    if let Some(archive) = &archive {
+        for entry in archive.known() {
+            portfolio.mark_seen(&entry.code);
+        }
+        println!(
+            "Archive: {} solution(s) known from earlier runs, not reported again",
+            archive.known().len()
+        );
+    }
+    let mut reporter = Reporter {
+        args,
+        target: &target,
+        policy,
+        found: 0,
+        shortest: None,
+        stopped: None,
+        steer: None,
+        log: None,
+        stats: None,
+        archive,
+        tape_init: config.tape_init().clone(),
+        cases: config.cases().to_vec(),
+        gap: args.solution_gap(),
+        expansions: 0,
+    };
+    let started = Instant::now();
+
+    let reason = loop {
+        let next = portfolio.next_solution_until(|| reporter.stopping());
+        let Some((member, solution)) = next else {
+            break match portfolio.stop_reason() {
+                _ if interrupt::requested() => StopReason::Interrupted,
+                Some(bf_search::StopReason::Budget) => StopReason::Budget,
+                Some(bf_search::StopReason::Aborted(e)) => StopReason::Aborted(e),
+                Some(bf_search::StopReason::Cancelled) if reporter.gap.ran_out(0) => StopReason::SolutionGap,
+                Some(bf_search::StopReason::Cancelled) => StopReason::AutoContinueElapsed,
+                _ => StopReason::Exhausted,
+            };
+        };
+        println!();
+        println!(
+            "Found by member {} ({}):",
+            member + 1,
+            portfolio.members()[member].label()
+        );
+        if reporter.on_solution(&solution).is_continue() {
+            continue;
+        }
+        // The weights are the members' own here: no steering
+        while reporter.steer.take().is_some() {
+            println!("  Not available with --portfolio; each member keeps its weights");
+            let _ = reporter.decide();
+        }
+        if let Some(reason) = reporter.stopped {
+            break reason;
+        }
+    };
+
+    println!();
+    println!("Portfolio summary:");
+    let why = match reason {
+        StopReason::Exhausted => "every member's search space exhausted",
+        StopReason::Quit => "quit by user",
+        StopReason::SolutionLimit => "requested number of solutions found",
+        StopReason::AutoContinueElapsed => "auto-continue time elapsed",
+        StopReason::SolutionGap => "no new solution within the --solution-gap",
+        StopReason::Budget => "--portfolio-budget used up",
+        StopReason::Interrupted => "interrupted (Ctrl+C)",
+        StopReason::Aborted(_) => "aborted by an internal error",
+        StopReason::ReplayEnd | StopReason::Diverged => unreachable!("no --replay with --portfolio"),
+    };
+    println!("  Stopped       : {}", why);
+    println!(
+        "  Expansions    : {} over all members in {:.2?}",
+        portfolio.expansions(),
+        started.elapsed()
+    );
+    println!("  Solutions     : {}", reporter.found);
+    if let Some(mut archive) = reporter.archive.take() {
+        match archive.save() {
+            Ok(n) => println!("  Archived      : {} new solution(s) to {}", n, archive.path().display()),
+            Err(e) => eprintln!("Cannot write --archive file {}: {}", archive.path().display(), e),
+        }
+    }
+    println!(
+        "  {:>6}  {:<16}  {:>10}  {:>9}  {:>8}  Status",
+        "Member", "Weights", "Expansions", "Solutions", "Shortest"
+    );
+    for (i, member) in portfolio.members().iter().enumerate() {
+        let status = match member.stopped() {
+            None => "running",
+            Some(bf_search::StopReason::Exhausted) => "exhausted",
+            Some(bf_search::StopReason::Budget) => "budget",
+            Some(_) => "aborted",
+        };
+        let shortest = member.searcher().front().entries().first().map(|e| e.min_len);
+        println!(
+            "  {:>6}  {:<16}  {:>10}  {:>9}  {:>8}  {}",
+            i + 1,
+            member.label(),
+            member.searcher().stats().expansions,
+            member.solutions(),
+            shortest.map_or("-".to_string(), |len| len.to_string()),
+            status
+        );
+    }
+    match reason {
+        StopReason::Interrupted => EXIT_STOPPED,
+        StopReason::Aborted(_) => EXIT_INTERNAL,
+        _ => 0,
+    }
+}
+
+/// An estimate in `SearchStats::memory`, for people.
+fn mib(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1 << 20) as f64)
+}
 
-            let score_val = child.score(args.beta, args.gamma);
-            // Guard against NaN
-            let score = match NotNan::new(score_val) {
-                Ok(s) => s,
-                Err(_) => continue,
+/// Carry out a command typed at the prompt between solutions.
+fn steer(searcher: &mut Searcher, command: prompt::Steer) {
+    let (beta, gamma) = (searcher.config().beta(), searcher.config().gamma());
+    let weights = match command {
+        prompt::Steer::Beta(beta) => (beta, gamma),
+        prompt::Steer::Gamma(gamma) => (beta, gamma),
+        prompt::Steer::Show => {
+            let stats = searcher.stats();
+            println!("  beta = {}, gamma = {}", beta, gamma);
+            let memory = match searcher.config().max_memory() {
+                Some(_) => format!(", about {}", mib(stats.memory)),
+                None => String::new(),
             };
+            println!(
+                "  Frontier: {} open node(s){}; {} expansion(s), {} node(s) pushed",
+                searcher.frontier_len(),
+                memory,
+                stats.expansions,
+                stats.pushed
+            );
+            if let Some(best) = searcher.best() {
+                println!("  Best node: {}", best.score_breakdown(beta, gamma));
+            }
+            return;
+        }
+    };
+    match searcher.set_weights(weights.0, weights.1) {
+        Ok(()) => println!(
+            "  Re-scored {} open node(s) with beta = {}, gamma = {}",
+            searcher.frontier_len(),
+            weights.0,
+            weights.1
+        ),
+        Err(e) => println!("  Not changed: {}", e),
+    }
+}
+
+fn parse_target_line(line: &str) -> Result<Option<Vec<u8>>, String> {
+    let line = line.split('#').next().unwrap_or("").trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let bytes = if let Some(hex) = line.strip_prefix("hex:") {
+        parse_hex_bytes(hex)?
+    } else {
+        line.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .map(|t| {
+                t.parse::<u8>()
+                    .map_err(|_| format!("'{}' is not a decimal byte (0..=255)", t))
+            })
+            .collect::<Result<Vec<u8>, String>>()?
+    };
+    if bytes.is_empty() {
+        return Err("empty target".into());
+    }
+    Ok(Some(bytes))
+}
 
-            heap.push(HeapItem {
-                score,
-                seq: seq_counter,
-                node: child,
+struct BatchRow {
+    line: usize,
+    target: Vec<u8>,
+    status: &'static str,
+    shortest: Option<(String, u32)>,
+    expansions: u64,
+}
+
+/// Search every target in `path` in turn with the same parameters, then print
+/// a summary table. Returns the process exit code.
+fn run_batch(args: &Args, instr_set: &InstrSet, tape_init: &Tape, idiom_set: &[Program], path: &Path) -> i32 {
+    let text = match std::fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Cannot read targets file {}: {}", path.display(), e);
+            return 2;
+        }
+    };
+    let mut targets = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        match parse_target_line(line) {
+            Ok(Some(t)) => targets.push((i + 1, t)),
+            Ok(None) => {}
+            Err(e) => eprintln!(
+                "Warning: {}:{}: skipping target: {}",
+                path.display(),
+                i + 1,
+                e
+            ),
+        }
+    }
+
+    println!("Targets: {} from {}", targets.len(), path.display());
+    print_parameters(args, instr_set, tape_init);
+    let budget = Budget {
+        expansions: args.per_target_expansions,
+        time: args.per_target_secs.map(Duration::from_secs_f64),
+    };
+
+    let mut rows = Vec::new();
+    let mut interrupted = false;
+    let mut aborted = false;
+    for (n, (line, target)) in targets.into_iter().enumerate() {
+        println!();
+        let target = args.encode_target(target);
+        let shown = if args.as_digits { to_text(&target) } else { to_dec(&target) };
+        println!("=== Target {} (line {}): {} ===", n + 1, line, shown);
+        if let Some(reason) = instr_set.unsolvable_reason(&target, tape_init) {
+            println!("Unsolvable with this instruction set: {}.", reason);
+            rows.push(BatchRow {
+                line,
+                target,
+                status: "unsolvable",
+                shortest: None,
+                expansions: 0,
             });
-            seq_counter = seq_counter.wrapping_add(1);
+            continue;
         }
+        let mut policy =
+            prompt::ContinuePolicy::new(false, args.solutions, args.auto_continue_secs, args.solution_gap().is_set());
+        let config = match args.search_config(&target, instr_set, tape_init, idiom_set, &[], &budget) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Invalid parameters: {}", e);
+                return 2;
+            }
+        };
+        let archive = match args.archive.as_deref().map(|path| open_archive(path, &target)) {
+            Some(Ok(archive)) => Some(archive),
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+            None => None,
+        };
+        let outcome = search(args, config, &mut policy, None, archive);
+        print_summary(&outcome, &target);
+        let status = match (&outcome.shortest, outcome.reason) {
+            (Some(_), _) => "solved",
+            (None, StopReason::Budget) => "budget",
+            (None, StopReason::Interrupted) => "interrupted",
+            (None, StopReason::Aborted(_)) => "error",
+            (None, _) => "unsolved",
+        };
+        rows.push(BatchRow {
+            line,
+            target,
+            status,
+            shortest: outcome.shortest,
+            expansions: outcome.searcher.stats().expansions,
+        });
+        match outcome.reason {
+            StopReason::Interrupted => {
+                interrupted = true;
+                break;
+            }
+            StopReason::Aborted(_) => aborted = true,
+            _ => {}
+        }
+    }
+
+    println!();
+    println!("Batch summary:");
+    println!(
+        "  {:>5}  {:<11}  {:>4}  {:>11}  {:<24}  program",
+        "line", "status", "len", "expansions", "target"
+    );
+    for row in &rows {
+        let mut target = to_dec(&row.target);
+        if target.len() > 24 {
+            target.truncate(21);
+            target.push_str("...");
+        }
+        let (code, len) = match &row.shortest {
+            Some((code, len)) => (code.as_str(), len.to_string()),
+            None => ("-", "-".to_string()),
+        };
+        println!(
+            "  {:>5}  {:<11}  {:>4}  {:>11}  {:<24}  {}",
+            row.line, row.status, len, row.expansions, target, code
+        );
+    }
+    let solved = rows.iter().filter(|r| r.shortest.is_some()).count();
+    println!("  Solved {} of {} targets.", solved, rows.len());
+
+    if interrupted {
+        EXIT_STOPPED
+    } else if aborted {
+        EXIT_INTERNAL
+    } else {
+        0
     }
 }
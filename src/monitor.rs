@@ -0,0 +1,194 @@
+//! Progress snapshots of a running search, for watching it from outside the
+//! process.
+//!
+//! A `Snapshot` is a small serializable summary of a `Searcher`. A
+//! `SnapshotFile` keeps one on disk, replaced every so often between runs:
+//! each is written to a temporary file beside the target and renamed over
+//! it, so a reader sees the previous snapshot or the next, never part of
+//! one. Serializing and writing happen on a background thread; a snapshot
+//! taken while the previous one is still being written is dropped rather
+//! than waited for. A failing write is reported to the caller and the
+//! search goes on.
+//!
+//! ```
+//! use bf_search::monitor::{Snapshot, SnapshotFile};
+//! use bf_search::{SearchConfig, Searcher};
+//! use std::time::Duration;
+//!
+//! let path = std::env::temp_dir().join(format!("bf_search-doc-{}.json", std::process::id()));
+//! let mut file = SnapshotFile::new(&path, Duration::ZERO);
+//! let mut searcher = Searcher::new(SearchConfig::new(vec![3, 6, 9]).build().unwrap());
+//! let read = || -> Option<Snapshot> {
+//!     Some(serde_json::from_str(&std::fs::read_to_string(&path).ok()?).unwrap())
+//! };
+//!
+//! // a reader polling the file only ever sees whole snapshots
+//! let reader = {
+//!     let path = path.clone();
+//!     std::thread::spawn(move || {
+//!         for _ in 0..2000 {
+//!             if let Ok(text) = std::fs::read_to_string(&path) {
+//!                 serde_json::from_str::<Snapshot>(&text).expect("a whole snapshot");
+//!             }
+//!         }
+//!     })
+//! };
+//! let mut seen = 0;
+//! for solution in 0..3 {
+//!     searcher.next_solution().unwrap();
+//!     assert!(file.due());
+//!     assert!(file.update(&searcher).is_none());
+//!     // wait for the writer, then check the file caught up
+//!     while read().is_none_or(|s| s.solutions != solution + 1) {
+//!         std::thread::sleep(Duration::from_millis(1));
+//!         file.update(&searcher);
+//!     }
+//!     assert!(read().unwrap().expansions > seen);
+//!     seen = read().unwrap().expansions;
+//! }
+//! reader.join().unwrap();
+//! file.finish(&searcher).unwrap();
+//! let last = read().unwrap();
+//! assert_eq!((last.expansions, last.solutions), (searcher.stats().expansions, 3));
+//! assert_eq!(last.best_correct, Some(3));
+//! assert!(last.best_program.is_some() && last.best_score.is_some());
+//! assert_eq!(last.front, searcher.front().entries());
+//! std::fs::remove_file(&path).unwrap();
+//!
+//! // a path that cannot be written is reported, and does not stop anything
+//! let bad = std::env::temp_dir().join("bf_search-no-such-dir").join("stats.json");
+//! let mut file = SnapshotFile::new(&bad, Duration::from_secs(3600));
+//! file.update(&searcher);
+//! assert!(!file.due());
+//! assert!(file.finish(&searcher).is_err());
+//! ```
+
+use crate::pareto::FrontEntry;
+use crate::{ProgramNode, Searcher};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Where a search stands.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub uptime_secs: f64,
+    pub expansions: u64,
+    pub expansions_per_sec: f64, // averaged over the uptime
+    pub frontier: usize, // open nodes
+    pub memory: usize, // their estimated bytes (`SearchStats::memory`)
+    pub solutions: usize,
+    pub best_score: Option<f64>, // `Searcher::best`, under the current β and γ
+    pub best_correct: Option<usize>,
+    pub best_program: Option<String>, // with its holes, as `?`
+    pub front: Vec<FrontEntry>, // `Searcher::front`, shortest first
+}
+
+impl Snapshot {
+    pub fn of(searcher: &Searcher) -> Snapshot {
+        let stats = searcher.stats();
+        let uptime = stats.started.elapsed().as_secs_f64();
+        let best = searcher.best();
+        Snapshot {
+            uptime_secs: uptime,
+            expansions: stats.expansions,
+            expansions_per_sec: if uptime > 0.0 { stats.expansions as f64 / uptime } else { 0.0 },
+            frontier: searcher.frontier_len(),
+            memory: stats.memory,
+            solutions: stats.solutions,
+            best_score: searcher.best_score(),
+            best_correct: best.map(|node| node.correct),
+            best_program: best.map(|node| ProgramNode::to_bf_string(&node.root)),
+            front: searcher.front().entries().to_vec(),
+        }
+    }
+}
+
+/// A file holding the latest `Snapshot` of a search, rewritten at most once
+/// per interval.
+pub struct SnapshotFile {
+    interval: Duration,
+    next: Instant,
+    queue: Option<SyncSender<Snapshot>>,
+    failures: Receiver<io::Error>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl SnapshotFile {
+    /// Start the writer thread; the first snapshot is due at once.
+    pub fn new(path: &Path, interval: Duration) -> SnapshotFile {
+        let (queue, snapshots) = mpsc::sync_channel(1);
+        let (failed, failures) = mpsc::channel();
+        let path = path.to_path_buf();
+        let writer = std::thread::spawn(move || {
+            let mut last = Ok(());
+            for snapshot in snapshots {
+                let result = write(&path, &snapshot);
+                // Report only the first failure of a run of them.
+                if let (Ok(()), Err(e)) = (&last, &result) {
+                    let _ = failed.send(io::Error::new(e.kind(), e.to_string()));
+                }
+                last = result;
+            }
+            last
+        });
+        SnapshotFile {
+            interval,
+            next: Instant::now(),
+            queue: Some(queue),
+            failures,
+            writer: Some(writer),
+        }
+    }
+
+    /// Whether the interval since the last `update` has passed.
+    pub fn due(&self) -> bool {
+        Instant::now() >= self.next
+    }
+
+    /// Hand a snapshot of `searcher` to the writer, unless it is still busy
+    /// with the last one, and restart the interval. Returns the error of an
+    /// earlier write that failed, once per run of failures.
+    pub fn update(&mut self, searcher: &Searcher) -> Option<io::Error> {
+        self.next = Instant::now() + self.interval;
+        if let Some(queue) = &self.queue {
+            let _ = queue.try_send(Snapshot::of(searcher));
+        }
+        self.failures.try_recv().ok()
+    }
+
+    /// Write a last snapshot of `searcher`, waiting for it, and stop the
+    /// writer. Returns the error of the last write, if it failed.
+    pub fn finish(mut self, searcher: &Searcher) -> io::Result<()> {
+        if let Some(queue) = self.queue.take() {
+            let _ = queue.send(Snapshot::of(searcher));
+        }
+        match self.writer.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(io::Error::other("the snapshot writer panicked")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for SnapshotFile {
+    fn drop(&mut self) {
+        self.queue = None; // ends the writer's loop
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Replace `path` with `snapshot` in one rename.
+fn write(path: &Path, snapshot: &Snapshot) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut json = serde_json::to_string(snapshot).map_err(io::Error::other)?;
+    json.push('\n');
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)
+}
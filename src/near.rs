@@ -0,0 +1,146 @@
+//! Neighbourhood seeds: a concrete program with parts of it cut out, for
+//! searching near a program that almost works instead of from scratch (see
+//! `SearchConfigBuilder::idioms`, which takes the variants as seeds).
+//!
+//! A hole stands for the rest of its block, so a cut region is a block's
+//! tail: from some command to the `]` closing the block, or to the end of
+//! the program. A region may be empty (the hole then only appends), and
+//! may take whole loops with it. A variant cuts up to `holes` regions
+//! that do not overlap:
+//!
+//! ```
+//! use bf_search::near::variants;
+//!
+//! let codes = |src: &str, holes| -> Vec<String> {
+//!     variants(&src.parse().unwrap(), holes).iter().map(|p| p.to_string()).collect()
+//! };
+//! assert_eq!(codes("+[.+]", 1), ["+[.+?]", "+[.?]", "+[?]", "+[.+]?", "+?"]);
+//! let two = codes("+[.+]", 2);
+//! assert_eq!(two.len(), 5 + 3);
+//! assert!(two.contains(&"+[.?]?".to_string()));
+//! ```
+//!
+//! Seeded in place of the empty program, the variants of a program for
+//! `1 2 3 5` find one for `1 2 3 4` in fewer expansions than a cold
+//! search. They are scored like any other node, so the search still
+//! prefers the best program it can reach from them, here `+[.+]` from
+//! `+?`, over the one-byte fix of the original:
+//!
+//! ```
+//! use bf_search::near::variants;
+//! use bf_search::{SearchConfig, Searcher};
+//!
+//! let mut almost = Searcher::new(SearchConfig::new(vec![1, 2, 3, 5]).build().unwrap());
+//! let program = almost.next_solution().unwrap().program;
+//! assert_eq!(program.to_string(), "+.+.+.++.");
+//!
+//! let mut cold = Searcher::new(SearchConfig::new(vec![1, 2, 3, 4]).build().unwrap());
+//! assert_eq!(cold.next_solution().unwrap().code, "+[.+]");
+//! let seeds = variants(&bf_search::Program { root: program }, 1);
+//! let config = SearchConfig::new(vec![1, 2, 3, 4]).idioms(seeds).from_scratch(false);
+//! let mut near = Searcher::new(config.build().unwrap());
+//! assert_eq!(near.next_solution().unwrap().code, "+[.+]");
+//! assert!(near.stats().expansions * 3 < cold.stats().expansions * 2);
+//!
+//! // under a lighter length weight, the fix itself
+//! let mut cold = Searcher::new(SearchConfig::new(vec![1, 2, 3, 4]).beta(0.2).build().unwrap());
+//! let seeds = variants(&"+.+.+.++.".parse().unwrap(), 1);
+//! let config = SearchConfig::new(vec![1, 2, 3, 4]).beta(0.2).idioms(seeds).from_scratch(false);
+//! let mut near = Searcher::new(config.build().unwrap());
+//! assert_eq!(cold.next_solution().unwrap().code, "+.+.+.+.");
+//! assert_eq!(near.next_solution().unwrap().code, "+.+.+.+.");
+//! assert!(near.stats().expansions * 2 < cold.stats().expansions);
+//! ```
+
+use crate::{Program, ProgramNode};
+use std::collections::HashSet;
+
+/// A block's tail, as a byte range of the program's text.
+#[derive(Clone, Copy)]
+struct Region {
+    start: usize,
+    end: usize,
+    depth: usize, // loops around the block
+}
+
+/// The variants of `program` with 1 to `holes` regions replaced by holes,
+/// without repeats. Fewer holes come first; among the same number, regions
+/// in deeper loops, then nearer the end, which is where a program that
+/// almost works usually goes wrong.
+pub fn variants(program: &Program, holes: usize) -> Vec<Program> {
+    let text = ProgramNode::to_bf_string(&program.root);
+    let mut regions = regions(&text);
+    regions.sort_by_key(|r| (std::cmp::Reverse(r.depth), std::cmp::Reverse(r.start)));
+    let mut cuts: Vec<Vec<Region>> = Vec::new();
+    let mut chosen = Vec::new();
+    choose(&regions, holes, &mut chosen, &mut cuts);
+    cuts.sort_by_key(|cut| cut.len()); // stable: keeps the region order within
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for mut cut in cuts {
+        cut.sort_by_key(|r| std::cmp::Reverse(r.start));
+        let mut code = text.clone();
+        for region in cut {
+            code.replace_range(region.start..region.end, "?");
+        }
+        if seen.insert(code.clone()) {
+            out.push(code.parse().expect("a block's tail is a valid hole"));
+        }
+    }
+    out
+}
+
+/// Every tail of every block of `text` (a concrete program without
+/// comments), the empty ones included.
+fn regions(text: &str) -> Vec<Region> {
+    let mut regions = Vec::new();
+    // Per open block: byte offsets of its commands.
+    let mut blocks: Vec<Vec<usize>> = vec![Vec::new()];
+    let mut close = |commands: Vec<usize>, end: usize, depth: usize| {
+        for &start in commands.iter().chain([&end]) {
+            regions.push(Region { start, end, depth });
+        }
+    };
+    for (pos, c) in text.char_indices() {
+        match c {
+            '[' => {
+                blocks.last_mut().unwrap().push(pos);
+                blocks.push(Vec::new());
+            }
+            ']' => {
+                let depth = blocks.len() - 1;
+                close(blocks.pop().unwrap(), pos, depth);
+            }
+            _ => blocks.last_mut().unwrap().push(pos),
+        }
+    }
+    close(blocks.pop().unwrap(), text.len(), 0);
+    // Cutting the whole program is starting from scratch.
+    regions.retain(|r| r.start > 0 || r.depth > 0);
+    regions
+}
+
+/// Extend `chosen` by regions after its last one (by index in `regions`)
+/// that overlap none of it, up to `holes` in all, pushing each set.
+fn choose(regions: &[Region], holes: usize, chosen: &mut Vec<usize>, out: &mut Vec<Vec<Region>>) {
+    if chosen.len() == holes {
+        return;
+    }
+    let from = chosen.last().map_or(0, |&i| i + 1);
+    for i in from..regions.len() {
+        let r = regions[i];
+        // Tails of one block always overlap; an empty tail touching another
+        // region's edge would make two holes in a row.
+        let clear = chosen.iter().all(|&j| {
+            let o = regions[j];
+            r.end < o.start || o.end < r.start
+        });
+        if clear {
+            chosen.push(i);
+            out.push(chosen.iter().map(|&j| regions[j]).collect());
+            choose(regions, holes, chosen, out);
+            chosen.pop();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! The Pareto front of solutions by length and running time: the programs
+//! no other is both as short as and as fast as.
+//!
+//! A `Searcher` keeps one over the solutions it reports (see
+//! `Searcher::front`), with `Solution::steps` as the running time: the
+//! steps taken to print the whole target. Entries are kept shortest first,
+//! and so slowest first.
+//!
+//! ```
+//! use bf_search::pareto::{FrontEntry, ParetoFront};
+//!
+//! let entry = |min_len, steps| FrontEntry { min_len, steps, code: format!("{}/{}", min_len, steps) };
+//! let points = |front: &ParetoFront| -> Vec<(u32, u64)> {
+//!     front.entries().iter().map(|e| (e.min_len, e.steps)).collect()
+//! };
+//! let mut front = ParetoFront::new();
+//! assert!(front.insert(entry(10, 100)));
+//! assert!(front.insert(entry(12, 50))); // longer but faster
+//! assert!(front.insert(entry(8, 400))); // shorter but slower
+//! assert_eq!(points(&front), [(8, 400), (10, 100), (12, 50)]);
+//!
+//! // dominated: no shorter than (10, 100) and no faster
+//! assert!(front.dominated(11, 100) && front.dominated(10, 120));
+//! assert!(!front.insert(entry(11, 120)));
+//! // ties: as good as an entry on both axes is no improvement
+//! assert!(!front.insert(entry(10, 100)));
+//! // tied on one axis, better on the other: the old entry goes
+//! assert!(front.insert(entry(10, 90)));
+//! assert!(front.insert(entry(9, 50)));
+//! assert_eq!(points(&front), [(8, 400), (9, 50)]);
+//! assert!(front.insert(entry(1, 1)));
+//! assert_eq!(points(&front), [(1, 1)]);
+//! assert_eq!(front.entries()[0].code, "1/1");
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// A solution on the front.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrontEntry {
+    pub min_len: u32,
+    pub steps: u64,
+    pub code: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ParetoFront {
+    entries: Vec<FrontEntry>, // by `min_len` ascending, so `steps` descending
+}
+
+impl ParetoFront {
+    pub fn new() -> ParetoFront {
+        ParetoFront::default()
+    }
+
+    pub fn entries(&self) -> &[FrontEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether some entry is at least as short and at least as fast.
+    pub fn dominated(&self, min_len: u32, steps: u64) -> bool {
+        // The fastest entry no longer than `min_len` is the last of them.
+        let shorter = self.entries.partition_point(|e| e.min_len <= min_len);
+        shorter > 0 && self.entries[shorter - 1].steps <= steps
+    }
+
+    /// Add `entry` unless it is dominated, dropping the entries it
+    /// dominates. Returns whether it was added.
+    pub fn insert(&mut self, entry: FrontEntry) -> bool {
+        if self.dominated(entry.min_len, entry.steps) {
+            return false;
+        }
+        self.entries.retain(|e| e.min_len < entry.min_len || e.steps < entry.steps);
+        let at = self.entries.partition_point(|e| e.min_len < entry.min_len);
+        self.entries.insert(at, entry);
+        true
+    }
+}
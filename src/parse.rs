@@ -0,0 +1,88 @@
+//! Brainfuck source -> `ProgramNode`. Non-command characters are comments.
+//!
+//! Partial programs may use `?` for a hole. A hole stands for the rest of
+//! its block, so it must be the last command before `]` or the end:
+//!
+//! ```
+//! use bf_search::parse::parse_partial;
+//! use bf_search::ProgramNode;
+//!
+//! let root = parse_partial("+[>?]<?").unwrap();
+//! assert_eq!(ProgramNode::to_bf_string(&root), "+[>?]<?");
+//! assert_eq!(root.size, 6);
+//! let err = parse_partial("+?-").err().unwrap();
+//! assert_eq!(err, "'?' at byte 1 is not the last command of its block");
+//! ```
+
+use crate::{Instr, ProgramNode, Shared};
+
+// A command of an open block. A loop's body is built when its `]` is
+// reached, so nothing recurses along a long program.
+enum Item {
+    Instr(Instr),
+    Loop(Shared<ProgramNode>),
+    Hole(usize), // byte offset of the `?`
+}
+
+/// Parse a concrete program. `?` is a comment here.
+pub fn parse_program(src: &str) -> Result<Shared<ProgramNode>, String> {
+    parse(src, false)
+}
+
+/// Parse a partial program with `?` holes.
+pub fn parse_partial(src: &str) -> Result<Shared<ProgramNode>, String> {
+    parse(src, true)
+}
+
+fn parse(src: &str, holes: bool) -> Result<Shared<ProgramNode>, String> {
+    // Stack of open blocks, each with the byte offset of its '['.
+    let mut stack: Vec<(Vec<Item>, usize)> = vec![(Vec::new(), 0)];
+    for (pos, c) in src.char_indices() {
+        if let Some(&Item::Hole(at)) = stack.last().unwrap().0.last() {
+            if c != ']' && (c == '[' || c == '?' && holes || Instr::from_char(c).is_some()) {
+                return Err(format!("'?' at byte {} is not the last command of its block", at));
+            }
+        }
+        match c {
+            '[' => stack.push((Vec::new(), pos)),
+            '?' if holes => stack.last_mut().unwrap().0.push(Item::Hole(pos)),
+            ']' => {
+                if stack.len() == 1 {
+                    return Err(format!("unmatched ']' at byte {}", pos));
+                }
+                let (body, _) = stack.pop().unwrap();
+                stack.last_mut().unwrap().0.push(Item::Loop(close(body)));
+            }
+            _ => {
+                if let Some(i) = Instr::from_char(c) {
+                    stack.last_mut().unwrap().0.push(Item::Instr(i));
+                }
+            }
+        }
+    }
+    if stack.len() > 1 {
+        return Err(format!("unmatched '[' at byte {}", stack.last().unwrap().1));
+    }
+    let (items, _) = stack.pop().unwrap();
+    Ok(close(items))
+}
+
+/// Link a finished block's commands, ending it with the hole or else with
+/// `Empty`.
+fn close(mut items: Vec<Item>) -> Shared<ProgramNode> {
+    let mut node = match items.last() {
+        Some(&Item::Hole(_)) => {
+            items.pop();
+            ProgramNode::hole()
+        }
+        _ => ProgramNode::empty(),
+    };
+    for item in items.into_iter().rev() {
+        node = match item {
+            Item::Instr(i) => ProgramNode::instr(i, node),
+            Item::Loop(body) => ProgramNode::looped(body, node),
+            Item::Hole(_) => unreachable!("a hole ends its block"),
+        };
+    }
+    node
+}
@@ -0,0 +1,283 @@
+//! Several searches for one target with different score weights, taking
+//! turns on one thread, since which β and γ suit a target is hard to guess.
+//!
+//! Each member is a `Searcher` of its own, with its own frontier; they
+//! share which programs were reported, so a solution found by one member is
+//! reported once and never again by another, and credited to the member
+//! that found it. Members take turns of `with_slice` expansions each, in
+//! order, skipping those whose search has ended. `next_solution` returns
+//! `None` once every member has run out, or the portfolio's shared budget
+//! (`with_budget`) has.
+//!
+//! ```
+//! use bf_search::portfolio::{parse_portfolio, Portfolio};
+//! use bf_search::{SearchConfig, StopReason};
+//! use std::collections::HashSet;
+//!
+//! let base = SearchConfig::new(vec![1, 2]).build().unwrap();
+//! // the same weights twice: both members find the same programs first
+//! let weights = parse_portfolio("b=1,g=1;b=1,g=1").unwrap();
+//! let mut portfolio = Portfolio::new(&base, &weights).unwrap().with_slice(20).with_budget(Some(2000));
+//! let mut reported = Vec::new();
+//! while let Some((member, solution)) = portfolio.next_solution() {
+//!     reported.push((member, solution.code));
+//! }
+//! assert_eq!(portfolio.stop_reason(), Some(StopReason::Budget));
+//! assert_eq!(reported[0], (0, "+.+.".to_string()));
+//! // each program reported exactly once, by whichever member got there first
+//! let codes: HashSet<_> = reported.iter().map(|(_, code)| code).collect();
+//! assert_eq!(codes.len(), reported.len());
+//! let members = portfolio.members();
+//! assert_eq!(members[0].solutions() + members[1].solutions(), reported.len());
+//! assert!(members.iter().all(|m| m.solutions() > 0));
+//! let per_member: Vec<u64> = members.iter().map(|m| m.searcher().stats().expansions).collect();
+//! assert_eq!(per_member.iter().sum::<u64>(), 2000);
+//! assert_eq!(portfolio.expansions(), 2000);
+//! assert_eq!(members[1].label(), "b=1,g=1");
+//! ```
+
+use crate::{Callbacks, ConfigError, Expansion, SearchConfig, Searcher, Solution, StopReason};
+use std::fmt;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+/// One member's score weights, as in "b=0.3,g=2"; one left out keeps the
+/// base config's.
+///
+/// ```
+/// use bf_search::portfolio::{parse_portfolio, Weights};
+///
+/// let w: Weights = "g=2, beta=0.5".parse().unwrap();
+/// assert_eq!((w.beta, w.gamma), (Some(0.5), Some(2.0)));
+/// assert_eq!(w.to_string(), "b=0.5,g=2");
+/// assert_eq!("gamma=0".parse::<Weights>().unwrap().to_string(), "g=0");
+/// assert!("b=x".parse::<Weights>().is_err() && "c=1".parse::<Weights>().is_err());
+///
+/// let members = parse_portfolio("b=1,g=1; b=0.3,g=2 ;b=2,g=0.5").unwrap();
+/// assert_eq!(members.len(), 3);
+/// assert_eq!(members[2], Weights { beta: Some(2.0), gamma: Some(0.5) });
+/// assert!(parse_portfolio("").is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Weights {
+    pub beta: Option<f64>,
+    pub gamma: Option<f64>,
+}
+
+impl FromStr for Weights {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Weights, String> {
+        let mut weights = Weights::default();
+        for part in spec.split(',').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", part.trim()))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("expected a number, got '{}'", value.trim()))?;
+            match key.trim() {
+                "b" | "beta" => weights.beta = Some(value),
+                "g" | "gamma" => weights.gamma = Some(value),
+                key => return Err(format!("unknown key '{}' (expected b or g)", key)),
+            }
+        }
+        Ok(weights)
+    }
+}
+
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [("b", self.beta), ("g", self.gamma)]
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|v| format!("{}={}", key, v)))
+            .collect();
+        f.write_str(&parts.join(","))
+    }
+}
+
+/// The members of a portfolio, `;`-separated `Weights`.
+pub fn parse_portfolio(spec: &str) -> Result<Vec<Weights>, String> {
+    let members = spec
+        .split(';')
+        .filter(|m| !m.trim().is_empty())
+        .map(|m| m.parse().map_err(|e| format!("'{}': {}", m.trim(), e)))
+        .collect::<Result<Vec<Weights>, String>>()?;
+    if members.is_empty() {
+        return Err("no members given".into());
+    }
+    Ok(members)
+}
+
+pub struct Member {
+    label: String, // its `Weights`, as given
+    searcher: Searcher,
+    solutions: usize, // reported, found by this member
+    stopped: Option<StopReason>, // why its search ended, once it has
+}
+
+impl Member {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn searcher(&self) -> &Searcher {
+        &self.searcher
+    }
+
+    pub fn solutions(&self) -> usize {
+        self.solutions
+    }
+
+    /// Why the member's search ended (`Exhausted`, `Budget` or `Aborted`),
+    /// or `None` while it is still taking turns.
+    pub fn stopped(&self) -> Option<StopReason> {
+        self.stopped
+    }
+}
+
+pub struct Portfolio {
+    members: Vec<Member>,
+    slice: u64, // expansions per turn
+    budget: Option<u64>, // expansions over all members
+    turn: usize, // the member going next
+    stop_reason: Option<StopReason>,
+}
+
+impl Portfolio {
+    /// One member per entry of `weights`, each searching with `base` but
+    /// for its weights.
+    pub fn new(base: &SearchConfig, weights: &[Weights]) -> Result<Portfolio, ConfigError> {
+        let mut members = Vec::with_capacity(weights.len());
+        for w in weights {
+            let mut searcher = Searcher::new(base.clone());
+            searcher.set_weights(w.beta.unwrap_or(base.beta()), w.gamma.unwrap_or(base.gamma()))?;
+            members.push(Member {
+                label: w.to_string(),
+                searcher,
+                solutions: 0,
+                stopped: None,
+            });
+        }
+        Ok(Portfolio {
+            members,
+            slice: 1000,
+            budget: None,
+            turn: 0,
+            stop_reason: None,
+        })
+    }
+
+    /// Expansions per turn (at least 1; by default 1000).
+    pub fn with_slice(mut self, slice: u64) -> Portfolio {
+        self.slice = slice.max(1);
+        self
+    }
+
+    /// Stop once the members have made `budget` expansions between them.
+    pub fn with_budget(mut self, budget: Option<u64>) -> Portfolio {
+        self.budget = budget;
+        self
+    }
+
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// Expansions over all members.
+    pub fn expansions(&self) -> u64 {
+        self.members.iter().map(|m| m.searcher.stats().expansions).sum()
+    }
+
+    /// Never report `code`, in any member (see `Searcher::mark_seen`).
+    pub fn mark_seen(&mut self, code: &str) {
+        for member in &mut self.members {
+            member.searcher.mark_seen(code.to_string());
+        }
+    }
+
+    /// Why the last `next_solution` call returned `None`: `Exhausted` if
+    /// every member's search was, `Budget` for the shared budget, or else
+    /// the first other reason a member stopped for.
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// The next solution any member finds, with the index of that member.
+    pub fn next_solution(&mut self) -> Option<(usize, Solution)> {
+        self.next_solution_until(|| false)
+    }
+
+    /// Like `next_solution`, but gives up with `StopReason::Cancelled` as
+    /// soon as `cancel` returns true (polled once per expansion).
+    pub fn next_solution_until(&mut self, cancel: impl Fn() -> bool) -> Option<(usize, Solution)> {
+        self.stop_reason = None;
+        loop {
+            let left = self.budget.map(|b| b.saturating_sub(self.expansions()));
+            let reason = if left == Some(0) {
+                Some(StopReason::Budget)
+            } else if cancel() {
+                Some(StopReason::Cancelled)
+            } else if self.members.iter().all(|m| m.stopped.is_some()) {
+                let mut stopped = self.members.iter().filter_map(|m| m.stopped);
+                let other = stopped.find(|&r| r != StopReason::Exhausted);
+                Some(other.unwrap_or(StopReason::Exhausted))
+            } else {
+                None
+            };
+            if reason.is_some() {
+                self.stop_reason = reason;
+                return None;
+            }
+
+            let i = self.turn;
+            self.turn = (i + 1) % self.members.len();
+            if self.members[i].stopped.is_some() {
+                continue;
+            }
+            let mut turn = Turn {
+                left: left.map_or(self.slice, |left| left.min(self.slice)),
+                cancel: &cancel,
+                found: None,
+            };
+            let member = &mut self.members[i];
+            match member.searcher.run(&mut turn) {
+                StopReason::Stopped | StopReason::Cancelled => {}
+                reason => member.stopped = Some(reason),
+            }
+            if let Some(solution) = turn.found {
+                member.solutions += 1;
+                for (j, other) in self.members.iter_mut().enumerate() {
+                    if j != i {
+                        other.searcher.mark_seen(solution.code.clone());
+                    }
+                }
+                return Some((i, solution));
+            }
+        }
+    }
+}
+
+/// One member's turn as callbacks: up to `left` expansions, or the first
+/// solution.
+struct Turn<'a, F> {
+    left: u64,
+    cancel: &'a F,
+    found: Option<Solution>,
+}
+
+impl<F: Fn() -> bool> Callbacks for Turn<'_, F> {
+    fn on_solution(&mut self, solution: &Solution) -> ControlFlow<()> {
+        self.found = Some(solution.clone());
+        ControlFlow::Break(())
+    }
+
+    fn on_expansion(&mut self, _expansion: &Expansion) {
+        self.left = self.left.saturating_sub(1);
+    }
+
+    fn cancelled(&mut self) -> bool {
+        self.left == 0 || (self.cancel)()
+    }
+}
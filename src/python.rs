@@ -0,0 +1,136 @@
+//! Python module `bf_search` (feature `python`):
+//!
+//! ```python
+//! import bf_search
+//! for sol in bf_search.search(bytes([1, 2]), max_expansions=100_000):
+//!     print(sol.code, sol.length, sol.steps, sol.score)
+//!     break
+//! bf_search.run("++.+.", 2)  # b'\x02\x03'
+//! ```
+//!
+//! The iterator drives the search lazily and releases the GIL while it
+//! expands nodes, taking it back every `CHUNK` expansions to let Python
+//! handle signals (Ctrl+C raises `KeyboardInterrupt` from `next`).
+
+// pyo3 0.22's `#[pyfunction]` expansion converts `PyErr` into itself.
+#![allow(clippy::useless_conversion)]
+
+use crate::interp::Interpreter;
+use crate::{InstrSet, SearchConfig, Searcher, StopReason};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::cell::Cell;
+
+/// Expansions between checks for signals; about 70 ms.
+const CHUNK: u64 = 10_000;
+
+#[pyclass(name = "Solution", module = "bf_search", frozen, get_all)]
+struct PySolution {
+    code: String,
+    length: u32,
+    steps: u64,
+    score: f64,
+}
+
+#[pymethods]
+impl PySolution {
+    fn __repr__(&self) -> String {
+        format!(
+            "Solution(code={:?}, length={}, steps={}, score={})",
+            self.code, self.length, self.steps, self.score
+        )
+    }
+}
+
+/// Iterator over the solutions of one search.
+#[pyclass(name = "Search", module = "bf_search")]
+struct PySearch {
+    searcher: Searcher,
+}
+
+#[pymethods]
+impl PySearch {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PySolution>> {
+        let searcher = &mut self.searcher;
+        let solution = loop {
+            let found = py.allow_threads(|| {
+                let polls = Cell::new(0);
+                searcher.next_solution_until(|| {
+                    polls.set(polls.get() + 1);
+                    polls.get() > CHUNK
+                })
+            });
+            match found {
+                Some(solution) => break solution,
+                None if searcher.stop_reason() == Some(StopReason::Cancelled) => py.check_signals()?,
+                None => return Ok(None),
+            }
+        };
+        Ok(Some(PySolution {
+            code: solution.code,
+            length: solution.min_len,
+            steps: solution.steps,
+            score: solution.score,
+        }))
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (target, beta=1.0, gamma=1.0, max_steps=1_000_000, max_expansions=None, instr_set=None, golf=false))]
+fn search(
+    target: &[u8],
+    beta: f64,
+    gamma: f64,
+    max_steps: u64,
+    max_expansions: Option<u64>,
+    instr_set: Option<&str>,
+    golf: bool,
+) -> PyResult<PySearch> {
+    let mut builder = SearchConfig::new(target.to_vec())
+        .beta(beta)
+        .gamma(gamma)
+        .max_steps(max_steps)
+        .max_expansions(max_expansions)
+        .golf(golf);
+    if let Some(set) = instr_set {
+        builder = builder.instr_set(InstrSet::parse(set).map_err(PyValueError::new_err)?);
+    }
+    let config = builder
+        .build()
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(PySearch {
+        searcher: Searcher::new(config),
+    })
+}
+
+/// Output of `code` (up to `limit` bytes, or until it halts).
+#[pyfunction]
+#[pyo3(signature = (code, limit, max_steps=1_000_000))]
+fn run<'py>(
+    py: Python<'py>,
+    code: &str,
+    limit: usize,
+    max_steps: u64,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mut interp = Interpreter::from_source(code)
+        .map_err(PyValueError::new_err)?
+        .with_step_cap(max_steps);
+    interp
+        .run_to_limit(limit)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(PyBytes::new_bound(py, interp.output()))
+}
+
+#[pymodule]
+fn bf_search(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySolution>()?;
+    m.add_class::<PySearch>()?;
+    m.add_function(wrap_pyfunction!(search, m)?)?;
+    m.add_function(wrap_pyfunction!(run, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,249 @@
+//! Logs of a search's expansions, for telling where two runs part ways.
+//!
+//! A `Recorder` writes one line of JSON per expansion (the node expanded,
+//! its score, and the children queued with their scores) after a header
+//! line naming the format version and the target. A `Replay` reads such a
+//! log back and checks a new run of the same search against it, expansion
+//! by expansion, up to the first difference:
+//!
+//! ```
+//! use bf_search::record::{Recorder, Replay};
+//! use bf_search::{SearchConfig, Searcher};
+//!
+//! let config = SearchConfig::new(vec![1, 2]).max_expansions(Some(10)).build().unwrap();
+//! let mut recorder = Recorder::new(Vec::new(), &[1, 2]).unwrap();
+//! Searcher::new(config.clone()).run(&mut recorder);
+//! let log = String::from_utf8(recorder.finish().unwrap()).unwrap();
+//! assert_eq!(log.lines().count(), 1 + 10);
+//!
+//! let mut replay = Replay::parse(&log, &[1, 2]).unwrap();
+//! Searcher::new(config.clone()).run(&mut replay);
+//! assert_eq!(replay.finish(), Ok(10));
+//!
+//! // the third expansion logged under another node number
+//! let mut lines: Vec<String> = log.lines().map(String::from).collect();
+//! lines[3] = lines[3].replacen("\"seq\":", "\"seq\":9", 1);
+//! let mut replay = Replay::parse(&lines.join("\n"), &[1, 2]).unwrap();
+//! Searcher::new(config).run(&mut replay);
+//! let divergence = replay.finish().unwrap_err();
+//! assert_eq!(divergence.expansion, 3);
+//! assert_eq!(divergence.found.unwrap().seq + 90, divergence.expected.seq);
+//!
+//! // a run stopping early diverges too
+//! let short = SearchConfig::new(vec![1, 2]).max_expansions(Some(4)).build().unwrap();
+//! let mut replay = Replay::parse(&log, &[1, 2]).unwrap();
+//! Searcher::new(short).run(&mut replay);
+//! let divergence = replay.finish().unwrap_err();
+//! assert_eq!((divergence.expansion, divergence.found), (5, None));
+//! ```
+
+use crate::search::{Callbacks, Expansion};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, Write};
+
+/// Version of the log format, written in the header and checked on reading.
+pub const VERSION: u32 = 1;
+
+const FORMAT: &str = "bf_search-record";
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+    target: Vec<u8>,
+}
+
+/// One logged expansion; see `Expansion`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub seq: u64,
+    pub score: f64,
+    pub children: Vec<(u64, f64)>,
+}
+
+impl From<&Expansion<'_>> for Entry {
+    fn from(e: &Expansion) -> Entry {
+        Entry {
+            seq: e.seq,
+            score: e.score,
+            children: e.children.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "node {} (score {}) queuing ", self.seq, self.score)?;
+        if self.children.is_empty() {
+            return write!(f, "nothing");
+        }
+        for (i, (seq, score)) in self.children.iter().enumerate() {
+            let sep = if i > 0 { ", " } else { "" };
+            write!(f, "{}{} ({})", sep, seq, score)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the log of a run; as `Callbacks`, it logs every expansion.
+pub struct Recorder<W: Write> {
+    out: W,
+    error: Option<io::Error>, // the first write that failed
+}
+
+impl<W: Write> Recorder<W> {
+    /// Start the log of a search for `target` by writing its header.
+    pub fn new(mut out: W, target: &[u8]) -> io::Result<Recorder<W>> {
+        let header = Header {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            target: target.to_vec(),
+        };
+        serde_json::to_writer(&mut out, &header)?;
+        out.write_all(b"\n")?;
+        Ok(Recorder { out, error: None })
+    }
+
+    /// Log one expansion. Write errors are kept for `finish`, and nothing
+    /// more is written after one.
+    pub fn record(&mut self, expansion: &Expansion) {
+        if self.error.is_some() {
+            return;
+        }
+        let written = serde_json::to_writer(&mut self.out, &Entry::from(expansion))
+            .map_err(io::Error::from)
+            .and_then(|()| self.out.write_all(b"\n"));
+        self.error = written.err();
+    }
+
+    /// Flush the log, returning the writer or the first error.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.out.flush().map(|()| self.out),
+        }
+    }
+}
+
+impl<W: Write> Callbacks for Recorder<W> {
+    fn on_expansion(&mut self, expansion: &Expansion) {
+        self.record(expansion);
+    }
+}
+
+/// The first expansion (counting from 1) where a run differs from its log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    pub expansion: usize,
+    pub expected: Entry,
+    pub found: Option<Entry>, // `None` if the run stopped before it
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expansion {}: the log has {}; ", self.expansion, self.expected)?;
+        match &self.found {
+            Some(found) => write!(f, "the run has {}", found),
+            None => write!(f, "the run stopped"),
+        }
+    }
+}
+
+/// Checks a run against a log. As `Callbacks`, it checks every expansion
+/// and cancels the run at the first divergence or the end of the log.
+pub struct Replay {
+    entries: Vec<Entry>,
+    checked: usize, // expansions matched so far
+    divergence: Option<Divergence>,
+}
+
+impl Replay {
+    /// Read a log written by `Recorder` for a search of `target`.
+    pub fn parse(log: &str, target: &[u8]) -> Result<Replay, String> {
+        let mut lines = log.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let (_, first) = lines.next().ok_or("empty log")?;
+        let header: Header =
+            serde_json::from_str(first).map_err(|e| format!("line 1: not a search log: {}", e))?;
+        if header.format != FORMAT {
+            return Err(format!("line 1: not a search log (format \"{}\")", header.format));
+        }
+        if header.version != VERSION {
+            return Err(format!(
+                "log format version {} is not supported (expected {})",
+                header.version, VERSION
+            ));
+        }
+        if header.target != target {
+            return Err("the log is of a search for another target".to_string());
+        }
+        let entries = lines
+            .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e)))
+            .collect::<Result<_, String>>()?;
+        Ok(Replay {
+            entries,
+            checked: 0,
+            divergence: None,
+        })
+    }
+
+    /// Compare the run's next expansion with the log. Ignored after a
+    /// divergence or past the end of the log.
+    pub fn check(&mut self, expansion: &Expansion) {
+        if self.done() {
+            return;
+        }
+        let expected = &self.entries[self.checked];
+        let found = Entry::from(expansion);
+        if found == *expected {
+            self.checked += 1;
+        } else {
+            self.divergence = Some(Divergence {
+                expansion: self.checked + 1,
+                expected: expected.clone(),
+                found: Some(found),
+            });
+        }
+    }
+
+    /// The run should stop: it diverged, or the log has no more to check.
+    pub fn done(&self) -> bool {
+        self.divergence.is_some() || self.checked == self.entries.len()
+    }
+
+    /// Expansions in the log.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Once the run has stopped: the number of expansions it matched (the
+    /// whole log), or where it diverged, counting a run that stopped short
+    /// of the log's end.
+    pub fn finish(&self) -> Result<usize, Divergence> {
+        if let Some(d) = &self.divergence {
+            return Err(d.clone());
+        }
+        match self.entries.get(self.checked) {
+            Some(expected) => Err(Divergence {
+                expansion: self.checked + 1,
+                expected: expected.clone(),
+                found: None,
+            }),
+            None => Ok(self.checked),
+        }
+    }
+}
+
+impl Callbacks for Replay {
+    fn on_expansion(&mut self, expansion: &Expansion) {
+        self.check(expansion);
+    }
+
+    fn cancelled(&mut self) -> bool {
+        self.done()
+    }
+}
@@ -0,0 +1,2182 @@
+//! Best-first search driver: a priority queue of `SearchNode`s ordered by
+//! score, yielding each distinct solution as it is found.
+
+use crate::intern::Interner;
+use crate::interp::{Interpreter, RunEnd};
+use crate::pareto::{FrontEntry, ParetoFront};
+use crate::{
+    exec_known_step, step_once_with, AdvancePolicy, Instr, InstrSet, PKind, Program, ProgramNode,
+    SearchError, SearchNode, Shared, StepOutcome, Tape, MAX_COEFFICIENT,
+};
+use ordered_float::NotNan;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant; // std's Instant panics in the browser
+
+/// Parameters of one search, built and validated by `SearchConfigBuilder`.
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    target: Vec<u8>,
+    beta: f64,
+    gamma: f64,
+    max_steps: u64, // nodes past this many interpreter steps are dropped
+    max_steps_per_byte: Option<u64>, // ... and past this many since their last output
+    instr_set: InstrSet,
+    tape_init: Tape, // the cells every program starts on
+    cases: Vec<Case>, // more tapes and what the same program must print from them
+    case_check_steps: Option<u64>, // steps between checks of a branch against `cases`
+    idioms: Vec<Program>, // templates queued next to the empty program
+    from_scratch: bool, // queue the empty program too
+    golf: bool, // after each solution, keep only strictly shorter programs
+    max_len: Option<u32>, // nodes whose program is longer than this are dropped
+    exact_halt: bool, // solutions must halt right after the target
+    require_halt: Option<u64>, // solutions must halt within this many steps
+    pareto_only: bool, // report only solutions that extend the Pareto front
+    optimize_steps: Option<u32>, // after a solution, look for faster ones up to this much longer
+    max_expansions: Option<u64>,
+    time_limit: Option<Duration>,
+    progress_every: Option<u64>, // expansions between `Callbacks::on_progress` calls
+    frontier: Frontier,
+    intern: bool, // share identical program nodes across the frontier
+    rollout: u32, // expansions that follow the best child without queueing it
+    dedup: Dedup,
+    eager: bool, // check children for solutions when they are generated
+    live_length: bool, // score by `SearchNode::live_len` instead of `min_len`
+    retry_relaxed: Option<f64>, // park nodes past `max_steps`, raise it by this factor
+    relax_after: Option<u64>, // ... also after this many expansions without a solution
+    max_parked: usize,
+    max_memory: Option<usize>, // bytes the frontier may hold, by `SearchNode::footprint`
+}
+
+/// How the frontier orders nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frontier {
+    /// Exact best-first: highest score, ties in insertion order.
+    Heap,
+    /// Scores rounded down to multiples of `width`; the best nonempty bucket
+    /// is served first-in first-out. Cheaper per node than the heap, but
+    /// nodes within `width` of each other are expanded in insertion order,
+    /// so the width changes the exploration order (and which solutions come
+    /// first).
+    Bucket { width: f64 },
+}
+
+/// When two solutions count as the same one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dedup {
+    /// Different source text, different solution.
+    Source,
+    /// Same behavior, same solution: the programs print the same first
+    /// `target.len() + extra` bytes (within `max_steps` steps) and agree on
+    /// whether they halt there. A variant is reported only if it is shorter
+    /// than every one reported before it.
+    Behavior { extra: usize, max_steps: u64 },
+}
+
+/// A starting tape and what a program must print from it; see
+/// `SearchConfigBuilder::cases`. Written `tape=CELLS;out=BYTES`, the cells
+/// as for `Tape`'s `FromStr` and the bytes in decimal.
+///
+/// ```
+/// use bf_search::Case;
+///
+/// let case: Case = "tape=2;out=4".parse().unwrap();
+/// assert_eq!((case.tape.get(0), case.target.clone()), (2, vec![4]));
+/// let case: Case = " out = 1, 2 ; tape = cell[-1]=5 ".parse().unwrap();
+/// assert_eq!((case.tape.get(-1), case.target.clone()), (5, vec![1, 2]));
+/// assert_eq!(case.to_string(), "tape=cell[-1]=5;out=1 2");
+/// assert_eq!("out=7".parse::<Case>().unwrap().to_string(), "tape=;out=7");
+/// assert!("tape=2".parse::<Case>().is_err());
+/// assert!("tape=2;out=300".parse::<Case>().is_err());
+/// assert!("tape=2;out=4;steps=9".parse::<Case>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Case {
+    pub tape: Tape,
+    pub target: Vec<u8>,
+}
+
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Case, String> {
+        let (mut tape, mut target) = (Tape::new(), None);
+        for part in spec.split(';') {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", part.trim()))?;
+            match key.trim() {
+                "tape" => tape = value.parse()?,
+                "out" => {
+                    let byte = |b: &str| {
+                        b.parse::<u8>()
+                            .map_err(|_| format!("output bytes are 0..=255, got '{}'", b))
+                    };
+                    let bytes = value.split([' ', ',']).filter(|b| !b.is_empty()).map(byte);
+                    target = Some(bytes.collect::<Result<Vec<u8>, String>>()?);
+                }
+                key => return Err(format!("unknown key '{}' (expected tape or out)", key)),
+            }
+        }
+        let target = target.ok_or("missing out=BYTES")?;
+        Ok(Case { tape, target })
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let out: Vec<String> = self.target.iter().map(|b| b.to_string()).collect();
+        write!(f, "tape={};out={}", self.tape, out.join(" "))
+    }
+}
+
+/// Why `SearchConfigBuilder::build` rejected its parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    EmptyTarget, // or the output of a case
+    BadCoefficient { name: &'static str, value: f64 }, // not within 0..=MAX_COEFFICIENT
+    ZeroMaxSteps,
+    ZeroStepsPerByte,
+    ZeroProgressInterval,
+    BadBucketWidth(f64), // not positive and finite
+    BadRelaxFactor(f64), // not finite and above 1
+    ZeroRelaxInterval,
+    ZeroCaseCheckInterval,
+    ZeroMaxMemory,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::EmptyTarget => write!(f, "target must not be empty"),
+            ConfigError::BadCoefficient { name, value } => {
+                write!(f, "{} must be a number from 0 to {}, got {:?}", name, MAX_COEFFICIENT, value)
+            }
+            ConfigError::ZeroMaxSteps => write!(f, "max steps must be at least 1"),
+            ConfigError::ZeroStepsPerByte => write!(f, "max steps per byte must be at least 1"),
+            ConfigError::ZeroProgressInterval => {
+                write!(f, "progress interval must be at least 1 expansion")
+            }
+            ConfigError::BadBucketWidth(w) => {
+                write!(f, "bucket width must be a positive number, got {}", w)
+            }
+            ConfigError::BadRelaxFactor(x) => {
+                write!(f, "relaxation factor must be a number above 1, got {}", x)
+            }
+            ConfigError::ZeroRelaxInterval => {
+                write!(f, "relaxation interval must be at least 1 expansion")
+            }
+            ConfigError::ZeroCaseCheckInterval => {
+                write!(f, "case check interval must be at least 1 step")
+            }
+            ConfigError::ZeroMaxMemory => write!(f, "memory cap must be at least 1 byte"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl SearchConfig {
+    /// Start from the command-line defaults: β = γ = 1, 1_000_000 steps, all
+    /// instructions, no budget, heap frontier, no interning, no rollouts,
+    /// solutions told apart by source, nodes past the step cap dropped.
+    ///
+    /// ```
+    /// use bf_search::{ConfigError, SearchConfig};
+    ///
+    /// let config = SearchConfig::new(vec![1, 2]).beta(0.5).max_steps(10_000).build().unwrap();
+    /// assert_eq!((config.beta(), config.gamma()), (0.5, 1.0));
+    /// let err = SearchConfig::new(vec![]).build().unwrap_err();
+    /// assert_eq!(err, ConfigError::EmptyTarget);
+    /// let err = SearchConfig::new(vec![1]).gamma(f64::MAX).build().unwrap_err();
+    /// assert_eq!(err, ConfigError::BadCoefficient { name: "gamma", value: f64::MAX });
+    /// ```
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(target: Vec<u8>) -> SearchConfigBuilder {
+        SearchConfigBuilder {
+            config: SearchConfig {
+                target,
+                beta: 1.0,
+                gamma: 1.0,
+                max_steps: 1_000_000,
+                max_steps_per_byte: None,
+                instr_set: InstrSet::parse("><+-.,[]").unwrap(),
+                tape_init: Tape::new(),
+                cases: Vec::new(),
+                case_check_steps: None,
+                idioms: Vec::new(),
+                from_scratch: true,
+                golf: false,
+                max_len: None,
+                exact_halt: false,
+                require_halt: None,
+                pareto_only: false,
+                optimize_steps: None,
+                max_expansions: None,
+                time_limit: None,
+                progress_every: None,
+                frontier: Frontier::Heap,
+                intern: false,
+                rollout: 0,
+                dedup: Dedup::Source,
+                eager: false,
+                live_length: false,
+                retry_relaxed: None,
+                relax_after: None,
+                max_parked: 100_000,
+                max_memory: None,
+            },
+        }
+    }
+
+    pub fn target(&self) -> &[u8] {
+        &self.target
+    }
+
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    pub fn max_steps(&self) -> u64 {
+        self.max_steps
+    }
+
+    pub fn max_steps_per_byte(&self) -> Option<u64> {
+        self.max_steps_per_byte
+    }
+
+    pub fn instr_set(&self) -> &InstrSet {
+        &self.instr_set
+    }
+
+    pub fn tape_init(&self) -> &Tape {
+        &self.tape_init
+    }
+
+    pub fn cases(&self) -> &[Case] {
+        &self.cases
+    }
+
+    pub fn case_check_steps(&self) -> Option<u64> {
+        self.case_check_steps
+    }
+
+    pub fn idioms(&self) -> &[Program] {
+        &self.idioms
+    }
+
+    pub fn from_scratch(&self) -> bool {
+        self.from_scratch
+    }
+
+    pub fn golf(&self) -> bool {
+        self.golf
+    }
+
+    pub fn max_len(&self) -> Option<u32> {
+        self.max_len
+    }
+
+    pub fn exact_halt(&self) -> bool {
+        self.exact_halt
+    }
+
+    pub fn require_halt(&self) -> Option<u64> {
+        self.require_halt
+    }
+
+    pub fn pareto_only(&self) -> bool {
+        self.pareto_only
+    }
+
+    pub fn optimize_steps(&self) -> Option<u32> {
+        self.optimize_steps
+    }
+
+    pub fn max_expansions(&self) -> Option<u64> {
+        self.max_expansions
+    }
+
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    pub fn progress_every(&self) -> Option<u64> {
+        self.progress_every
+    }
+
+    pub fn frontier(&self) -> Frontier {
+        self.frontier
+    }
+
+    pub fn intern(&self) -> bool {
+        self.intern
+    }
+
+    pub fn rollout(&self) -> u32 {
+        self.rollout
+    }
+
+    pub fn dedup(&self) -> Dedup {
+        self.dedup
+    }
+
+    pub fn eager(&self) -> bool {
+        self.eager
+    }
+
+    pub fn live_length(&self) -> bool {
+        self.live_length
+    }
+
+    pub fn retry_relaxed(&self) -> Option<f64> {
+        self.retry_relaxed
+    }
+
+    pub fn relax_after(&self) -> Option<u64> {
+        self.relax_after
+    }
+
+    pub fn max_parked(&self) -> usize {
+        self.max_parked
+    }
+
+    pub fn max_memory(&self) -> Option<usize> {
+        self.max_memory
+    }
+}
+
+/// Setters for `SearchConfig`; `build` checks the result.
+#[derive(Clone, Debug)]
+pub struct SearchConfigBuilder {
+    config: SearchConfig,
+}
+
+impl SearchConfigBuilder {
+    pub fn beta(mut self, beta: f64) -> Self {
+        self.config.beta = beta;
+        self
+    }
+
+    pub fn gamma(mut self, gamma: f64) -> Self {
+        self.config.gamma = gamma;
+        self
+    }
+
+    pub fn max_steps(mut self, max_steps: u64) -> Self {
+        self.config.max_steps = max_steps;
+        self
+    }
+
+    /// Drop nodes that ran more than `r` steps since their last output (or
+    /// since the start, before the first). Unlike `max_steps` this bounds
+    /// the gaps, not the total, so it cuts loops spinning without output
+    /// while programs printing at a steady pace run on.
+    ///
+    /// ```
+    /// use bf_search::{Callbacks, PruneReason, SearchConfig, Searcher, Solution};
+    /// use std::ops::ControlFlow;
+    ///
+    /// #[derive(Default)]
+    /// struct First(Option<String>, u64);
+    /// impl Callbacks for First {
+    ///     fn on_solution(&mut self, s: &Solution) -> ControlFlow<()> {
+    ///         self.0 = Some(s.code.clone());
+    ///         ControlFlow::Break(())
+    ///     }
+    ///     fn on_prune(&mut self, reason: PruneReason) {
+    ///         self.1 += (reason == PruneReason::StepsPerByte) as u64;
+    ///     }
+    /// }
+    /// let first = |r| {
+    ///     let config = SearchConfig::new(vec![3, 6, 9]).max_steps_per_byte(r).build().unwrap();
+    ///     let mut found = First::default();
+    ///     Searcher::new(config).run(&mut found);
+    ///     found
+    /// };
+    /// assert_eq!(first(None).0.as_deref(), Some("+[++.+]"));
+    /// // the loop runs 4 steps between outputs; branches spinning longer go
+    /// let loop_ok = first(Some(4));
+    /// assert_eq!(loop_ok.0.as_deref(), Some("+[++.+]"));
+    /// assert!(loop_ok.1 > 0);
+    /// assert_eq!(first(Some(3)).0.as_deref(), Some("+++.+++.+++."));
+    /// ```
+    pub fn max_steps_per_byte(mut self, r: Option<u64>) -> Self {
+        self.config.max_steps_per_byte = r;
+        self
+    }
+
+    pub fn instr_set(mut self, instr_set: InstrSet) -> Self {
+        self.config.instr_set = instr_set;
+        self
+    }
+
+    /// Run every program on `tape` instead of blank cells, as if it held
+    /// the program's input; solutions are verified on it too.
+    ///
+    /// ```
+    /// use bf_search::interp::Interpreter;
+    /// use bf_search::{SearchConfig, Searcher};
+    ///
+    /// let first = |tape: &str| {
+    ///     let config = SearchConfig::new(vec![3]).tape_init(tape.parse().unwrap()).build().unwrap();
+    ///     Searcher::new(config).next_solution().unwrap().code
+    /// };
+    /// assert_eq!(first(""), "+++.");
+    /// assert_eq!(first("3"), ".");
+    /// assert_eq!(first("0 1"), ">++.");
+    /// // on blank cells `.` prints 0
+    /// let mut run = Interpreter::from_source(".").unwrap().with_step_cap(10);
+    /// run.run_to_limit(1).unwrap();
+    /// assert_eq!(run.output(), &[0]);
+    /// ```
+    pub fn tape_init(mut self, tape: Tape) -> Self {
+        self.config.tape_init = tape;
+        self
+    }
+
+    /// Also require the program, run on each case's tape, to print that
+    /// case's target: one program for several (tape, output) pairs, a
+    /// function of what it starts with. The search runs `target` from
+    /// `tape_init` as usual. When a branch completes it, and every
+    /// `case_check_steps` steps if set, the program so far runs on the
+    /// other tapes up to its first hole: a wrong byte, a halt or a `,`
+    /// before then prunes the branch (no filling of the holes changes
+    /// that), and the bytes that came out right count towards the score as
+    /// `SearchNode::case_correct`. A solution is reported once its program
+    /// passes every case.
+    ///
+    /// ```
+    /// use bf_search::{Case, InstrSet, SearchConfig, Searcher};
+    ///
+    /// let case = |s: &str| s.parse::<Case>().unwrap();
+    /// // double the number in cell 0
+    /// let config = SearchConfig::new(vec![4])
+    ///     .tape_init("2".parse().unwrap())
+    ///     .cases(vec![case("tape=5;out=10"), case("tape=1;out=2")])
+    ///     .instr_set(InstrSet::parse("+-<>.[]").unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// let mut searcher = Searcher::new(config);
+    /// let double = searcher.next_solution().unwrap().code;
+    /// assert_eq!(double, "[>++<-]>.");
+    /// for x in 0..100u8 {
+    ///     let mut run = bf_search::interp::Interpreter::from_source(&double).unwrap();
+    ///     run = run.with_tape(x.to_string().parse().unwrap()).with_step_cap(1000);
+    ///     run.run_to_limit(1).unwrap();
+    ///     assert_eq!(run.output(), &[2 * x]);
+    /// }
+    /// assert!(searcher.stats().pruned.get(bf_search::PruneReason::OtherCase) > 0);
+    /// ```
+    pub fn cases(mut self, cases: Vec<Case>) -> Self {
+        self.config.cases = cases;
+        self
+    }
+
+    /// Under `cases`, also check a branch against them every `k` steps,
+    /// besides when it completes the target.
+    pub fn case_check_steps(mut self, k: Option<u64>) -> Self {
+        self.config.case_check_steps = k;
+        self
+    }
+
+    /// Besides the empty program (see `from_scratch`), start from each of
+    /// these templates (see `idioms`): run from `tape_init` up to its first
+    /// hole and queued there, scored like any other node. A template is
+    /// left out if it repeats an earlier one, uses commands outside
+    /// `instr_set`, or skips a loop outside every loop (it would only be a
+    /// longer copy of what the search finds anyway); one that goes wrong
+    /// before its first hole, as a branch would, is counted among the
+    /// pruned nodes.
+    ///
+    /// ```
+    /// use bf_search::idioms::parse_idioms;
+    /// use bf_search::{InstrSet, PruneReason, SearchConfig, Searcher};
+    ///
+    /// // `[-]` skips on blank cells, `,` is outside the set, `+.` prints 1
+    /// let idioms = parse_idioms("[-]?\n,?\n+.?\n+++.?\n+++[->+<]?").unwrap();
+    /// let config = SearchConfig::new(vec![3, 4])
+    ///     .instr_set(InstrSet::parse("+-<>.[]").unwrap())
+    ///     .idioms(idioms)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut searcher = Searcher::new(config);
+    /// assert_eq!(searcher.frontier_len(), 3); // the empty program and two templates
+    /// assert_eq!(searcher.stats().pruned.get(PruneReason::Mismatch), 1);
+    /// let best = searcher.best().unwrap();
+    /// assert_eq!((best.root.to_string(), best.correct, best.steps), ("+++.?".to_string(), 1, 4));
+    /// assert_eq!(searcher.next_solution().unwrap().code, "+++.+.");
+    /// ```
+    pub fn idioms(mut self, idioms: Vec<Program>) -> Self {
+        self.config.idioms = idioms;
+        self
+    }
+
+    /// Whether to start from the empty program; true by default. Without
+    /// it only what grows from the `idioms` is searched, such as the
+    /// neighbourhood of a program (see `near`).
+    pub fn from_scratch(mut self, from_scratch: bool) -> Self {
+        self.config.from_scratch = from_scratch;
+        self
+    }
+
+    pub fn golf(mut self, golf: bool) -> Self {
+        self.config.golf = golf;
+        self
+    }
+
+    /// Drop every node whose program (by `ProgramNode::min_len`) is longer
+    /// than `len`, pruned by `PruneReason::LengthCap`. The search then runs
+    /// dry once it has been through every such program, which is what
+    /// `census` counts.
+    ///
+    /// ```
+    /// use bf_search::{InstrSet, PruneReason, SearchConfig, Searcher, StopReason};
+    ///
+    /// let config = SearchConfig::new(vec![2])
+    ///     .instr_set(InstrSet::parse("+-.").unwrap())
+    ///     .max_len(Some(4))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut searcher = Searcher::new(config);
+    /// let codes: Vec<String> = searcher.solutions().map(|s| s.code).collect();
+    /// assert_eq!(codes, ["++.", "++.+", "++.-", "++.."]); // not `+-++.`
+    /// assert_eq!(searcher.stop_reason(), Some(StopReason::Exhausted));
+    /// assert!(searcher.stats().pruned.get(PruneReason::LengthCap) > 0);
+    /// ```
+    pub fn max_len(mut self, len: Option<u32>) -> Self {
+        self.config.max_len = len;
+        self
+    }
+
+    /// Only report programs that halt right after printing the target,
+    /// and drop every branch that prints past it.
+    ///
+    /// ```
+    /// use bf_search::{SearchConfig, Searcher};
+    ///
+    /// let first = |exact_halt| {
+    ///     let config = SearchConfig::new(vec![1]).exact_halt(exact_halt).build().unwrap();
+    ///     let mut searcher = Searcher::new(config);
+    ///     searcher.solutions().take(20).map(|s| s.code).collect::<Vec<_>>()
+    /// };
+    /// // `+[.]` prints 1 forever
+    /// assert!(first(false).contains(&"+[.]".to_string()));
+    /// let halting = first(true);
+    /// assert_eq!(halting[0], "+.");
+    /// assert!(!halting.contains(&"+[.]".to_string()));
+    /// assert!(!halting.contains(&"+..".to_string()));
+    /// ```
+    pub fn exact_halt(mut self, exact_halt: bool) -> Self {
+        self.config.exact_halt = exact_halt;
+        self
+    }
+
+    /// Only report programs that, run from the start, halt within `steps`
+    /// steps (after printing the target and whatever follows). The others
+    /// go to `Callbacks::on_non_halting` and the search goes on.
+    ///
+    /// ```
+    /// use bf_search::{Callbacks, SearchConfig, Searcher, Solution};
+    /// use std::ops::ControlFlow;
+    ///
+    /// #[derive(Default)]
+    /// struct Log(Vec<String>, Vec<String>);
+    /// impl Callbacks for Log {
+    ///     fn on_solution(&mut self, s: &Solution) -> ControlFlow<()> {
+    ///         self.0.push(s.code.clone());
+    ///         if self.0.len() < 20 { ControlFlow::Continue(()) } else { ControlFlow::Break(()) }
+    ///     }
+    ///     fn on_non_halting(&mut self, s: &Solution) {
+    ///         self.1.push(s.code.clone());
+    ///     }
+    /// }
+    ///
+    /// let config = SearchConfig::new(vec![1]).require_halt(Some(1000)).build().unwrap();
+    /// let mut searcher = Searcher::new(config);
+    /// let mut log = Log::default();
+    /// searcher.run(&mut log);
+    /// // `+..` prints past the target but halts; `+[.]` never does
+    /// assert!(log.0.contains(&"+..".to_string()));
+    /// assert!(!log.0.contains(&"+[.]".to_string()));
+    /// assert!(log.1.contains(&"+[.]".to_string()));
+    /// assert_eq!(searcher.stats().non_halting, log.1.len() as u64);
+    /// ```
+    pub fn require_halt(mut self, steps: Option<u64>) -> Self {
+        self.config.require_halt = steps;
+        self
+    }
+
+    /// Only report solutions that extend the Pareto front of length and
+    /// steps (see `Searcher::front`): those no earlier solution is both as
+    /// short as and as fast as. The others are counted in
+    /// `SearchStats::dominated` and, like reported programs, never checked
+    /// again; the front only gets better, so they stay dominated.
+    ///
+    /// ```
+    /// use bf_search::{SearchConfig, Searcher};
+    ///
+    /// let run = |pareto_only| {
+    ///     let config = SearchConfig::new(vec![1, 2, 3]).pareto_only(pareto_only);
+    ///     let mut searcher = Searcher::new(config.max_expansions(Some(2000)).build().unwrap());
+    ///     let found: Vec<_> = searcher.solutions().map(|s| (s.code, s.on_front)).collect();
+    ///     (found, searcher)
+    /// };
+    /// let (all, everything) = run(false);
+    /// assert_eq!(all[..3], [("+.+.+.".into(), true), ("+[.+]".into(), true), ("+.+.+.>".into(), false)]);
+    /// let (front, searcher) = run(true);
+    /// assert_eq!(front, all.iter().filter(|s| s.1).cloned().collect::<Vec<_>>());
+    /// assert_eq!(searcher.front().entries(), everything.front().entries());
+    /// assert_eq!(searcher.stats().dominated as usize, all.len() - front.len());
+    /// ```
+    pub fn pareto_only(mut self, pareto_only: bool) -> Self {
+        self.config.pareto_only = pareto_only;
+        self
+    }
+
+    /// Once a solution is found, keep searching for faster programs of
+    /// about its length: report only solutions extending the Pareto front,
+    /// as under `pareto_only` (so a shorter one, or one as short and
+    /// faster), and drop every node more than `slack` longer than the
+    /// shortest solution so far, as `golf` does. The steps compared are
+    /// those the concrete program takes to print the target, measured
+    /// when it is verified (`Solution::steps`).
+    ///
+    /// ```
+    /// use bf_search::{InstrSet, SearchConfig, Searcher};
+    ///
+    /// let search = |slack| {
+    ///     let config = SearchConfig::new(vec![1, 4, 7])
+    ///         .instr_set(InstrSet::parse("+-.[]").unwrap())
+    ///         .max_steps(100)
+    ///         .max_expansions(Some(20_000))
+    ///         .optimize_steps(slack)
+    ///         .build()
+    ///         .unwrap();
+    ///     let mut searcher = Searcher::new(config);
+    ///     let found: Vec<_> = searcher.solutions().map(|s| (s.code, s.min_len, s.steps)).collect();
+    ///     (found, searcher)
+    /// };
+    /// let (all, _) = search(None);
+    /// assert_eq!(all[..2], [("+[.+++]".into(), 7, 13), ("-[++.+]".into(), 7, 15)]);
+    /// // as short and slower: left out; one longer and faster: reported
+    /// let (faster, searcher) = search(Some(1));
+    /// assert_eq!(faster, [("+[.+++]".into(), 7, 13), ("+.[+++.]".into(), 8, 12)]);
+    /// assert_eq!(searcher.golf_bound(), Some(8));
+    /// assert!(searcher.stats().dominated > 0);
+    /// let (same_length, _) = search(Some(0));
+    /// assert_eq!(same_length, [("+[.+++]".into(), 7, 13)]);
+    /// ```
+    pub fn optimize_steps(mut self, slack: Option<u32>) -> Self {
+        self.config.optimize_steps = slack;
+        self
+    }
+
+    pub fn max_expansions(mut self, n: Option<u64>) -> Self {
+        self.config.max_expansions = n;
+        self
+    }
+
+    pub fn time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.config.time_limit = limit;
+        self
+    }
+
+    pub fn progress_every(mut self, n: Option<u64>) -> Self {
+        self.config.progress_every = n;
+        self
+    }
+
+    pub fn frontier(mut self, frontier: Frontier) -> Self {
+        self.config.frontier = frontier;
+        self
+    }
+
+    /// Build expansions through an `Interner`, so that identical program
+    /// nodes are shared between frontier nodes: less memory, slower
+    /// expansion. The search itself is unchanged.
+    pub fn intern(mut self, intern: bool) -> Self {
+        self.config.intern = intern;
+        self
+    }
+
+    /// After popping a node, expand its best child next instead of queueing
+    /// it, for up to `k` expansions in a row; the other children are queued
+    /// as usual. 0 (the default) is plain best-first search.
+    ///
+    /// Rollouts change the order of expansion, never the set of nodes
+    /// reachable: a search that runs to exhaustion reports the same
+    /// solutions for every `k`.
+    ///
+    /// ```
+    /// use bf_search::{InstrSet, SearchConfig, Searcher, StopReason};
+    ///
+    /// for (target, set, steps) in [(vec![1, 1], "+-.<>", 5), (vec![1, 0], "+-.[]", 7)] {
+    ///     let all = |k| {
+    ///         let config = SearchConfig::new(target.clone())
+    ///             .instr_set(InstrSet::parse(set).unwrap())
+    ///             .max_steps(steps)
+    ///             .rollout(k)
+    ///             .build()
+    ///             .unwrap();
+    ///         let mut searcher = Searcher::new(config);
+    ///         let mut found: Vec<String> = searcher.solutions().map(|s| s.code).collect();
+    ///         assert_eq!(searcher.stop_reason(), Some(StopReason::Exhausted));
+    ///         found.sort();
+    ///         found
+    ///     };
+    ///     let plain = all(0);
+    ///     assert!(!plain.is_empty());
+    ///     for k in [1, 4, 16] {
+    ///         assert_eq!(all(k), plain);
+    ///     }
+    /// }
+    /// ```
+    pub fn rollout(mut self, k: u32) -> Self {
+        self.config.rollout = k;
+        self
+    }
+
+    /// ```
+    /// use bf_search::{Dedup, SearchConfig, Searcher};
+    ///
+    /// let first = |dedup| {
+    ///     let config = SearchConfig::new(vec![0, 0]).dedup(dedup).build().unwrap();
+    ///     let mut searcher = Searcher::new(config);
+    ///     let codes: Vec<String> = searcher.solutions().take(4).map(|s| s.code).collect();
+    ///     (codes, searcher.stats().duplicates)
+    /// };
+    /// // `..` padded with moves and arithmetic after its output
+    /// assert_eq!(first(Dedup::Source), (vec!["..".into(), "..>".into(), "..<".into(), "..+".into()], 0));
+    /// let (codes, duplicates) = first(Dedup::Behavior { extra: 4, max_steps: 1000 });
+    /// assert_eq!(codes, ["..", "...", "..+.", "..-."]);
+    /// assert!(duplicates > 0);
+    /// ```
+    pub fn dedup(mut self, dedup: Dedup) -> Self {
+        self.config.dedup = dedup;
+        self
+    }
+
+    /// Check each child for a solution as soon as it is generated, instead
+    /// of when it is popped. The child completing the target can score well
+    /// below its parent (its `.` costs β and earns only 1) and then waits
+    /// behind everything in between; eagerly it is reported right after the
+    /// expansion that made it. It is still queued, and not reported again
+    /// when popped.
+    ///
+    /// ```
+    /// use bf_search::{SearchConfig, Searcher};
+    ///
+    /// let search = |eager| {
+    ///     let config = SearchConfig::new(vec![3, 3]).beta(6.0).eager(eager).build().unwrap();
+    ///     Searcher::new(config)
+    /// };
+    /// let mut plain = search(false);
+    /// let mut eager = search(true);
+    /// assert_eq!(plain.next_solution().unwrap().code, "+++..");
+    /// assert_eq!(eager.next_solution().unwrap().code, "+++..");
+    /// assert!(plain.stats().expansions > 20 * eager.stats().expansions);
+    ///
+    /// let mut codes: Vec<String> = eager.solutions().take(10).map(|s| s.code).collect();
+    /// codes.sort();
+    /// codes.dedup();
+    /// assert_eq!(codes.len(), 10);
+    /// ```
+    pub fn eager(mut self, eager: bool) -> Self {
+        self.config.eager = eager;
+        self
+    }
+
+    /// Score nodes by `SearchNode::live_len`, leaving out the loops they
+    /// skipped for good, so that they rank with the shorter programs they
+    /// will be reported as. A skipped loop then costs only its step, which
+    /// makes such branches cheap to pile up.
+    ///
+    /// ```
+    /// use bf_search::{Callbacks, Expansion, SearchConfig, Searcher};
+    ///
+    /// #[derive(Default)]
+    /// struct Log(Vec<(u64, Vec<f64>)>);
+    /// impl Callbacks for Log {
+    ///     fn on_expansion(&mut self, e: &Expansion) {
+    ///         self.0.push((e.seq, e.children.iter().map(|&(_, score)| score).collect()));
+    ///     }
+    /// }
+    /// let log = |live| {
+    ///     let config = SearchConfig::new(vec![1, 2])
+    ///         .max_expansions(Some(2))
+    ///         .live_length(live)
+    ///         .build()
+    ///         .unwrap();
+    ///     let mut log = Log::default();
+    ///     Searcher::new(config).run(&mut log);
+    ///     log.0
+    /// };
+    /// let (plain, live) = (log(false), log(true));
+    /// // the start node's children: `>`, `<`, `+`, `-`, then `[?]` skipped
+    /// assert_eq!(plain[0].1, [-2.0, -2.0, -2.0, -2.0, -3.0]);
+    /// assert_eq!(live[0].1, [-2.0, -2.0, -2.0, -2.0, -1.0]);
+    /// // ... which now goes first
+    /// assert_eq!((plain[1].0, live[1].0), (1, 5));
+    /// ```
+    pub fn live_length(mut self, live: bool) -> Self {
+        self.config.live_length = live;
+        self
+    }
+
+    /// Park the nodes that run past `max_steps` instead of dropping them.
+    /// When the frontier runs out before the first solution (or, with
+    /// `relax_after`, once that many expansions went by without one), the
+    /// cap is multiplied by `factor`, as often as it takes to fit at least
+    /// one of them, the parked nodes within the new cap are queued again,
+    /// and `Callbacks::on_relax` is told. `None` (the default) drops them.
+    ///
+    /// ```
+    /// use bf_search::{InstrSet, SearchConfig, Searcher, StopReason};
+    ///
+    /// // `+++++.` takes 6 steps, twice the cap
+    /// let search = |factor| {
+    ///     let config = SearchConfig::new(vec![5])
+    ///         .instr_set(InstrSet::parse("+.").unwrap())
+    ///         .max_steps(3)
+    ///         .retry_relaxed(factor)
+    ///         .build()
+    ///         .unwrap();
+    ///     Searcher::new(config)
+    /// };
+    /// let mut strict = search(None);
+    /// assert!(strict.next_solution().is_none());
+    /// assert_eq!(strict.stop_reason(), Some(StopReason::Exhausted));
+    ///
+    /// let mut relaxed = search(Some(1.5));
+    /// assert_eq!(relaxed.next_solution().unwrap().code, "+++++.");
+    /// assert_eq!(relaxed.config().max_steps(), 8); // 3, then 4.5 and 7.5 rounded up
+    /// // `++++`, then `++++++` and `+++++.`
+    /// let stats = relaxed.stats();
+    /// assert_eq!((stats.relaxations, stats.parked, stats.revived), (2, 3, 3));
+    /// ```
+    pub fn retry_relaxed(mut self, factor: Option<f64>) -> Self {
+        self.config.retry_relaxed = factor;
+        self
+    }
+
+    /// Under `retry_relaxed`, also relax after `n` expansions without a
+    /// solution (counted from the start or the last relaxation), even if
+    /// the frontier still has nodes.
+    pub fn relax_after(mut self, n: Option<u64>) -> Self {
+        self.config.relax_after = n;
+        self
+    }
+
+    /// Under `retry_relaxed`, park at most `n` nodes; the ones past that
+    /// are dropped as before. 100_000 by default.
+    pub fn max_parked(mut self, n: usize) -> Self {
+        self.config.max_parked = n;
+        self
+    }
+
+    /// Keep the frontier's estimated size (`SearchStats::memory`) within
+    /// `bytes`: when a push goes over, the worst-scoring nodes are evicted
+    /// until it is back under three quarters of the cap, each counted as
+    /// pruned by `PruneReason::MemoryCap`. The best nodes stay, so the
+    /// search goes on where it would have; only what it would have come
+    /// back to much later is lost, and an exhausted search no longer
+    /// proves there is no solution. Without a cap the frontier is not
+    /// sized, and `SearchStats::memory` stays 0.
+    ///
+    /// ```
+    /// use bf_search::{PruneReason, SearchConfig, Searcher};
+    ///
+    /// let search = |cap| {
+    ///     let config =
+    ///         SearchConfig::new(b"Hi".to_vec()).max_memory(cap).max_expansions(Some(20_000)).build().unwrap();
+    ///     let mut searcher = Searcher::new(config);
+    ///     searcher.next_solution();
+    ///     searcher
+    /// };
+    /// let free = search(None);
+    /// assert_eq!(free.stats().peak_memory, 0);
+    /// assert_eq!(free.stats().pruned.get(PruneReason::MemoryCap), 0);
+    ///
+    /// let capped = search(Some(64 << 10));
+    /// assert!(capped.stats().peak_memory <= 64 << 10);
+    /// assert!(capped.stats().pruned.get(PruneReason::MemoryCap) > 0);
+    /// assert!(capped.frontier_len() < free.frontier_len() / 10);
+    /// assert_eq!(capped.stats().expansions, 20_000);
+    /// ```
+    pub fn max_memory(mut self, bytes: Option<usize>) -> Self {
+        self.config.max_memory = bytes;
+        self
+    }
+
+    pub fn build(self) -> Result<SearchConfig, ConfigError> {
+        let c = self.config;
+        if c.target.is_empty() || c.cases.iter().any(|case| case.target.is_empty()) {
+            return Err(ConfigError::EmptyTarget);
+        }
+        check_weights(c.beta, c.gamma)?;
+        if c.max_steps == 0 {
+            return Err(ConfigError::ZeroMaxSteps);
+        }
+        if c.max_steps_per_byte == Some(0) {
+            return Err(ConfigError::ZeroStepsPerByte);
+        }
+        if c.progress_every == Some(0) {
+            return Err(ConfigError::ZeroProgressInterval);
+        }
+        if let Frontier::Bucket { width } = c.frontier {
+            if !(width.is_finite() && width > 0.0) {
+                return Err(ConfigError::BadBucketWidth(width));
+            }
+        }
+        if let Some(factor) = c.retry_relaxed {
+            if !(factor.is_finite() && factor > 1.0) {
+                return Err(ConfigError::BadRelaxFactor(factor));
+            }
+        }
+        if c.relax_after == Some(0) {
+            return Err(ConfigError::ZeroRelaxInterval);
+        }
+        if c.case_check_steps == Some(0) {
+            return Err(ConfigError::ZeroCaseCheckInterval);
+        }
+        if c.max_memory == Some(0) {
+            return Err(ConfigError::ZeroMaxMemory);
+        }
+        Ok(c)
+    }
+}
+
+fn check_weights(beta: f64, gamma: f64) -> Result<(), ConfigError> {
+    for (name, value) in [("beta", beta), ("gamma", gamma)] {
+        if !(0.0..=MAX_COEFFICIENT).contains(&value) {
+            return Err(ConfigError::BadCoefficient { name, value });
+        }
+    }
+    Ok(())
+}
+
+/// A concrete program reproducing the target.
+///
+/// Loops the search left unentered are not part of it, so no solution is
+/// a copy of another with `[]`s sprinkled in:
+///
+/// ```
+/// use bf_search::{SearchConfig, Searcher};
+///
+/// let mut searcher = Searcher::new(SearchConfig::new(vec![0, 0]).build().unwrap());
+/// let codes: Vec<String> = searcher.solutions().take(40).map(|s| s.code).collect();
+/// assert_eq!(codes[..3], ["..", "..>", "..<"]);
+/// assert!(codes.iter().all(|code| !code.contains("[]")));
+/// ```
+#[derive(Clone)]
+pub struct Solution {
+    pub code: String,
+    pub program: Shared<ProgramNode>, // see `ProgramNode::concretize_solution`
+    pub min_len: u32,
+    pub steps: u64, // interpreter steps the program takes to print the target
+    pub score: f64,
+    pub on_front: bool, // extended `Searcher::front` when found
+    pub state: Option<Interpreter>, // the run at that point; see `Interpreter::resume`
+}
+
+/// Whether a golf bound of `bound` drops `node`. A solution leaves out the
+/// loops it never entered, and the branch that never added them finds the
+/// same code in fewer steps; so only nodes without dead loops are kept, and
+/// of those the ones whose live length (which then only grows) is within
+/// the bound.
+fn past_golf_bound(node: &SearchNode, bound: u32) -> bool {
+    node.live_len() > bound || node.dead_len > 0
+}
+
+/// Why `Searcher::next_solution` returned `None`, or `Searcher::run` returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Exhausted,
+    Budget,
+    Cancelled,
+    Stopped, // `Callbacks::on_solution` returned `Break`
+    Aborted(SearchError), // ran out after skipping nodes; the last error
+}
+
+/// Why a child node was dropped instead of queued.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PruneReason {
+    Mismatch,      // output a byte differing from the target
+    Input,         // reached a `,` (input is not supported)
+    PrematureHalt, // halted before producing the whole target
+    StepLimit,     // past `max_steps`
+    StepsPerByte,  // past `max_steps_per_byte` since the last output
+    ExtraOutput,   // printed past the target under `exact_halt`
+    GolfBound,     // longer than `golf` or `optimize_steps` still allows
+    OtherCase,     // printed a wrong byte (or stopped early) on one of `cases`
+    MemoryCap,     // evicted from the frontier under `max_memory`
+    LengthCap,     // longer than `max_len`
+}
+
+impl PruneReason {
+    pub const ALL: [PruneReason; 10] = [
+        PruneReason::Mismatch,
+        PruneReason::Input,
+        PruneReason::PrematureHalt,
+        PruneReason::StepLimit,
+        PruneReason::StepsPerByte,
+        PruneReason::ExtraOutput,
+        PruneReason::GolfBound,
+        PruneReason::OtherCase,
+        PruneReason::MemoryCap,
+        PruneReason::LengthCap,
+    ];
+
+    /// A short name for summaries, e.g. "premature halt".
+    pub fn describe(self) -> &'static str {
+        match self {
+            PruneReason::Mismatch => "output mismatch",
+            PruneReason::Input => "input instruction",
+            PruneReason::PrematureHalt => "premature halt",
+            PruneReason::StepLimit => "step limit",
+            PruneReason::StepsPerByte => "steps per byte",
+            PruneReason::ExtraOutput => "output past target",
+            PruneReason::GolfBound => "golf bound",
+            PruneReason::OtherCase => "other case failed",
+            PruneReason::MemoryCap => "evicted under the memory cap",
+            PruneReason::LengthCap => "length cap",
+        }
+    }
+}
+
+/// How many branches were pruned for each reason.
+///
+/// ```
+/// use bf_search::{InstrSet, PruneReason, SearchConfig, Searcher, StopReason};
+///
+/// let exhaust = |target: Vec<u8>, set: &str| {
+///     let config = SearchConfig::new(target)
+///         .instr_set(InstrSet::parse(set).unwrap())
+///         .max_steps(3)
+///         .build()
+///         .unwrap();
+///     let mut searcher = Searcher::new(config);
+///     assert_eq!(searcher.next_solution().map(|s| s.code), None);
+///     assert_eq!(searcher.stop_reason(), Some(StopReason::Exhausted));
+///     searcher.stats().pruned.clone()
+/// };
+/// // Only `.` and `,` to choose from: `.` prints a wrong byte, `,` reads
+/// // input, and the empty program halts at once.
+/// let pruned = exhaust(vec![1], ".,");
+/// assert_eq!(pruned.get(PruneReason::Mismatch), 1);
+/// assert_eq!(pruned.get(PruneReason::Input), 1);
+/// assert_eq!(pruned.get(PruneReason::PrematureHalt), 1);
+/// assert_eq!(pruned.total(), 3);
+/// // `+` four times is one step too many
+/// let pruned = exhaust(vec![4], "+.");
+/// assert_eq!(pruned.get(PruneReason::StepLimit), 1);
+/// assert_eq!(pruned.get(PruneReason::Mismatch), 4); // after 0 to 3 `+`
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PruneCounts([u64; PruneReason::ALL.len()]);
+
+impl PruneCounts {
+    pub fn get(&self, reason: PruneReason) -> u64 {
+        self.0[reason as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    /// The reasons that pruned anything, with their counts, in `ALL` order.
+    pub fn iter(&self) -> impl Iterator<Item = (PruneReason, u64)> + '_ {
+        PruneReason::ALL.into_iter().map(|r| (r, self.get(r))).filter(|&(_, n)| n > 0)
+    }
+
+    fn add(&mut self, reason: PruneReason) {
+        self.0[reason as usize] += 1;
+    }
+}
+
+/// One expansion, as `Callbacks::on_expansion` sees it. Nodes are named by
+/// the sequence number they were queued under; the start node is 0.
+#[derive(Clone, Copy, Debug)]
+pub struct Expansion<'a> {
+    pub seq: u64,
+    pub score: f64,
+    pub children: &'a [(u64, f64)], // (seq, score), including a child a rollout goes on with
+}
+
+/// Hooks for `Searcher::run`. They only see shared references or copies, so
+/// they cannot disturb the search; every method defaults to a no-op.
+pub trait Callbacks {
+    /// Called once per distinct solution; `Break` ends the run.
+    fn on_solution(&mut self, _solution: &Solution) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called instead of `on_solution` when the program, run on its own,
+    /// does not reproduce the target; `output` is what it printed. That is
+    /// a bug in the search, and the program is not counted as a solution.
+    fn on_unverified(&mut self, _solution: &Solution, _output: &[u8]) {}
+
+    /// Under `SearchConfigBuilder::require_halt`, called instead of
+    /// `on_solution` for a program still running when its steps ran out.
+    fn on_non_halting(&mut self, _solution: &Solution) {}
+
+    /// Called every `SearchConfig::progress_every` expansions.
+    fn on_progress(&mut self, _stats: &SearchStats) {}
+
+    fn on_prune(&mut self, _reason: PruneReason) {}
+
+    /// Called after each expansion with the children it queued; see
+    /// `record` for logging these.
+    fn on_expansion(&mut self, _expansion: &Expansion) {}
+
+    /// Under `SearchConfigBuilder::retry_relaxed`, called after `max_steps`
+    /// was raised to `max_steps` and `revived` parked nodes were queued
+    /// again.
+    fn on_relax(&mut self, _max_steps: u64, _revived: usize) {}
+
+    /// Called when expanding a node failed; the node is skipped.
+    fn on_error(&mut self, _error: SearchError) {}
+
+    /// Polled once per expansion, just before it; returning true ends the
+    /// run with `StopReason::Cancelled`, and the node is expanded first when
+    /// the run resumes.
+    fn cancelled(&mut self) -> bool {
+        false
+    }
+}
+
+/// `next_solution_until` as callbacks: stop at the first solution.
+struct FirstSolution<F> {
+    cancel: F,
+    found: Option<Solution>,
+}
+
+impl<F: Fn() -> bool> Callbacks for FirstSolution<F> {
+    fn on_solution(&mut self, solution: &Solution) -> ControlFlow<()> {
+        self.found = Some(solution.clone());
+        ControlFlow::Break(())
+    }
+
+    fn cancelled(&mut self) -> bool {
+        (self.cancel)()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchStats {
+    pub started: Instant,
+    pub expansions: u64,
+    pub pushed: u64,
+    pub solutions: usize,
+    pub errors: u64, // nodes skipped after a `SearchError`
+    pub unverified: u64, // solutions whose program failed the re-run against the target
+    pub duplicates: u64, // under `Dedup::Behavior`, variants not reported
+    pub non_halting: u64, // under `require_halt`, programs not reported
+    pub dominated: u64, // under `pareto_only`, programs not reported
+    pub case_failures: u64, // under `cases`, programs failing another case, not reported
+    pub parked: u64, // under `retry_relaxed`, nodes set aside past `max_steps`
+    pub revived: u64, // ... and queued again after a relaxation
+    pub relaxations: u32,
+    pub clamped: u64, // node scores outside ±`SCORE_LIMIT` (or not a number), clamped
+    pub memory: usize, // under `max_memory`, estimated bytes held by the frontier (see `SearchNode::footprint`)
+    pub peak_memory: usize, // ... the most so far, after any eviction
+    pub pruned: PruneCounts,
+}
+
+// For the priority queue; the node itself waits in the `Slab`, so sifting
+// only moves these.
+struct HeapItem {
+    score: NotNan<f64>,
+    seq: u64, // tie-breaker for deterministic ordering
+    slot: usize,
+}
+
+impl PartialEq for HeapItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score && self.seq == other.seq
+    }
+}
+impl Eq for HeapItem {}
+
+impl PartialOrd for HeapItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for HeapItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Max-heap by score, then by smaller seq first
+        match self.score.cmp(&other.score) {
+            Ordering::Equal => self.seq.cmp(&other.seq).reverse(),
+            ord => ord,
+        }
+    }
+}
+
+/// The frontier's order over slab slots (see `Frontier`).
+enum Queue {
+    Heap(BinaryHeap<HeapItem>),
+    Buckets {
+        width: f64,
+        buckets: BTreeMap<i64, VecDeque<(NotNan<f64>, usize)>>,
+    },
+}
+
+impl Queue {
+    fn new(frontier: Frontier) -> Queue {
+        match frontier {
+            Frontier::Heap => Queue::Heap(BinaryHeap::new()),
+            Frontier::Bucket { width } => Queue::Buckets {
+                width,
+                buckets: BTreeMap::new(),
+            },
+        }
+    }
+
+    fn push(&mut self, score: NotNan<f64>, seq: u64, slot: usize) {
+        match self {
+            Queue::Heap(heap) => heap.push(HeapItem { score, seq, slot }),
+            Queue::Buckets { width, buckets } => {
+                let key = (score.into_inner() / *width).floor() as i64;
+                buckets.entry(key).or_default().push_back((score, slot));
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<(NotNan<f64>, usize)> {
+        match self {
+            Queue::Heap(heap) => heap.pop().map(|item| (item.score, item.slot)),
+            Queue::Buckets { buckets, .. } => {
+                let mut best = buckets.last_entry()?;
+                let item = best.get_mut().pop_front();
+                if best.get().is_empty() {
+                    best.remove();
+                }
+                item
+            }
+        }
+    }
+
+    /// Empty the queue, returning its slots in no particular order.
+    fn drain(&mut self) -> Vec<usize> {
+        match self {
+            Queue::Heap(heap) => heap.drain().map(|item| item.slot).collect(),
+            Queue::Buckets { buckets, .. } => {
+                let slots = buckets.values().flatten().map(|&(_, slot)| slot).collect();
+                buckets.clear();
+                slots
+            }
+        }
+    }
+
+    /// Offer slots to `evict` worst first, dropping each it takes, until it
+    /// declines one (which stays).
+    fn evict(&mut self, mut evict: impl FnMut(usize) -> bool) {
+        match self {
+            Queue::Heap(heap) => {
+                let mut items = std::mem::take(heap).into_sorted_vec();
+                let evicted = items.iter().take_while(|item| evict(item.slot)).count();
+                *heap = BinaryHeap::from(items.split_off(evicted));
+            }
+            Queue::Buckets { buckets, .. } => {
+                // Within a bucket the back is popped last.
+                while let Some(mut worst) = buckets.first_entry() {
+                    let &(_, slot) = worst.get().back().expect("buckets are never empty");
+                    if !evict(slot) {
+                        break;
+                    }
+                    worst.get_mut().pop_back();
+                    if worst.get().is_empty() {
+                        worst.remove();
+                    }
+                }
+            }
+        }
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(usize) -> bool) {
+        match self {
+            Queue::Heap(heap) => heap.retain(|item| keep(item.slot)),
+            Queue::Buckets { buckets, .. } => {
+                for bucket in buckets.values_mut() {
+                    bucket.retain(|&(_, slot)| keep(slot));
+                }
+                buckets.retain(|_, bucket| !bucket.is_empty());
+            }
+        }
+    }
+}
+
+/// The frontier's search nodes, with their sequence numbers and estimated
+/// sizes. A slot is freed when its node leaves the queue (popped, or
+/// dropped by the golf bound or the memory cap) and reused by a later push.
+#[derive(Default)]
+struct Slab {
+    nodes: Vec<Option<(u64, usize, SearchNode)>>,
+    free: Vec<usize>,
+    sized: bool, // estimate sizes at all; only a memory cap needs them
+    bytes: usize, // sum of the live nodes' sizes
+}
+
+impl Slab {
+    fn insert(&mut self, seq: u64, node: SearchNode) -> usize {
+        // Taken once, so that removing the node takes back the same amount.
+        let size = if self.sized { node.footprint() + std::mem::size_of::<HeapItem>() } else { 0 };
+        self.bytes += size;
+        match self.free.pop() {
+            Some(slot) => {
+                self.nodes[slot] = Some((seq, size, node));
+                slot
+            }
+            None => {
+                self.nodes.push(Some((seq, size, node)));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn get(&self, slot: usize) -> &SearchNode {
+        &self.nodes[slot].as_ref().expect("queued slots are live").2
+    }
+
+    fn seq(&self, slot: usize) -> u64 {
+        self.nodes[slot].as_ref().expect("queued slots are live").0
+    }
+
+    fn remove(&mut self, slot: usize) -> (u64, SearchNode) {
+        let (seq, size, node) = self.nodes[slot].take().expect("queued slots are live");
+        self.free.push(slot);
+        self.bytes -= size;
+        (seq, node)
+    }
+}
+
+/// Resumable best-first search: each call to `next_solution` continues the
+/// same frontier until the next distinct solution.
+///
+/// ```
+/// use bf_search::{SearchConfig, Searcher};
+///
+/// let mut searcher = Searcher::new(SearchConfig::new(vec![1, 2]).build().unwrap());
+/// let first = searcher.solutions().next().unwrap();
+/// assert_eq!(first.code, "+.+.");
+/// assert_eq!(searcher.stats().unverified, 0);
+/// ```
+pub struct Searcher {
+    config: SearchConfig,
+    queue: Queue,
+    slab: Slab,
+    interner: Interner, // shares program nodes between the frontier's trees
+    seq_counter: u64, // sequence number of the next queued (or carried) node
+    solutions_seen: HashSet<String>,
+    // Under `Dedup::Behavior`: (output, halted) of each reported solution,
+    // with the length of the shortest one reported.
+    behaviors: HashMap<(Vec<u8>, bool), u32>,
+    front: ParetoFront, // of reported solutions
+    stats: SearchStats,
+    best: Option<(usize, NotNan<f64>, SearchNode)>, // (correct, score, node)
+    golf_bound: Option<u32>,
+    stop_reason: Option<StopReason>,
+    last_error: Option<SearchError>, // of the latest node skipped
+    // The child a rollout expands next (or the node a cancelled run was about
+    // to expand), and how many more expansions the rollout may take after
+    // it. Kept here so that a resumed run continues it.
+    carry: Option<(NotNan<f64>, u64, SearchNode, u32)>,
+    children: Vec<(u64, f64)>, // reused for `Callbacks::on_expansion`
+    pending: VecDeque<Solution>, // found eagerly, not yet passed to `on_solution`
+    parked: Vec<SearchNode>, // past `max_steps`, in the order they were set aside
+    relaxed_at: u64, // expansions at the last relaxation
+}
+
+impl Searcher {
+    pub fn new(config: SearchConfig) -> Searcher {
+        let mut searcher = Searcher {
+            queue: Queue::new(config.frontier),
+            interner: if config.intern {
+                Interner::new()
+            } else {
+                Interner::disabled()
+            },
+            slab: Slab {
+                sized: config.max_memory.is_some(),
+                ..Slab::default()
+            },
+            config,
+            seq_counter: 0,
+            solutions_seen: HashSet::new(),
+            behaviors: HashMap::new(),
+            front: ParetoFront::new(),
+            stats: SearchStats {
+                started: Instant::now(),
+                expansions: 0,
+                pushed: 0,
+                solutions: 0,
+                errors: 0,
+                unverified: 0,
+                duplicates: 0,
+                non_halting: 0,
+                dominated: 0,
+                case_failures: 0,
+                parked: 0,
+                revived: 0,
+                relaxations: 0,
+                clamped: 0,
+                memory: 0,
+                peak_memory: 0,
+                pruned: PruneCounts::default(),
+            },
+            best: None,
+            golf_bound: None,
+            stop_reason: None,
+            last_error: None,
+            carry: None,
+            children: Vec::new(),
+            pending: VecDeque::new(),
+            parked: Vec::new(),
+            relaxed_at: 0,
+        };
+        if searcher.config.from_scratch {
+            let start_node = SearchNode::initial().with_tape(searcher.config.tape_init.clone());
+            let score = searcher.score(&start_node);
+            searcher.push(start_node, score);
+        }
+        let mut seeded = HashSet::new();
+        for template in searcher.config.idioms.clone() {
+            if !seeded.insert(template.to_string()) {
+                continue;
+            }
+            if let Some(node) = searcher.seed(&template) {
+                let score = searcher.score(&node);
+                searcher.push(node, score);
+            }
+        }
+        searcher.stats.pushed = 0;
+        searcher
+    }
+
+    pub fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// The most promising node seen so far: most correct bytes, then highest score.
+    pub fn best(&self) -> Option<&SearchNode> {
+        self.best.as_ref().map(|(_, _, node)| node)
+    }
+
+    /// The score of `best`, under the current β and γ.
+    pub fn best_score(&self) -> Option<f64> {
+        self.best.as_ref().map(|(_, score, _)| score.into_inner())
+    }
+
+    /// Nodes waiting in the frontier.
+    pub fn frontier_len(&self) -> usize {
+        self.slab.nodes.len() - self.slab.free.len() + self.carry.is_some() as usize
+    }
+
+    /// Change β and γ between runs. Every open node is scored again and
+    /// the frontier rebuilt, ties still going to the node queued first, so
+    /// the next expansion already follows the new weights.
+    ///
+    /// ```
+    /// use bf_search::{ConfigError, Frontier, SearchConfig, Searcher};
+    ///
+    /// let search = |frontier| {
+    ///     Searcher::new(SearchConfig::new(vec![3, 6, 9]).frontier(frontier).build().unwrap())
+    /// };
+    /// let mut plain = search(Frontier::Heap);
+    /// let mut steered = search(Frontier::Heap);
+    /// assert_eq!(plain.next_solution().unwrap().code, "+[++.+]");
+    /// assert_eq!(steered.next_solution().unwrap().code, "+[++.+]");
+    /// steered.set_weights(0.2, 1.0).unwrap();
+    /// assert_eq!(plain.next_solution().unwrap().code, "++[+.++]");
+    /// assert_eq!(steered.next_solution().unwrap().code, "+++.+++.+++.");
+    ///
+    /// let err = steered.set_weights(-1.0, 1.0).unwrap_err();
+    /// assert_eq!(err, ConfigError::BadCoefficient { name: "beta", value: -1.0 });
+    /// assert_eq!(steered.config().beta(), 0.2);
+    ///
+    /// // the same weights again change nothing, ties included
+    /// for frontier in [Frontier::Heap, Frontier::Bucket { width: 0.5 }] {
+    ///     let (mut plain, mut same) = (search(frontier), search(frontier));
+    ///     for _ in 0..3 {
+    ///         let a = plain.next_solution().unwrap().code;
+    ///         same.set_weights(1.0, 1.0).unwrap();
+    ///         assert_eq!(same.next_solution().unwrap().code, a);
+    ///         assert_eq!(same.stats().expansions, plain.stats().expansions);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_weights(&mut self, beta: f64, gamma: f64) -> Result<(), ConfigError> {
+        check_weights(beta, gamma)?;
+        self.config.beta = beta;
+        self.config.gamma = gamma;
+        // Re-queued in the order they were first queued, which is all the
+        // bucket frontier has to break ties by.
+        let mut slots = self.queue.drain();
+        slots.sort_unstable_by_key(|&slot| self.slab.seq(slot));
+        for slot in slots {
+            let score = score_node(&self.config, &mut self.stats, self.slab.get(slot));
+            self.queue.push(score, self.slab.seq(slot), slot);
+        }
+        if let Some((_, seq, node, left)) = self.carry.take() {
+            self.carry = Some((self.score(&node), seq, node, left));
+        }
+        if let Some((correct, _, node)) = self.best.take() {
+            self.best = Some((correct, self.score(&node), node));
+        }
+        Ok(())
+    }
+
+    /// The reported solutions no other reported one is both as short as and
+    /// as fast as (by `Solution::steps`).
+    pub fn front(&self) -> &ParetoFront {
+        &self.front
+    }
+
+    /// Never report `code`, as if it had been reported already (say, by an
+    /// earlier run; see `archive`). Returns false if it was.
+    ///
+    /// ```
+    /// use bf_search::{SearchConfig, Searcher};
+    ///
+    /// let mut searcher = Searcher::new(SearchConfig::new(vec![2]).build().unwrap());
+    /// assert!(searcher.mark_seen("++.".into()));
+    /// assert!(!searcher.mark_seen("++.".into()));
+    /// assert_eq!(searcher.next_solution().unwrap().code, "++.>");
+    /// ```
+    pub fn mark_seen(&mut self, code: String) -> bool {
+        self.solutions_seen.insert(code)
+    }
+
+    /// Under `golf` or `optimize_steps`, the longest program length still
+    /// being searched.
+    pub fn golf_bound(&self) -> Option<u32> {
+        self.golf_bound
+    }
+
+    /// Why the last `next_solution` call returned `None`.
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// Iterate over solutions; dropping the iterator and calling this again
+    /// resumes the same search.
+    pub fn solutions(&mut self) -> Solutions<'_> {
+        Solutions { searcher: self }
+    }
+
+    pub fn next_solution(&mut self) -> Option<Solution> {
+        self.next_solution_until(|| false)
+    }
+
+    /// Like `next_solution`, but gives up with `StopReason::Cancelled` as
+    /// soon as `cancel` returns true (polled once per expansion).
+    pub fn next_solution_until(&mut self, cancel: impl Fn() -> bool) -> Option<Solution> {
+        let mut first = FirstSolution { cancel, found: None };
+        self.run(&mut first);
+        if first.found.is_some() {
+            self.stop_reason = None;
+        }
+        first.found
+    }
+
+    /// Search until the frontier or budget runs out, `callbacks` cancels, or
+    /// `on_solution` returns `Break`. Can be called again to resume.
+    ///
+    /// A node whose expansion fails with a `SearchError` is skipped and
+    /// counted in `stats().errors`. The subtree under it was never
+    /// searched, so once the frontier runs out the run ends with
+    /// `StopReason::Aborted` rather than `Exhausted`.
+    ///
+    /// ```
+    /// use bf_search::{Callbacks, SearchConfig, Searcher, Solution, StopReason};
+    /// use std::ops::ControlFlow;
+    ///
+    /// struct Two(Vec<String>);
+    /// impl Callbacks for Two {
+    ///     fn on_solution(&mut self, s: &Solution) -> ControlFlow<()> {
+    ///         self.0.push(s.code.clone());
+    ///         if self.0.len() < 2 { ControlFlow::Continue(()) } else { ControlFlow::Break(()) }
+    ///     }
+    /// }
+    ///
+    /// let mut searcher = Searcher::new(SearchConfig::new(vec![1, 2]).build().unwrap());
+    /// let mut found = Two(Vec::new());
+    /// assert_eq!(searcher.run(&mut found), StopReason::Stopped);
+    /// assert_eq!(found.0.len(), 2);
+    /// ```
+    pub fn run(&mut self, callbacks: &mut impl Callbacks) -> StopReason {
+        let reason = loop {
+            if let Some(solution) = self.pending.pop_front() {
+                if callbacks.on_solution(&solution).is_break() {
+                    break StopReason::Stopped;
+                }
+                continue;
+            }
+            if self.config.max_expansions.is_some_and(|n| self.stats.expansions >= n)
+                || self.config.time_limit.is_some_and(|t| self.stats.started.elapsed() >= t)
+            {
+                break StopReason::Budget;
+            }
+            let overdue =
+                self.config.relax_after.is_some_and(|n| self.stats.expansions - self.relaxed_at >= n);
+            if overdue && self.relax(callbacks) {
+                continue;
+            }
+            let (score, seq, node, left) = match self.carry.take() {
+                Some(carried) => carried,
+                None => {
+                    let Some((score, slot)) = self.queue.pop() else {
+                        if self.relax(callbacks) {
+                            continue;
+                        }
+                        break self.last_error.map_or(StopReason::Exhausted, StopReason::Aborted);
+                    };
+                    let (seq, node) = self.slab.remove(slot);
+                    self.note_memory();
+                    (score, seq, node, self.config.rollout)
+                }
+            };
+            if callbacks.cancelled() {
+                // Next in line when the run resumes, as a rollout child is.
+                self.carry = Some((score, seq, node, left));
+                break StopReason::Cancelled;
+            }
+
+            let solution = self.check_solution(&node, score, callbacks);
+            if let Some((score, seq, child)) = self.expand(seq, &node, score, left > 0, callbacks) {
+                self.carry = Some((score, seq, child, left - 1));
+            }
+            if let Some(solution) = solution {
+                if callbacks.on_solution(&solution).is_break() {
+                    break StopReason::Stopped;
+                }
+            }
+        };
+        self.stop_reason = Some(reason);
+        reason
+    }
+
+    /// If `node` already matches the full target (and under `exact_halt`, has
+    /// halted), its minimal concretization is a solution, unless it was
+    /// reported before (or is too long to golf).
+    /// The concrete program is run again to confirm it prints the target
+    /// (and, under `Dedup::Behavior`, to compare it with earlier solutions).
+    fn check_solution(
+        &mut self,
+        node: &SearchNode,
+        score: NotNan<f64>,
+        callbacks: &mut impl Callbacks,
+    ) -> Option<Solution> {
+        if node.correct < self.config.target.len() || self.config.exact_halt && !node.halted() {
+            return None;
+        }
+        // Build a concrete minimal program by setting all holes to Empty
+        // (and leaving out the loops that were only ever skipped)
+        let concrete = node.root.concretize_solution();
+        if self.golf_bound.is_some_and(|b| concrete.min_len > b) {
+            return None;
+        }
+        let code = ProgramNode::to_bf_string(&concrete);
+        if !self.solutions_seen.insert(code.clone()) {
+            return None;
+        }
+        let mut solution = Solution {
+            code,
+            min_len: concrete.min_len,
+            program: concrete,
+            steps: node.steps,
+            score: score.into_inner(),
+            on_front: false,
+            state: Interpreter::resume(node, &self.config.target),
+        };
+        let target_len = self.config.target.len();
+        let mut run = Interpreter::new(solution.program.clone())
+            .with_tape(self.config.tape_init.clone())
+            .with_step_cap(node.steps);
+        // An error stops the run early, which the short output shows.
+        let _ = run.run_to_limit(target_len);
+        let mut verified = run.output() == self.config.target;
+        // Fewer than the node's if it skipped loops left out of `concrete`
+        solution.steps = run.steps();
+        if self.config.exact_halt {
+            // ... and then it must stop before printing anything more
+            run = run.with_step_cap(node.steps + 1);
+            verified &= run.run_to_limit(target_len + 1) == Ok(RunEnd::Halted);
+        }
+        if !verified {
+            self.stats.unverified += 1;
+            callbacks.on_unverified(&solution, run.output());
+            return None;
+        }
+        let cases = &self.config.cases;
+        if !cases.iter().all(|case| passes(&solution.program, case, self.config.max_steps, self.config.exact_halt)) {
+            self.stats.case_failures += 1;
+            return None;
+        }
+        if let Some(cap) = self.config.require_halt {
+            // The program stays in `solutions_seen`: running it again would
+            // not end differently. It is kept out of `behaviors`, though.
+            let mut probe = run.clone().with_step_cap(cap);
+            if probe.run_to_halt() != Ok(RunEnd::Halted) {
+                self.stats.non_halting += 1;
+                callbacks.on_non_halting(&solution);
+                return None;
+            }
+        }
+        let filter = self.config.pareto_only || self.config.optimize_steps.is_some();
+        if filter && self.front.dominated(solution.min_len, solution.steps) {
+            // Kept out of `behaviors` too, like a non-halting program.
+            self.stats.dominated += 1;
+            return None;
+        }
+        // Under `optimize_steps`, a faster variant is the point.
+        let dedup = if self.config.optimize_steps.is_some() { Dedup::Source } else { self.config.dedup };
+        if let Dedup::Behavior { extra, max_steps } = dedup {
+            let mut run = run.with_step_cap(max_steps.max(node.steps));
+            let halted = run.run_to_limit(target_len + extra) == Ok(RunEnd::Halted);
+            let shortest = self.behaviors.entry((run.into_output(), halted)).or_insert(u32::MAX);
+            if *shortest <= solution.min_len {
+                self.stats.duplicates += 1;
+                return None;
+            }
+            *shortest = solution.min_len;
+        }
+        solution.on_front = self.front.insert(FrontEntry {
+            min_len: solution.min_len,
+            steps: solution.steps,
+            code: solution.code.clone(),
+        });
+        self.stats.solutions += 1;
+        let golf = self.config.golf.then(|| solution.min_len.saturating_sub(1));
+        let optimize = self.config.optimize_steps.map(|slack| {
+            let shortest = self.front.entries().first().map_or(solution.min_len, |e| e.min_len);
+            shortest.saturating_add(slack)
+        });
+        let bound = golf.into_iter().chain(optimize).min();
+        if let Some(bound) = bound.filter(|&b| self.golf_bound.is_none_or(|old| b < old)) {
+            self.golf_bound = Some(bound);
+            let slab = &mut self.slab;
+            self.queue.retain(|slot| {
+                let keep = !past_golf_bound(slab.get(slot), bound);
+                if !keep {
+                    slab.remove(slot);
+                }
+                keep
+            });
+            self.note_memory();
+        }
+        Some(solution)
+    }
+
+    /// Advance `node` (numbered `seq`) by one interpreter step and queue the
+    /// surviving children. With `keep_best`, the best-scoring child (the
+    /// first of equals) is returned instead of queued.
+    fn expand(
+        &mut self,
+        seq: u64,
+        node: &SearchNode,
+        score: NotNan<f64>,
+        keep_best: bool,
+        callbacks: &mut impl Callbacks,
+    ) -> Option<(NotNan<f64>, u64, SearchNode)> {
+        // Guard against runaway nodes
+        if node.steps > self.config.max_steps {
+            return None;
+        }
+
+        let target_len = self.config.target.len();
+        let outcome = step_once_with(
+            node,
+            &self.config.target,
+            &self.config.instr_set,
+            AdvancePolicy::Search,
+            &mut self.interner,
+        );
+        self.stats.expansions += 1;
+        let mut log = std::mem::take(&mut self.children);
+        log.clear();
+        let outcomes = match outcome {
+            Ok(StepOutcome::Expanded(outcomes)) => outcomes,
+            // The node itself halted: it was checked as a solution when popped
+            Ok(StepOutcome::Halted(_)) => Vec::new(),
+            Ok(outcome) => vec![outcome],
+            Err(e) => {
+                self.stats.errors += 1;
+                self.last_error = Some(e);
+                callbacks.on_error(e);
+                self.log_expansion(seq, score, log, callbacks);
+                return None;
+            }
+        };
+        let every = self.config.progress_every;
+        if every.is_some_and(|n| self.stats.expansions.is_multiple_of(n)) {
+            callbacks.on_progress(&self.stats);
+        }
+
+        let mut survivors = Vec::new(); // the children, if one may be kept
+        for outcome in outcomes {
+            let mut child = match outcome {
+                StepOutcome::Advanced(child) => child,
+                // A hole filled with nothing ended the program
+                StepOutcome::Halted(child) if child.correct >= target_len => child,
+                StepOutcome::Halted(_) => {
+                    self.prune(PruneReason::PrematureHalt, callbacks);
+                    continue;
+                }
+                StepOutcome::Pruned(reason) => {
+                    self.prune(reason, callbacks);
+                    continue;
+                }
+                StepOutcome::Expanded(_) => continue, // not nested
+            };
+
+            if self.config.exact_halt && !child.overflow.is_empty() {
+                self.prune(PruneReason::ExtraOutput, callbacks);
+                continue;
+            }
+
+            if child.steps > self.config.max_steps {
+                if self.config.retry_relaxed.is_some() && self.parked.len() < self.config.max_parked {
+                    self.parked.push(child);
+                    self.stats.parked += 1;
+                } else {
+                    self.prune(PruneReason::StepLimit, callbacks);
+                }
+                continue;
+            }
+
+            if self.config.max_steps_per_byte.is_some_and(|r| child.steps - child.last_output > r) {
+                self.prune(PruneReason::StepsPerByte, callbacks);
+                continue;
+            }
+
+            if self.golf_bound.is_some_and(|b| past_golf_bound(&child, b)) {
+                self.prune(PruneReason::GolfBound, callbacks);
+                continue;
+            }
+
+            if self.config.max_len.is_some_and(|l| child.root.min_len > l) {
+                self.prune(PruneReason::LengthCap, callbacks);
+                continue;
+            }
+
+            let completed = child.correct >= target_len && node.correct < target_len;
+            let checkpoint = self.config.case_check_steps.is_some_and(|k| child.steps.is_multiple_of(k));
+            if !self.config.cases.is_empty() && (completed || checkpoint) {
+                let progress = self.config.cases.iter().map(|case| case_progress(&child.root, case, self.config.max_steps));
+                match progress.sum::<Option<usize>>() {
+                    Some(correct) => child.case_correct = correct,
+                    None => {
+                        self.prune(PruneReason::OtherCase, callbacks);
+                        continue;
+                    }
+                }
+            }
+
+            let score = self.score(&child);
+
+            // Reported once this expansion is done; `solutions_seen` keeps
+            // the child from being reported again when it is popped.
+            if self.config.eager && child.correct >= target_len {
+                if let Some(solution) = self.check_solution(&child, score, callbacks) {
+                    self.pending.push_back(solution);
+                    if self.golf_bound.is_some_and(|b| past_golf_bound(&child, b)) {
+                        self.prune(PruneReason::GolfBound, callbacks);
+                        continue;
+                    }
+                }
+            }
+
+            if keep_best {
+                survivors.push((score, child));
+            } else {
+                log.push((self.push(child, score), score.into_inner()));
+            }
+        }
+
+        let kept =
+            (0..survivors.len()).reduce(|a, b| if survivors[b].0 > survivors[a].0 { b } else { a });
+        let mut carried = None;
+        for (i, (score, child)) in survivors.into_iter().enumerate() {
+            let seq = if Some(i) == kept {
+                self.note_best(&child, score);
+                let seq = self.next_seq();
+                carried = Some((score, seq, child));
+                seq
+            } else {
+                self.push(child, score)
+            };
+            log.push((seq, score.into_inner()));
+        }
+        self.log_expansion(seq, score, log, callbacks);
+        carried
+    }
+
+    /// Under `retry_relaxed` and before the first solution, raise
+    /// `max_steps` until some parked node fits and queue those that do.
+    /// False if there was nothing to relax.
+    fn relax(&mut self, callbacks: &mut impl Callbacks) -> bool {
+        let Some(factor) = self.config.retry_relaxed else {
+            return false;
+        };
+        let Some(least) = self.parked.iter().map(|n| n.steps).min() else {
+            return false;
+        };
+        if self.stats.solutions > 0 {
+            return false;
+        }
+        loop {
+            let cap = self.config.max_steps;
+            let raised = (cap as f64 * factor).ceil() as u64; // saturates
+            self.config.max_steps = raised.max(cap.saturating_add(1));
+            if self.config.max_steps >= least {
+                break;
+            }
+        }
+        let cap = self.config.max_steps;
+        let (fit, parked): (Vec<_>, _) = std::mem::take(&mut self.parked)
+            .into_iter()
+            .partition(|n: &SearchNode| n.steps <= cap);
+        self.parked = parked;
+        let revived = fit.len();
+        for node in fit {
+            let score = self.score(&node);
+            self.push(node, score);
+        }
+        self.stats.revived += revived as u64;
+        self.stats.relaxations += 1;
+        self.relaxed_at = self.stats.expansions;
+        callbacks.on_relax(cap, revived);
+        true
+    }
+
+    fn log_expansion(
+        &mut self,
+        seq: u64,
+        score: NotNan<f64>,
+        children: Vec<(u64, f64)>,
+        callbacks: &mut impl Callbacks,
+    ) {
+        callbacks.on_expansion(&Expansion {
+            seq,
+            score: score.into_inner(),
+            children: &children,
+        });
+        self.children = children;
+    }
+
+    fn score(&mut self, node: &SearchNode) -> NotNan<f64> {
+        score_node(&self.config, &mut self.stats, node)
+    }
+
+    fn prune(&mut self, reason: PruneReason, callbacks: &mut impl Callbacks) {
+        self.stats.pruned.add(reason);
+        callbacks.on_prune(reason);
+    }
+
+    fn note_best(&mut self, node: &SearchNode, score: NotNan<f64>) {
+        let better = match &self.best {
+            Some((correct, best_score, _)) => (node.correct, score) > (*correct, *best_score),
+            None => true,
+        };
+        if better {
+            self.best = Some((node.correct, score, node.clone()));
+        }
+    }
+
+    /// `template` run up to its first hole, unless it is left out; see
+    /// `SearchConfigBuilder::idioms`.
+    fn seed(&mut self, template: &Program) -> Option<SearchNode> {
+        let set = &self.config.instr_set;
+        let allowed = template.to_string().chars().all(|c| match c {
+            '[' | ']' => set.loops,
+            '?' => true,
+            c => Instr::from_char(c).is_some_and(|i| set.contains(i)),
+        });
+        if !allowed {
+            return None;
+        }
+        let target = &self.config.target;
+        let mut node = SearchNode::concrete(template.root.clone()).with_tape(self.config.tape_init.clone());
+        let seeded = loop {
+            if node.steps > self.config.max_steps {
+                break Err(PruneReason::StepLimit);
+            }
+            match node.pc.kind {
+                PKind::Hole => break Ok(node),
+                PKind::Loop { .. } if node.loop_stack.is_empty() && node.get_cell(node.dp) == 0 => {
+                    return None;
+                }
+                _ => {}
+            }
+            match exec_known_step(node, target) {
+                Ok(StepOutcome::Advanced(next)) => node = next,
+                Ok(StepOutcome::Halted(at)) if at.correct >= target.len() => break Ok(at),
+                Ok(StepOutcome::Halted(_)) => break Err(PruneReason::PrematureHalt),
+                Ok(StepOutcome::Pruned(reason)) => break Err(reason),
+                Ok(StepOutcome::Expanded(_)) => unreachable!("a known step expands nothing"),
+                Err(e) => {
+                    self.stats.errors += 1;
+                    self.last_error = Some(e);
+                    return None;
+                }
+            }
+        };
+        let reason = match seeded {
+            Ok(node) if self.config.exact_halt && !node.overflow.is_empty() => PruneReason::ExtraOutput,
+            Ok(node) if self.config.max_steps_per_byte.is_some_and(|r| node.steps - node.last_output > r) => {
+                PruneReason::StepsPerByte
+            }
+            Ok(mut node) => {
+                let progress = self.config.cases.iter().map(|case| case_progress(&node.root, case, self.config.max_steps));
+                match progress.sum::<Option<usize>>() {
+                    Some(correct) => {
+                        node.case_correct = correct;
+                        return Some(node);
+                    }
+                    None => PruneReason::OtherCase,
+                }
+            }
+            Err(reason) => reason,
+        };
+        self.stats.pruned.add(reason);
+        None
+    }
+
+    /// Queue `node` as if the search had generated it, bypassing every
+    /// check. Only for testing the search's own guards.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn inject(&mut self, node: SearchNode) {
+        let score = self.score(&node);
+        self.push(node, score);
+    }
+
+    /// Queue `node`, returning its sequence number.
+    fn push(&mut self, node: SearchNode, score: NotNan<f64>) -> u64 {
+        self.note_best(&node, score);
+        let seq = self.next_seq();
+        let slot = self.slab.insert(seq, node);
+        self.queue.push(score, seq, slot);
+        self.stats.pushed += 1;
+        if let Some(cap) = self.config.max_memory.filter(|&cap| self.slab.bytes > cap) {
+            let (slab, pruned) = (&mut self.slab, &mut self.stats.pruned);
+            self.queue.evict(|slot| {
+                let evict = slab.bytes > cap / 4 * 3;
+                if evict {
+                    slab.remove(slot);
+                    pruned.add(PruneReason::MemoryCap);
+                }
+                evict
+            });
+        }
+        self.note_memory();
+        seq
+    }
+
+    fn note_memory(&mut self) {
+        self.stats.memory = self.slab.bytes;
+        self.stats.peak_memory = self.stats.peak_memory.max(self.slab.bytes);
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        let seq = self.seq_counter;
+        self.seq_counter = self.seq_counter.wrapping_add(1);
+        seq
+    }
+}
+
+/// How many bytes of `case.target` the partial program `root` prints,
+/// run on `case.tape`, before it first needs a hole filled (all of them if
+/// it gets that far). `None` if it has gone wrong by then: a wrong byte, a
+/// halt or a `,`, or more than `max_steps` steps.
+fn case_progress(root: &Shared<ProgramNode>, case: &Case, max_steps: u64) -> Option<usize> {
+    let mut node = SearchNode::concrete(root.clone()).with_tape(case.tape.clone());
+    while node.correct < case.target.len() {
+        if node.steps > max_steps {
+            return None;
+        }
+        match exec_known_step(node, &case.target).ok()? {
+            StepOutcome::Advanced(next) => node = next,
+            StepOutcome::Halted(at) if matches!(at.pc.kind, PKind::Hole) => return Some(at.correct),
+            _ => return None,
+        }
+    }
+    Some(node.correct)
+}
+
+/// Whether the concrete `program` prints `case.target` from `case.tape`
+/// within `max_steps` (and under `exact_halt`, then halts).
+fn passes(program: &Shared<ProgramNode>, case: &Case, max_steps: u64, exact_halt: bool) -> bool {
+    let len = case.target.len();
+    let mut run = Interpreter::new(program.clone()).with_tape(case.tape.clone()).with_step_cap(max_steps);
+    // An error stops the run early, which the short output shows.
+    let _ = run.run_to_limit(len);
+    run.output() == case.target && (!exact_halt || run.run_to_limit(len + 1) == Ok(RunEnd::Halted))
+}
+
+/// `node`'s score under `config`, counting it in `stats` if it had to be
+/// clamped. Within the coefficient bounds `build` checks it never is; the
+/// count is a guard.
+fn score_node(config: &SearchConfig, stats: &mut SearchStats, node: &SearchNode) -> NotNan<f64> {
+    let (score, clamped) = node.checked_score(config.live_length, config.beta, config.gamma);
+    stats.clamped += clamped as u64;
+    NotNan::new(score).expect("clamped scores are numbers")
+}
+
+/// Iterator over the solutions of a `Searcher`, in discovery order.
+pub struct Solutions<'a> {
+    searcher: &'a mut Searcher,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        self.searcher.next_solution()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Log(Vec<(String, Vec<u8>)>);
+
+    impl Callbacks for Log {
+        fn on_unverified(&mut self, s: &Solution, output: &[u8]) {
+            self.0.push((s.code.clone(), output.to_vec()));
+        }
+    }
+
+    #[test]
+    fn unverified_programs_are_not_solutions() {
+        let config = SearchConfig::new(vec![1, 2]).max_expansions(Some(1)).build().unwrap();
+        let mut searcher = Searcher::new(config);
+        // claims the whole target before running anything
+        let mut corrupt = SearchNode::initial();
+        corrupt.correct = 2;
+        searcher.inject(corrupt);
+        let mut log = Log::default();
+        searcher.run(&mut log);
+        assert_eq!(log.0, vec![(String::new(), vec![])]);
+        assert_eq!((searcher.stats().solutions, searcher.stats().unverified), (0, 1));
+    }
+}
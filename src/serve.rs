@@ -0,0 +1,352 @@
+//! A JSON-RPC 2.0 service over a pair of streams, one message per line
+//! (`bf_search serve` runs it on stdin and stdout), for editors and
+//! notebooks that keep one process around instead of launching one per
+//! query.
+//!
+//! Methods:
+//!
+//! - `start_search {target, config?}`: `target` is an array of bytes,
+//!   `config` any of `beta`, `gamma`, `max_steps`, `max_steps_per_byte`,
+//!   `instr_set`, `tape_init`, `golf`, `exact_halt`, `pareto_only` and
+//!   `max_memory`, named as in `SearchConfigBuilder`. Returns
+//!   `{search}`, an id for the calls below.
+//! - `step {search, expansions}`: run up to that many more expansions;
+//!   returns the `solutions` found meanwhile (each with `code`, `min_len`,
+//!   `steps` and `score`) and the search's totals so far: `expansions`,
+//!   `pushed`, `frontier`, `memory`, `solutions_total`, `pruned` and
+//!   `done`. Any number of searches may be open at once, each with its own
+//!   frontier, and steps on them interleave freely.
+//! - `run_program {code, limit, max_steps?}`: run `code` until it has
+//!   printed `limit` bytes or stopped; returns `{output, steps, end}`.
+//! - `cancel {search}`: drop a search and its frontier.
+//!
+//! Each response carries the id of its request; a request without one is
+//! a notification and gets no response. Input that is not a well-formed
+//! call gets a JSON-RPC error and the service goes on; it stops at the end
+//! of the input.
+//!
+//! ```
+//! use bf_search::serve::serve;
+//!
+//! let session = [
+//!     r#"{"jsonrpc": "2.0", "id": 1, "method": "start_search", "params": {"target": [2]}}"#,
+//!     r#"{"jsonrpc": "2.0", "id": "a", "method": "step", "params": {"search": 1, "expansions": 100}}"#,
+//!     r#"{"jsonrpc": "2.0", "id": 2, "method": "run_program", "params": {"code": "++.", "limit": 1}}"#,
+//!     r#"{"jsonrpc": "2.0", "id": 3, "method": "fly"}"#,
+//!     r#"not json"#,
+//! ];
+//! let mut out = Vec::new();
+//! serve(session.join("\n").as_bytes(), &mut out).unwrap();
+//! let replies: Vec<serde_json::Value> =
+//!     String::from_utf8(out).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+//! assert_eq!(replies[0]["result"]["search"], 1);
+//! assert_eq!(replies[1]["id"], "a");
+//! assert_eq!(replies[1]["result"]["solutions"][0]["code"], "++.");
+//! assert_eq!(replies[2]["result"]["output"], serde_json::json!([2]));
+//! assert_eq!(replies[3]["error"]["code"], -32601);
+//! assert_eq!(replies[4]["error"]["code"], -32700);
+//! ```
+
+use crate::interp::{Interpreter, RunEnd};
+use crate::{Callbacks, InstrSet, SearchConfig, Searcher, Solution, StopReason, Tape};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::ops::ControlFlow;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// Search options of `start_search`; omitted ones keep the defaults.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RpcConfig {
+    beta: Option<f64>,
+    gamma: Option<f64>,
+    max_steps: Option<u64>,
+    max_steps_per_byte: Option<u64>,
+    instr_set: Option<String>,
+    tape_init: Option<String>,
+    golf: bool,
+    exact_halt: bool,
+    pareto_only: bool,
+    max_memory: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StartParams {
+    target: Vec<u8>,
+    #[serde(default)]
+    config: RpcConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StepParams {
+    search: u64,
+    expansions: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchParams {
+    search: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RunParams {
+    code: String,
+    limit: usize,
+    #[serde(default = "default_max_steps")]
+    max_steps: u64,
+}
+
+fn default_max_steps() -> u64 {
+    1_000_000
+}
+
+#[derive(Serialize)]
+struct RpcSolution {
+    code: String,
+    min_len: u32,
+    steps: u64,
+    score: f64,
+}
+
+#[derive(Serialize)]
+struct RpcPruned {
+    reason: &'static str, // `PruneReason::describe`
+    count: u64,
+}
+
+/// What `step` returns.
+#[derive(Serialize)]
+struct Progress {
+    solutions: Vec<RpcSolution>, // found during this call
+    expansions: u64,             // totals since `start_search`
+    pushed: u64,
+    frontier: usize,
+    memory: usize, // `SearchStats::memory`
+    solutions_total: usize,
+    pruned: Vec<RpcPruned>, // totals by reason, for the reasons that pruned anything
+    done: bool, // the search space is exhausted (or the search aborted)
+}
+
+/// Collects solutions until `left` expansions have been spent.
+struct Slice {
+    left: u64,
+    found: Vec<RpcSolution>,
+}
+
+impl Callbacks for Slice {
+    fn on_solution(&mut self, s: &Solution) -> ControlFlow<()> {
+        self.found.push(RpcSolution {
+            code: s.code.clone(),
+            min_len: s.min_len,
+            steps: s.steps,
+            score: s.score,
+        });
+        ControlFlow::Continue(())
+    }
+
+    fn cancelled(&mut self) -> bool {
+        if self.left == 0 {
+            return true;
+        }
+        self.left -= 1;
+        false
+    }
+}
+
+/// An error for the response's `error` member.
+#[derive(Debug, PartialEq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// The open searches, by id.
+#[derive(Default)]
+pub struct Server {
+    searches: BTreeMap<u64, (Searcher, bool)>, // with whether it is done
+    next_id: u64,
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server::default()
+    }
+
+    /// The response to one line of input, if it calls for one.
+    pub fn handle_line(&mut self, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(response(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string())))),
+        };
+        let Some(request) = request.as_object() else {
+            return Some(response(Value::Null, Err(RpcError::new(INVALID_REQUEST, "a request is an object"))));
+        };
+        match request.get("id") {
+            None => {
+                let _ = self.call(request);
+                None
+            }
+            Some(id @ (Value::Null | Value::Number(_) | Value::String(_))) => {
+                Some(response(id.clone(), self.call(request)))
+            }
+            Some(_) => {
+                let e = RpcError::new(INVALID_REQUEST, "id must be a number, a string or null");
+                Some(response(Value::Null, Err(e)))
+            }
+        }
+    }
+
+    fn call(&mut self, request: &Map<String, Value>) -> Result<Value, RpcError> {
+        if request.get("jsonrpc") != Some(&json!("2.0")) {
+            return Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""));
+        }
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return Err(RpcError::new(INVALID_REQUEST, "method must be a string"));
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        match method {
+            "start_search" => self.start_search(parse_params(params)?),
+            "step" => self.step(parse_params(params)?),
+            "run_program" => run_program(parse_params(params)?),
+            "cancel" => self.cancel(parse_params(params)?),
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("no method '{}'", method))),
+        }
+    }
+
+    fn start_search(&mut self, params: StartParams) -> Result<Value, RpcError> {
+        let invalid = |e: String| RpcError::new(INVALID_PARAMS, e);
+        let c = params.config;
+        let mut builder = SearchConfig::new(params.target)
+            .max_steps_per_byte(c.max_steps_per_byte)
+            .golf(c.golf)
+            .exact_halt(c.exact_halt)
+            .pareto_only(c.pareto_only)
+            .max_memory(c.max_memory);
+        if let Some(beta) = c.beta {
+            builder = builder.beta(beta);
+        }
+        if let Some(gamma) = c.gamma {
+            builder = builder.gamma(gamma);
+        }
+        if let Some(max_steps) = c.max_steps {
+            builder = builder.max_steps(max_steps);
+        }
+        if let Some(set) = c.instr_set {
+            builder = builder.instr_set(InstrSet::parse(&set).map_err(invalid)?);
+        }
+        if let Some(tape) = c.tape_init {
+            builder = builder.tape_init(tape.parse::<Tape>().map_err(invalid)?);
+        }
+        let config = builder.build().map_err(|e| invalid(e.to_string()))?;
+        self.next_id += 1;
+        self.searches.insert(self.next_id, (Searcher::new(config), false));
+        Ok(json!({ "search": self.next_id }))
+    }
+
+    fn step(&mut self, params: StepParams) -> Result<Value, RpcError> {
+        let (searcher, done) = self.search(params.search)?;
+        let mut slice = Slice {
+            left: params.expansions,
+            found: Vec::new(),
+        };
+        if !*done {
+            let reason = searcher.run(&mut slice);
+            *done = matches!(reason, StopReason::Exhausted | StopReason::Aborted(_));
+        }
+        let stats = searcher.stats();
+        let progress = Progress {
+            solutions: slice.found,
+            expansions: stats.expansions,
+            pushed: stats.pushed,
+            frontier: searcher.frontier_len(),
+            memory: stats.memory,
+            solutions_total: stats.solutions,
+            pruned: stats
+                .pruned
+                .iter()
+                .map(|(reason, count)| RpcPruned {
+                    reason: reason.describe(),
+                    count,
+                })
+                .collect(),
+            done: *done,
+        };
+        Ok(serde_json::to_value(progress).expect("progress serializes"))
+    }
+
+    fn cancel(&mut self, params: SearchParams) -> Result<Value, RpcError> {
+        self.search(params.search)?;
+        self.searches.remove(&params.search);
+        Ok(Value::Bool(true))
+    }
+
+    fn search(&mut self, id: u64) -> Result<&mut (Searcher, bool), RpcError> {
+        self.searches
+            .get_mut(&id)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("no search {}", id)))
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn run_program(params: RunParams) -> Result<Value, RpcError> {
+    let mut interp = Interpreter::from_source(&params.code)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, e))?
+        .with_step_cap(params.max_steps);
+    let end = interp
+        .run_to_limit(params.limit)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+    let end = match end {
+        RunEnd::Halted => "halted",
+        RunEnd::InputUnsupported => "input",
+        RunEnd::OutputLimit => "limit",
+        RunEnd::StepCap => "step_cap",
+    };
+    Ok(json!({ "output": interp.output(), "steps": interp.steps(), "end": end }))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } }),
+    }
+}
+
+/// Answer the requests in `input`, one per line, on `output` until the
+/// input ends. Blank lines are skipped.
+pub fn serve(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::new();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(reply) = server.handle_line(&line) {
+            writeln!(output, "{}", reply)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
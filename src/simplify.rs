@@ -0,0 +1,99 @@
+//! Peephole simplification of concrete programs, applied until fixpoint:
+//!
+//! - `+-`, `-+`, `><`, `<>` cancel;
+//! - a loop is dropped when the current cell is known to be zero there, i.e.
+//!   before any `+`/`-`/`,` has run (the whole tape is still zero), or right
+//!   after another loop (which only exits on a zero cell).
+//!
+//! Every rule removes instructions, so the fixpoint is reached quickly.
+
+use crate::{Instr, PKind, ProgramNode, Shared};
+
+fn cancels(a: Instr, b: Instr) -> bool {
+    matches!(
+        (a, b),
+        (Instr::Inc, Instr::Dec)
+            | (Instr::Dec, Instr::Inc)
+            | (Instr::IncPtr, Instr::DecPtr)
+            | (Instr::DecPtr, Instr::IncPtr)
+    )
+}
+
+/// One rewriting pass over `node` and its continuation. `tape_zero`: every
+/// cell is still zero; `cell_zero`: the current cell is known to be zero.
+/// Walks the `next` chain in a loop, so only loop nesting recurses.
+fn pass(node: &Shared<ProgramNode>, tape_zero: bool, cell_zero: bool) -> Shared<ProgramNode> {
+    let (mut tape_zero, mut cell_zero) = (tape_zero, cell_zero);
+    let mut kept = Vec::new(); // (node, rewritten loop body)
+    let mut cur = node;
+    let tail = loop {
+        match &cur.kind {
+            PKind::Hole | PKind::Empty => break cur.clone(),
+            PKind::Instr(i, next) => {
+                if let PKind::Instr(j, rest) = &next.kind {
+                    if cancels(*i, *j) {
+                        cur = rest;
+                        continue;
+                    }
+                }
+                (tape_zero, cell_zero) = match i {
+                    Instr::Inc | Instr::Dec | Instr::Input => (false, false),
+                    Instr::IncPtr | Instr::DecPtr => (tape_zero, tape_zero),
+                    Instr::Output => (tape_zero, cell_zero),
+                };
+                kept.push((cur, None));
+                cur = next;
+            }
+            PKind::Loop { body, next } => {
+                // A dead loop (`[` always skips it) is dropped.
+                if !cell_zero {
+                    kept.push((cur, Some(pass(body, false, false))));
+                    (tape_zero, cell_zero) = (false, true);
+                }
+                cur = next;
+            }
+        }
+    };
+    kept.into_iter().rev().fold(tail, |next, (node, body)| match (&node.kind, body) {
+        (PKind::Instr(i, _), _) => ProgramNode::instr(*i, next),
+        (_, Some(body)) => ProgramNode::looped(body, next),
+        _ => unreachable!("kept loops carry their body"),
+    })
+}
+
+/// Apply the rewrites until nothing changes.
+///
+/// ```
+/// use bf_search::simplify::simplify;
+/// use bf_search::Program;
+///
+/// let simple = |src: &str| simplify(&src.parse::<Program>().unwrap().root).to_string();
+///
+/// // cancelling pairs, and the pairs that meet once those are gone
+/// assert_eq!(simple("++-."), "+.");
+/// assert_eq!(simple("+<>.-+"), "+.");
+/// assert_eq!(simple("+>+-<."), "+.");
+/// assert_eq!(simple("+.+>-"), "+.+>-");
+///
+/// // a loop where the whole tape is still zero
+/// assert_eq!(simple("[->+<]+."), "+.");
+/// assert_eq!(simple(">>[-]+."), ">>+.");
+/// assert_eq!(simple("+[-]."), "+[-].");
+/// assert_eq!(simple(".,[-]."), ".,[-].");
+///
+/// // a loop right after another
+/// assert_eq!(simple("+[-][+]."), "+[-].");
+/// assert_eq!(simple("+[-]++--[>]."), "+[-].");
+/// assert_eq!(simple("+[-]>[+]."), "+[-]>[+].");
+/// assert_eq!(simple("+[[-][-]]."), "+[[-]].");
+/// ```
+pub fn simplify(root: &Shared<ProgramNode>) -> Shared<ProgramNode> {
+    let mut cur = root.clone();
+    loop {
+        let next = pass(&cur, true, true);
+        if next.min_len == cur.min_len {
+            return next;
+        }
+        cur = next;
+    }
+}
@@ -0,0 +1,155 @@
+//! Serde support for programs and search states, and JSON helpers.
+//!
+//! A `ProgramNode` serializes structurally as its kind (`min_len`, `holes`
+//! and `size` are recomputed). A `SearchNode` stores its pc, and each loop
+//! frame its loop, as a position in `root` (see `node_at`), so the restored
+//! pointers are shared with the restored tree.
+//! The tape is a map from cell index to its (nonzero) value.
+//!
+//! Restoring a snapshot and continuing gives the same run as never having
+//! taken it:
+//!
+//! ```
+//! use bf_search::parse::parse_program;
+//! use bf_search::snapshot::{from_json, to_json};
+//! use bf_search::{exec_known_step, SearchNode};
+//!
+//! fn run(mut node: SearchNode, steps: usize) -> SearchNode {
+//!     for _ in 0..steps {
+//!         match exec_known_step(node.clone(), &[]).unwrap().advanced() {
+//!             Some(next) => node = next,
+//!             None => break,
+//!         }
+//!     }
+//!     node
+//! }
+//!
+//! let program = parse_program("++++[>+++[>++<-]<-]>>.").unwrap();
+//! let whole = run(SearchNode::concrete(program.clone()), 1000);
+//!
+//! let half = run(SearchNode::concrete(program), 40);
+//! let restored: SearchNode = from_json(&to_json(&half)).unwrap();
+//! let resumed = run(restored, 1000 - 40);
+//! assert_eq!(to_json(&resumed), to_json(&whole));
+//! assert_eq!(resumed.output(&[]), vec![24]);
+//! ```
+
+use crate::{node_at, Instr, LoopFrame, PKind, ProgramNode, SearchNode, Shared};
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+impl Serialize for Instr {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_char(self.to_char())
+    }
+}
+
+impl<'de> Deserialize<'de> for Instr {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Instr, D::Error> {
+        let c = char::deserialize(d)?;
+        Instr::from_char(c)
+            .ok_or_else(|| D::Error::custom(format!("'{}' is not a Brainfuck instruction", c)))
+    }
+}
+
+impl Serialize for ProgramNode {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.kind.serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProgramNode {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<ProgramNode, D::Error> {
+        PKind::deserialize(d).map(ProgramNode::with_kind)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SearchNodeRepr {
+    root: Shared<ProgramNode>,
+    pc: u32,              // position of the node in `root` to execute next
+    loop_stack: Vec<u32>, // positions of the entered loops, outermost first
+    dp: i64,
+    tape: BTreeMap<i64, u8>,
+    steps: u64,
+    last_output: u64,
+    correct: usize,
+    overflow: Vec<u8>,
+    #[serde(default)] // absent from snapshots older than `live_len`
+    dead_len: u32,
+    #[serde(default)] // ... and older than `Case`
+    case_correct: usize,
+}
+
+impl Serialize for SearchNode {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut loop_stack: Vec<u32> = self.loop_stack.iter().map(|f| f.at).collect();
+        loop_stack.reverse(); // outermost first
+        SearchNodeRepr {
+            root: self.root.clone(),
+            pc: self.pc_at,
+            loop_stack,
+            dp: self.dp,
+            tape: self.tape.nonzero().collect(),
+            steps: self.steps,
+            last_output: self.last_output,
+            correct: self.correct,
+            overflow: self.overflow.clone(),
+            dead_len: self.dead_len,
+            case_correct: self.case_correct,
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for SearchNode {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<SearchNode, D::Error> {
+        let r = SearchNodeRepr::deserialize(d)?;
+        let node = |at: u32, what: &str| {
+            node_at(&r.root, at)
+                .ok_or_else(|| D::Error::custom(format!("{} {} is not a node of root", what, at)))
+        };
+        let pc = node(r.pc, "pc")?;
+        let loop_stack = r
+            .loop_stack
+            .iter()
+            .map(|&at| match &node(at, "loop")?.kind {
+                PKind::Loop { body, next } => Ok(LoopFrame {
+                    at,
+                    body: body.clone(),
+                    next: next.clone(),
+                }),
+                _ => Err(D::Error::custom(format!("loop {} is not a loop of root", at))),
+            })
+            .collect::<Result<_, D::Error>>()?;
+        Ok(SearchNode {
+            root: r.root,
+            pc,
+            pc_at: r.pc,
+            loop_stack,
+            dp: r.dp,
+            tape: r.tape.into_iter().collect(),
+            steps: r.steps,
+            last_output: r.last_output,
+            correct: r.correct,
+            overflow: r.overflow,
+            dead_len: r.dead_len,
+            case_correct: r.case_correct,
+        })
+    }
+}
+
+pub fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("snapshot types always serialize")
+}
+
+/// Parse a value written by `to_json`. Programs nest as deeply as they are
+/// long, so serde_json's recursion limit is lifted.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    let mut de = serde_json::Deserializer::from_str(json);
+    de.disable_recursion_limit();
+    let value = T::deserialize(&mut de).map_err(|e| e.to_string())?;
+    de.end().map_err(|e| e.to_string())?;
+    Ok(value)
+}
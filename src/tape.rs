@@ -0,0 +1,201 @@
+//! The interpreter's tape: unbounded in both directions, all cells zero at
+//! the start. Programs found by the search rarely leave a few cells around
+//! the origin, so cells `DENSE_START..DENSE_START + DENSE_LEN` live in a
+//! plain array shared copy-on-write between search nodes, and only cells
+//! outside it go to a persistent hash map. Cloning a tape is two pointer
+//! copies; the first write after a clone copies the 64-byte block.
+
+use crate::Shared;
+use im::HashMap as ImHashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// First cell of the dense block. The pointer starts at 0 and mostly moves
+/// right, so the block leans that way.
+pub const DENSE_START: i64 = -16;
+pub const DENSE_LEN: usize = 64;
+
+/// Cell values by index. Zero cells are never stored in the sparse map, so
+/// equal tapes have equal representations.
+///
+/// ```
+/// use bf_search::Tape;
+///
+/// let mut tape = Tape::new();
+/// tape.set(3, 7);
+/// tape.set(1_000, 9); // far from the origin
+/// let copy = tape.clone();
+/// tape.set(1_000, 0);
+/// assert_eq!((tape.get(3), tape.get(1_000)), (7, 0));
+/// assert_eq!(copy.get(1_000), 9);
+/// assert_eq!(copy.nonzero().count(), 2);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Tape {
+    dense: Shared<[u8; DENSE_LEN]>,
+    sparse: ImHashMap<i64, u8>, // nonzero cells outside the dense block
+}
+
+impl Default for Tape {
+    fn default() -> Tape {
+        Tape::new()
+    }
+}
+
+impl Tape {
+    pub fn new() -> Tape {
+        Tape {
+            dense: Shared::new([0; DENSE_LEN]),
+            sparse: ImHashMap::new(),
+        }
+    }
+
+    fn slot(idx: i64) -> Option<usize> {
+        usize::try_from(idx.wrapping_sub(DENSE_START))
+            .ok()
+            .filter(|&i| i < DENSE_LEN)
+    }
+
+    pub fn get(&self, idx: i64) -> u8 {
+        match Tape::slot(idx) {
+            Some(i) => self.dense[i],
+            None => *self.sparse.get(&idx).unwrap_or(&0),
+        }
+    }
+
+    pub fn set(&mut self, idx: i64, val: u8) {
+        match Tape::slot(idx) {
+            Some(i) => {
+                if self.dense[i] != val {
+                    Shared::make_mut(&mut self.dense)[i] = val;
+                }
+            }
+            None if val == 0 => {
+                self.sparse.remove(&idx);
+            }
+            None => {
+                self.sparse.insert(idx, val);
+            }
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.nonzero().next().is_none()
+    }
+
+    /// Approximate bytes held: the dense block (even while shared with
+    /// other tapes) and the sparse cells, with the hash map's overhead.
+    pub fn footprint(&self) -> usize {
+        DENSE_LEN + self.sparse.len() * 2 * std::mem::size_of::<(i64, u8)>()
+    }
+
+    /// The nonzero cells, in no particular order.
+    pub fn nonzero(&self) -> impl Iterator<Item = (i64, u8)> + '_ {
+        let dense = self
+            .dense
+            .iter()
+            .enumerate()
+            .filter(|&(_, &v)| v != 0)
+            .map(|(i, &v)| (DENSE_START + i as i64, v));
+        dense.chain(self.sparse.iter().map(|(&i, &v)| (i, v)))
+    }
+}
+
+/// Builds a tape from `(index, value)` pairs; later pairs win.
+impl FromIterator<(i64, u8)> for Tape {
+    fn from_iter<T: IntoIterator<Item = (i64, u8)>>(iter: T) -> Tape {
+        let mut tape = Tape::new();
+        for (idx, val) in iter {
+            tape.set(idx, val);
+        }
+        tape
+    }
+}
+
+/// Consistent with `Eq`: the sparse cells are hashed in index order, since
+/// the map's own iteration order depends on its hasher.
+impl Hash for Tape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dense.hash(state);
+        let mut far: Vec<(i64, u8)> = self.sparse.iter().map(|(&i, &v)| (i, v)).collect();
+        far.sort_unstable();
+        far.hash(state);
+    }
+}
+
+/// How many cells from 0 on `Display` writes as a plain list of values.
+const LISTED: i64 = 16;
+
+/// A tape given as a list of cell values from cell 0 on, `cell[i]=v` for
+/// single cells anywhere (the index may be negative), or both, separated
+/// by spaces or commas. Later values win; zeros store nothing.
+///
+/// ```
+/// use bf_search::Tape;
+///
+/// let tape: Tape = "10 0 3".parse().unwrap();
+/// assert_eq!((tape.get(0), tape.get(1), tape.get(2), tape.get(3)), (10, 0, 3, 0));
+/// let tape: Tape = "cell[5]=7, 1 cell[-300]=2 cell[9]=0".parse().unwrap();
+/// assert_eq!((tape.get(5), tape.get(0), tape.get(-300)), (7, 1, 2));
+/// assert_eq!(tape.nonzero().count(), 3);
+/// assert_eq!(tape.to_string(), "cell[-300]=2 1 0 0 0 0 7");
+/// assert_eq!(tape.to_string().parse::<Tape>().unwrap(), tape);
+/// assert_eq!("cell[100]=1".parse::<Tape>().unwrap().to_string(), "cell[100]=1");
+/// assert!("".parse::<Tape>().unwrap().is_blank());
+/// assert!("256".parse::<Tape>().is_err());
+/// assert!("cell[x]=1".parse::<Tape>().is_err());
+/// ```
+impl FromStr for Tape {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Tape, String> {
+        let mut tape = Tape::new();
+        let mut next = 0; // the cell the next plain value goes to
+        for token in spec.split([' ', '\t', ',']).filter(|t| !t.is_empty()) {
+            let value = |v: &str| {
+                v.parse::<u8>()
+                    .map_err(|_| format!("cell values are 0..=255, got '{}'", v))
+            };
+            match token.strip_prefix("cell[").and_then(|t| t.split_once("]=")) {
+                Some((idx, v)) => {
+                    let idx = idx
+                        .parse::<i64>()
+                        .map_err(|_| format!("'{}' is not a cell index", idx))?;
+                    tape.set(idx, value(v)?);
+                }
+                None if token.starts_with("cell") => {
+                    return Err(format!("expected cell[INDEX]=VALUE, got '{}'", token))
+                }
+                None => {
+                    tape.set(next, value(token)?);
+                    next += 1;
+                }
+            }
+        }
+        Ok(tape)
+    }
+}
+
+/// The nonzero cells in index order: those in `0..16` as a list of values
+/// from cell 0, the others as `cell[i]=v`. Parses back to the same tape.
+impl fmt::Display for Tape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut cells: Vec<(i64, u8)> = self.nonzero().collect();
+        cells.sort_unstable();
+        let cell = |&(i, v): &(i64, u8)| format!("cell[{}]={}", i, v);
+        let listed = cells.iter().filter(|(i, _)| (0..LISTED).contains(i)).map(|(i, _)| i + 1).max();
+        let mut words: Vec<String> = cells.iter().filter(|(i, _)| *i < 0).map(cell).collect();
+        words.extend((0..listed.unwrap_or(0)).map(|i| self.get(i).to_string()));
+        words.extend(cells.iter().filter(|(i, _)| *i >= LISTED).map(cell));
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+impl fmt::Debug for Tape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut cells: Vec<(i64, u8)> = self.nonzero().collect();
+        cells.sort_unstable();
+        f.debug_map().entries(cells).finish()
+    }
+}
@@ -0,0 +1,143 @@
+//! WebAssembly entry point (feature `wasm`). The page starts a search and
+//! then calls `SearchHandle::step` from its event loop, a slice of
+//! expansions at a time, so the UI stays responsive:
+//!
+//! ```js
+//! const handle = start_search(new Uint8Array([1, 2]), { beta: 0.5 });
+//! const { solutions, expansions, done } = handle.step(10000);
+//! ```
+
+use crate::{Callbacks, InstrSet, SearchConfig, Searcher, Solution, StopReason};
+use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
+use wasm_bindgen::prelude::*;
+
+/// Options accepted by `start_search`; omitted fields keep the defaults.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+struct JsConfig {
+    beta: Option<f64>,
+    gamma: Option<f64>,
+    max_steps: Option<u64>,
+    instr_set: Option<String>,
+    golf: bool,
+}
+
+#[derive(Serialize)]
+struct JsPruned {
+    reason: &'static str, // `PruneReason::describe`
+    count: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsSolution {
+    code: String,
+    min_len: u32,
+    steps: u64,
+    score: f64,
+}
+
+/// What one `step` call returns.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsProgress {
+    solutions: Vec<JsSolution>, // found during this call
+    expansions: u64,            // totals since `start_search`
+    pushed: u64,
+    solutions_total: usize,
+    unverified: u64, // programs that failed the re-run against the target (a bug)
+    pruned: Vec<JsPruned>, // totals by reason, for the reasons that pruned anything
+    done: bool, // the search space is exhausted (or the search aborted)
+}
+
+#[wasm_bindgen]
+pub struct SearchHandle {
+    searcher: Searcher,
+    done: bool,
+}
+
+/// Collects solutions until `left` expansions have been spent.
+struct Slice {
+    left: u32,
+    found: Vec<JsSolution>,
+}
+
+impl Callbacks for Slice {
+    fn on_solution(&mut self, s: &Solution) -> ControlFlow<()> {
+        self.found.push(JsSolution {
+            code: s.code.clone(),
+            min_len: s.min_len,
+            steps: s.steps,
+            score: s.score,
+        });
+        ControlFlow::Continue(())
+    }
+
+    fn cancelled(&mut self) -> bool {
+        if self.left == 0 {
+            return true;
+        }
+        self.left -= 1;
+        false
+    }
+}
+
+#[wasm_bindgen]
+pub fn start_search(target: &[u8], config: JsValue) -> Result<SearchHandle, JsError> {
+    let js: JsConfig = if config.is_undefined() || config.is_null() {
+        JsConfig::default()
+    } else {
+        serde_wasm_bindgen::from_value(config)?
+    };
+    let mut builder = SearchConfig::new(target.to_vec()).golf(js.golf);
+    if let Some(beta) = js.beta {
+        builder = builder.beta(beta);
+    }
+    if let Some(gamma) = js.gamma {
+        builder = builder.gamma(gamma);
+    }
+    if let Some(max_steps) = js.max_steps {
+        builder = builder.max_steps(max_steps);
+    }
+    if let Some(set) = js.instr_set {
+        builder = builder.instr_set(InstrSet::parse(&set).map_err(|e| JsError::new(&e))?);
+    }
+    Ok(SearchHandle {
+        searcher: Searcher::new(builder.build()?),
+        done: false,
+    })
+}
+
+#[wasm_bindgen]
+impl SearchHandle {
+    /// Run up to `n` expansions; returns the solutions found and progress.
+    pub fn step(&mut self, n: u32) -> Result<JsValue, JsError> {
+        let mut slice = Slice {
+            left: n,
+            found: Vec::new(),
+        };
+        if !self.done {
+            let reason = self.searcher.run(&mut slice);
+            self.done = matches!(reason, StopReason::Exhausted | StopReason::Aborted(_));
+        }
+        let stats = self.searcher.stats();
+        let progress = JsProgress {
+            solutions: slice.found,
+            expansions: stats.expansions,
+            pushed: stats.pushed,
+            solutions_total: stats.solutions,
+            unverified: stats.unverified,
+            pruned: stats
+                .pruned
+                .iter()
+                .map(|(reason, count)| JsPruned {
+                    reason: reason.describe(),
+                    count,
+                })
+                .collect(),
+            done: self.done,
+        };
+        Ok(serde_wasm_bindgen::to_value(&progress)?)
+    }
+}
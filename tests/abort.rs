@@ -0,0 +1,44 @@
+// A search that skipped a node after an internal error cannot claim to have
+// exhausted the space: through the binary, it ends as aborted with the
+// error and exit code 4. The broken node goes in through the `testing`
+// feature's hidden --inject (`cargo test --features testing --test abort`).
+#![cfg(feature = "testing")]
+
+use bf_search::snapshot::to_json;
+use bf_search::{node_at, LoopFrame, Program, SearchNode};
+use std::process::{Command, Stdio};
+
+#[test]
+fn a_dangling_loop_frame_aborts_with_exit_code_4() {
+    // At the hole of `[+]?`, inside the loop before it: that loop is not
+    // around the hole, so expanding it cannot refresh the frame.
+    let p: Program = "[+]?".parse().unwrap();
+    let mut node = SearchNode::concrete(p.root.clone());
+    node.pc_at = 3;
+    node.pc = node_at(&p.root, 3).unwrap();
+    node.loop_stack.push(LoopFrame {
+        at: 0,
+        body: node_at(&p.root, 1).unwrap(),
+        next: node_at(&p.root, 3).unwrap(),
+    });
+    let dir = std::env::temp_dir().join(format!("bf_search-abort-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("node.json");
+    std::fs::write(&path, to_json(&node)).unwrap();
+
+    // `+` and `.` within 3 steps: a space small enough to run out
+    let out = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .args(["--instr-set", "+.", "--max-steps", "3", "--solutions", "10", "--inject"])
+        .arg(&path)
+        .arg("1")
+        .stdin(Stdio::null())
+        .output()
+        .expect("run bf_search");
+    std::fs::remove_dir_all(&dir).unwrap();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(out.status.code(), Some(4), "{}", stdout);
+    assert!(stdout.contains("Stopped       : aborted by an internal error"), "{}", stdout);
+    assert!(stdout.contains("Error         : loop #0 on the loop stack is not around the pc"), "{}", stdout);
+    assert!(stdout.contains("Skipped       : 1 node(s) after internal errors"), "{}", stdout);
+    assert!(stdout.contains("Program (Brainfuck):\n+.\n"), "{}", stdout);
+}
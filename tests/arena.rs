@@ -0,0 +1,108 @@
+// `ArenaSearch` against `Searcher`: on the corpus, both must expand the
+// same nodes in the same order (each expansion's seq and score, and its
+// children's), prune the same children and find the same solutions. The
+// tree operations must agree with the shared tree's on every hole and
+// position of a few partial programs.
+
+mod common;
+
+use bf_search::arena::{Arena, ArenaSearch};
+use bf_search::{node_at, replace_hole, Callbacks, Expansion, Instr, Program, ProgramNode, PruneReason, SearchConfig};
+use bf_search::{Searcher, Solution};
+use std::ops::ControlFlow;
+
+/// An expansion's seq and score, and each child's.
+type Step = (u64, f64, Vec<(u64, f64)>);
+
+#[derive(Debug, Default, PartialEq)]
+struct Trace {
+    expansions: Vec<Step>,
+    pruned: Vec<PruneReason>,
+    solutions: Vec<(String, u32, f64)>, // code, length, score
+}
+
+impl Callbacks for Trace {
+    fn on_solution(&mut self, s: &Solution) -> ControlFlow<()> {
+        self.solutions.push((s.code.clone(), s.min_len, s.score));
+        ControlFlow::Continue(())
+    }
+
+    fn on_prune(&mut self, reason: PruneReason) {
+        self.pruned.push(reason);
+    }
+
+    fn on_expansion(&mut self, e: &Expansion) {
+        self.expansions.push((e.seq, e.score, e.children.to_vec()));
+    }
+}
+
+/// The trace both searches left, which must be the same.
+fn compare(config: SearchConfig) -> Trace {
+    let (mut tree, mut arena) = (Trace::default(), Trace::default());
+    let stopped = Searcher::new(config.clone()).run(&mut tree);
+    assert_eq!(ArenaSearch::new(config).run(&mut arena), stopped);
+    assert_eq!(tree.expansions.len(), arena.expansions.len());
+    for (i, (a, b)) in tree.expansions.iter().zip(&arena.expansions).enumerate() {
+        assert_eq!(a, b, "expansion {}", i);
+    }
+    assert!(!tree.solutions.is_empty());
+    assert_eq!(tree, arena);
+    tree
+}
+
+#[test]
+fn same_expansions_on_the_corpus() {
+    for &(target, golf) in common::CORPUS.iter().filter(|(_, golf)| !golf) {
+        let config = SearchConfig::new(target.to_vec()).golf(golf).max_expansions(Some(20_000));
+        compare(config.build().unwrap());
+    }
+}
+
+#[test]
+fn same_expansions_under_limits() {
+    // the caps cut branches off, and dead loops count apart in the score
+    let config = || SearchConfig::new(vec![1, 2, 3]).live_length(true).max_expansions(Some(20_000));
+    let runs = [
+        (config().max_steps(30).max_len(Some(14)), [PruneReason::StepLimit, PruneReason::LengthCap]),
+        (config().max_steps_per_byte(Some(20)), [PruneReason::StepsPerByte, PruneReason::PrematureHalt]),
+    ];
+    for (config, reasons) in runs {
+        let trace = compare(config.build().unwrap());
+        for reason in reasons {
+            assert!(trace.pruned.contains(&reason), "{:?}", reason);
+        }
+    }
+}
+
+#[test]
+fn tree_operations_match_the_shared_tree() {
+    for src in ["?", "+[?]?", "[?]+[-?]?", "+[>[?]<-]?", ".[?]>[+[?]?]?", "[[?]?][?]?"] {
+        let p: Program = src.parse().unwrap();
+        let mut arena = Arena::new();
+        let root = arena.import(&p.root);
+        assert_eq!(arena.to_bf_string(root), src);
+        assert_eq!(arena[root].size, p.root.size);
+        for (concrete, want) in [
+            (arena.concretize_min(root), p.root.concretize_min()),
+            (arena.concretize_solution(root), p.root.concretize_solution()),
+        ] {
+            assert_eq!(arena.to_bf_string(concrete), want.to_string());
+            assert_eq!(arena[concrete].min_len, want.min_len);
+        }
+        for at in 0..=p.root.size {
+            let (got, want) = (arena.node_at(root, at), node_at(&p.root, at));
+            assert_eq!(got.map(|id| arena.export(id).to_string()), want.map(|n| n.to_string()), "{} at {}", src, at);
+            let (empty, hole) = (arena.empty(), arena.hole());
+            let fills = [
+                (empty, ProgramNode::empty()),
+                (arena.instr(Instr::Inc, hole), ProgramNode::instr(Instr::Inc, ProgramNode::hole())),
+                (arena.looped(hole, hole), ProgramNode::looped(ProgramNode::hole(), ProgramNode::hole())),
+            ];
+            for (id, node) in fills {
+                let got = arena.replace_hole(root, at, id).map(|r| arena.export(r).to_string());
+                assert_eq!(got, replace_hole(&p.root, at, node).map(|r| r.to_string()), "{} at {}", src, at);
+            }
+        }
+        assert_eq!(arena.to_bf_string(root), src);
+    }
+}
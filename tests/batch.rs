@@ -0,0 +1,47 @@
+// `--targets` through the binary: a file of two easy targets, one the
+// per-target budget cuts short, and a line that does not parse.
+
+use std::process::{Command, Stdio};
+
+#[test]
+fn three_targets_and_a_bad_line() {
+    let path = std::env::temp_dir().join(format!("bf_search-batch-{}.txt", std::process::id()));
+    std::fs::write(&path, "# easy ones first\n1 2\nhex:0203\n300 1\n7 200 3 100 9 1\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .arg("--targets")
+        .arg(&path)
+        .args(["--per-target-expansions", "2000"])
+        .stdin(Stdio::null())
+        .output()
+        .expect("run bf_search");
+    std::fs::remove_file(&path).unwrap();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+
+    // the bad line is skipped with a warning, not the batch
+    assert!(stderr.contains(":4: skipping target: '300' is not a decimal byte"), "{}", stderr);
+    assert!(stdout.contains("Targets: 3 from"), "{}", stdout);
+
+    // a section per target
+    let sections: Vec<&str> = stdout.split("=== Target ").skip(1).collect();
+    assert_eq!(sections.len(), 3);
+    assert!(sections[0].starts_with("1 (line 2): 1 2 ===") && sections[0].contains("+.+."));
+    assert!(sections[1].starts_with("2 (line 3): 2 3 ===") && sections[1].contains("Solution #1"));
+    assert!(sections[2].starts_with("3 (line 5): 7 200 3 100 9 1 ==="));
+    assert!(sections[2].contains("Stopped       : search budget used up"), "{}", sections[2]);
+
+    // and a summary row each
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("Batch summary:"))
+        .skip(2)
+        .take(3)
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(rows[0][..3], ["2", "solved", "4"]);
+    assert_eq!(rows[0].last(), Some(&"+.+."));
+    assert_eq!(rows[1][..2], ["3", "solved"]);
+    assert_eq!(rows[2][..4], ["5", "budget", "-", "2000"]);
+    assert!(stdout.contains("Solved 2 of 3 targets."));
+}
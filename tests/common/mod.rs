@@ -0,0 +1,50 @@
+// The fixed corpus of tests/golden.rs and tests/frontier.rs, and the search
+// they both run on it.
+#![allow(dead_code)] // each test reads its own part of `Found`
+
+use bf_search::{Frontier, SearchConfig, Searcher};
+
+/// Targets, each with whether to golf.
+pub const CORPUS: &[(&[u8], bool)] = &[
+    (&[1, 2, 3], false),
+    (&[5, 5, 5, 5], false),
+    (&[3, 6, 9], false),
+    (&[0, 1, 0, 1], false),
+    (&[2, 4], false),
+    (&[5, 5, 5, 5], true),
+    (&[3, 6, 9], true),
+];
+
+/// A solution, with the search's counts when it was found.
+#[derive(Debug, PartialEq)]
+pub struct Found {
+    pub code: String,
+    pub min_len: u32,
+    pub expansions: u64,
+    pub pushed: u64,
+}
+
+/// The first `n` solutions within 20,000 expansions.
+pub fn solutions(target: &[u8], golf: bool, frontier: Frontier, n: usize) -> Vec<Found> {
+    let config = SearchConfig::new(target.to_vec())
+        .golf(golf)
+        .frontier(frontier)
+        .max_expansions(Some(20_000))
+        .build()
+        .unwrap();
+    let mut searcher = Searcher::new(config);
+    let mut found = Vec::new();
+    while found.len() < n {
+        let Some(s) = searcher.next_solution() else {
+            break;
+        };
+        let stats = searcher.stats();
+        found.push(Found {
+            code: s.code,
+            min_len: s.min_len,
+            expansions: stats.expansions,
+            pushed: stats.pushed,
+        });
+    }
+    found
+}
@@ -0,0 +1,60 @@
+// `bf_search compare` through the binary: identical outputs, an early
+// divergence, and one program halting before the bytes asked for.
+
+use std::process::Command;
+
+/// The exit code and output of `compare` on `a` and `b` for `bytes` bytes.
+fn compare(a: &str, b: &str, bytes: usize) -> (Option<i32>, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .args(["compare", a, b, "--bytes", &bytes.to_string()])
+        .output()
+        .expect("run bf_search");
+    (out.status.code(), String::from_utf8(out.stdout).unwrap())
+}
+
+#[test]
+fn identical_outputs() {
+    let (code, stdout) = compare("+[.+]", "+.+.+.+.+[.+]", 8);
+    assert_eq!(code, Some(0));
+    assert!(stdout.contains("Program A: +[.+]\n  length 5, 24 steps, still running\n"), "{}", stdout);
+    assert!(stdout.contains("Program B: +.+.+.+.+[.+]\n  length 13, 20 steps, still running\n"));
+    assert!(stdout.contains("     0  A:   1   2   3   4   5   6   7   8\n        B:   1   2   3   4   5   6   7   8\n\n"));
+    assert!(!stdout.contains('^'));
+    assert!(stdout.ends_with("Identical for 8 bytes.\n"), "{}", stdout);
+}
+
+#[test]
+fn early_divergence() {
+    let (code, stdout) = compare("+.+.+.", "+.++.+.", 3);
+    assert_eq!(code, Some(1));
+    assert!(stdout.contains(concat!(
+        "     0  A:   1   2   3\n",
+        "        B:   1   3   4\n",
+        "               ^^^ ^^^\n",
+    )), "{}", stdout);
+    assert!(stdout.ends_with("First divergence at byte 1: A gives 2, B gives 3.\n"), "{}", stdout);
+}
+
+#[test]
+fn one_halts_early() {
+    // from a file, as well
+    let path = std::env::temp_dir().join(format!("bf_search-compare-{}.bf", std::process::id()));
+    std::fs::write(&path, "+.+.\n").unwrap();
+    let (code, stdout) = compare(path.to_str().unwrap(), "+[.+]", 4);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(code, Some(1));
+    assert!(stdout.contains("Program A: +.+.\n  length 4, 4 steps, halted after 2 bytes\n"), "{}", stdout);
+    assert!(stdout.contains("     0  A:   1   2   .   .\n        B:   1   2   3   4\n"));
+    assert!(stdout.ends_with("First divergence at byte 2: A gives nothing (stopped), B gives 3.\n"));
+
+    // both halting the same way counts as identical
+    let (code, stdout) = compare("+.+.", "+.+.", 4);
+    assert_eq!(code, Some(0));
+    assert!(stdout.ends_with("Identical for all 2 bytes produced (fewer than the 4 requested).\n"));
+}
+
+#[test]
+fn bad_program_is_an_error() {
+    let (code, _) = compare("+[.", "+.", 4);
+    assert_eq!(code, Some(2));
+}
@@ -0,0 +1,99 @@
+// `--config` through the binary: a value only the file gives, one the
+// command line overrides, files that do not parse, and file keys breaking
+// the constraints between options.
+
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// Write `contents` to a config file of its own for the test `name`.
+fn config_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bf_search-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn run(config: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .arg("--config")
+        .arg(config)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .expect("run bf_search")
+}
+
+/// The `--print-config` line for `key`, as (value, source).
+fn entry(stdout: &str, key: &str) -> (String, String) {
+    let line = stdout.lines().find(|l| l.starts_with(&format!("{} =", key))).expect("the key");
+    let (setting, source) = line.split_once('#').unwrap();
+    let value = setting.split_once('=').unwrap().1;
+    (value.trim().to_string(), source.trim().to_string())
+}
+
+#[test]
+fn file_only_value() {
+    let path = config_file("file-only", "beta = 0.4\nmax-steps = 5000\nportfolio-slice = 20\n");
+    let out = run(&path, &["--print-config", "1"]);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(entry(&stdout, "beta"), ("0.4".into(), "config file".into()));
+    assert_eq!(entry(&stdout, "max-steps"), ("5000".into(), "config file".into()));
+    assert_eq!(entry(&stdout, "portfolio-slice"), ("20".into(), "config file".into()));
+    assert_eq!(entry(&stdout, "gamma"), ("1.0".into(), "default".into()));
+
+    // and the search runs with it
+    let out = run(&path, &["1", "2"]);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("score = correct - 0.400 * min_len"), "{}", stdout);
+}
+
+#[test]
+fn command_line_overrides_the_file() {
+    let path = config_file("override", "beta = 0.4\ngamma = 0.2\nbytes = [7, 7]\n");
+    let out = run(&path, &["--print-config", "--beta", "2", "1"]);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(entry(&stdout, "beta"), ("2.0".into(), "command line".into()));
+    assert_eq!(entry(&stdout, "gamma"), ("0.2".into(), "config file".into()));
+    assert_eq!(entry(&stdout, "bytes"), ("[1]".into(), "command line".into()));
+}
+
+#[test]
+fn malformed_files_name_the_key() {
+    for (name, contents, expect) in [
+        ("type", "beta = \"high\"\n", "key `beta`"),
+        ("unknown", "gamma = 1.0\nbogus = 3\n", "bogus"),
+        ("syntax", "beta = = 1\n", ":1:"),
+        ("infinite", "gamma = inf\n", "key `gamma`: must be a finite number"),
+    ] {
+        let path = config_file(&format!("malformed-{}", name), contents);
+        let out = run(&path, &["1"]);
+        let stderr = String::from_utf8(out.stderr).unwrap();
+        assert_eq!(out.status.code(), Some(2), "{}", name);
+        assert!(stderr.contains("Invalid config") && stderr.contains(expect), "{}: {}", name, stderr);
+    }
+}
+
+#[test]
+fn file_keys_must_go_together() {
+    let conflict = config_file("conflict", "golf = true\ncount-only = true\nmax-len = 3\n");
+    let out = run(&conflict, &["1"]);
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr.contains("golf") && stderr.contains("cannot be used with"), "{}", stderr);
+
+    let missing = config_file("requires", "relax-after = 5\n");
+    let out = run(&missing, &["1"]);
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr.contains("relax-after") && stderr.contains("--retry-relaxed"), "{}", stderr);
+
+    // ... with the command line too
+    let count = config_file("with-cli", "count-only = true\n");
+    let out = run(&count, &["1"]);
+    assert_eq!(out.status.code(), Some(2));
+    let out = run(&count, &["--max-len", "2", "--max-steps", "100", "1"]);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(stdout.contains("every program up to --max-len searched"), "{}", stdout);
+}
@@ -0,0 +1,46 @@
+// `--count-only` through the binary: the counts for `1` up to four
+// instructions (see `bf_search::census` for where they come from), and
+// `--list` printing each program counted.
+
+use std::process::{Command, Stdio};
+
+fn count(extra: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .args(["--count-only", "--max-len", "4", "--max-steps", "1000"])
+        .args(extra)
+        .arg("1")
+        .stdin(Stdio::null())
+        .output()
+        .expect("run bf_search");
+    assert_eq!(out.status.code(), Some(0));
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn counts_for_a_tiny_target() {
+    let stdout = count(&[]);
+    assert!(stdout.contains("Stopped       : every program up to --max-len searched"), "{}", stdout);
+    assert!(stdout.contains("Programs      : 58 printing the target, 56 of them halting within --max-steps"));
+    assert!(stdout.contains("length cap"));
+    let rows: Vec<(u32, u64)> = stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("Programs by length:"))
+        .skip(2)
+        .map(|line| {
+            let mut cols = line.split_whitespace();
+            (cols.next().unwrap().parse().unwrap(), cols.next().unwrap().parse().unwrap())
+        })
+        .collect();
+    assert_eq!(rows, [(2, 1), (3, 7), (4, 50)]);
+}
+
+#[test]
+fn list_prints_each_program() {
+    let stdout = count(&["--list"]);
+    let listed: Vec<&str> = stdout.lines().filter(|line| line.starts_with("  ") && !line.contains(':')).collect();
+    let programs: Vec<&str> = listed.iter().take_while(|l| !l.contains("Length")).map(|l| l.trim()).collect();
+    assert_eq!(programs.len(), 58);
+    assert!(programs.contains(&"+."));
+    assert!(programs.contains(&"+-+."));
+    assert_eq!(programs.iter().filter(|p| p.contains("still running")).count(), 2);
+}
@@ -0,0 +1,246 @@
+// Differential testing of the interpreters in `bf_search::differential`:
+// random valid programs must run the same (output, steps and how the run
+// ended) under every runner, and as their `simplify`d selves, and search
+// stepping must see the same output against a target. Failures shrink to a
+// minimal program.
+#![cfg(not(target_arch = "wasm32"))]
+
+use bf_search::differential::{agree, Budget, Reference, Run, Runner};
+use bf_search::interp::RunEnd;
+use bf_search::simplify::simplify;
+use bf_search::{exec_known_step, Program, PruneReason, SearchNode, StepOutcome};
+use proptest::prelude::*;
+use proptest::strategy::ValueTree;
+use proptest::test_runner::{Config, TestCaseError, TestError, TestRng, TestRunner};
+
+const BUDGET: Budget = Budget {
+    steps: 2_000,
+    output: 32,
+};
+
+/// Valid programs of up to about 60 instructions, loops nested up to 4
+/// deep. `,` is rare, since it ends the run.
+fn programs() -> impl Strategy<Value = String> {
+    let instr = prop_oneof![
+        4 => Just("+"),
+        3 => Just("-"),
+        3 => Just(">"),
+        3 => Just("<"),
+        3 => Just("."),
+        1 => Just(","),
+    ];
+    let straight = prop::collection::vec(instr, 0..6).prop_map(|v| v.concat());
+    straight.prop_recursive(4, 60, 4, |inner| {
+        let looped = inner.clone().prop_map(|body| format!("[{}]", body));
+        prop::collection::vec(prop_oneof![inner, looped], 1..4).prop_map(|v| v.concat())
+    })
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(1000))]
+
+    #[test]
+    fn interpreters_agree(src in programs()) {
+        agree(&src, BUDGET).map_err(TestCaseError::fail)?;
+    }
+
+    // Simplifying only removes instructions, so the simplified program may
+    // get further before the step cap; up to there, it must run the same.
+    #[test]
+    fn simplify_preserves_output(src in programs()) {
+        let program: Program = src.parse().unwrap();
+        let simple = simplify(&program.root).to_string();
+        let before = Reference.run(&src, BUDGET).map_err(TestCaseError::fail)?;
+        let after = Reference.run(&simple, BUDGET).map_err(TestCaseError::fail)?;
+        prop_assert!(after.steps <= before.steps, "{} -> {}", src, simple);
+        if before.end == RunEnd::StepCap {
+            prop_assert!(after.output.starts_with(&before.output), "{} -> {}", src, simple);
+        } else {
+            prop_assert_eq!((&after.output, after.end), (&before.output, before.end), "{} -> {}", src, simple);
+        }
+    }
+}
+
+/// Long-running loops, nested deep and many times round, where every `]`
+/// jumps through the loop frames the search keeps.
+#[test]
+fn loop_heavy_programs_agree() {
+    let budget = Budget {
+        steps: 20_000,
+        output: 300,
+    };
+    let deep = format!("{}+{}.", "+[".repeat(8), "-]".repeat(8));
+    let after_long_prefix = format!("{}++[>+++[>++[-.]<-]<-]", "+-".repeat(500));
+    for src in [
+        "++++++++[>++++++++<-]>[.-]",
+        "++[>++[>++[>++[>++<-]<-]<-]<-]>>>>.",
+        "+[>+[-]<+]",
+        "+[[>+.<-]+]",
+        "++[>[-]+++[>+.[-]<-]<-]>>[<]",
+        deep.as_str(),
+        after_long_prefix.as_str(),
+    ] {
+        agree(src, budget).unwrap_or_else(|e| panic!("{}: {}", src, e));
+    }
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(500))]
+
+    // Search nodes keep only the output past the target. Against a prefix
+    // of the program's output as the target, stepping must end with the
+    // same output, split at the target's end; with that prefix's last byte
+    // changed, it must prune at exactly that byte.
+    #[test]
+    fn output_against_a_target(src in programs(), cut in any::<prop::sample::Index>(), wrong in any::<bool>()) {
+        let run = Reference.run(&src, BUDGET).map_err(TestCaseError::fail)?;
+        let k = cut.index(run.output.len() + 1);
+        let mut target = run.output[..k].to_vec();
+        let wrong = wrong && k > 0;
+        if wrong {
+            target[k - 1] ^= 1;
+        }
+        let program: Program = src.parse().unwrap();
+        let mut node = SearchNode::concrete(program.root);
+        let pruned = loop {
+            if node.correct + node.overflow.len() >= BUDGET.output || node.steps >= BUDGET.steps {
+                break None;
+            }
+            match exec_known_step(node.clone(), &target).unwrap() {
+                StepOutcome::Advanced(next) => node = next,
+                StepOutcome::Halted(_) | StepOutcome::Pruned(PruneReason::Input) => break None,
+                StepOutcome::Pruned(reason) => break Some(reason),
+                StepOutcome::Expanded(_) => unreachable!("no holes"),
+            }
+        };
+        if wrong {
+            prop_assert_eq!(pruned, Some(PruneReason::Mismatch), "{}", src);
+            prop_assert_eq!(node.correct, k - 1);
+        } else {
+            prop_assert_eq!(pruned, None, "{}", src);
+            prop_assert_eq!(node.correct, k);
+            prop_assert_eq!(&node.overflow[..], &run.output[k..]);
+            prop_assert_eq!(node.output(&target), run.output);
+        }
+    }
+}
+
+/// Loops entered and not yet left, after each of the first `steps` steps
+/// of `src` (which ends at a `,` or when it halts).
+fn loop_depths(src: &str, steps: u64) -> Vec<usize> {
+    let code = src.as_bytes();
+    let mut jump = vec![0; code.len()];
+    let mut open = Vec::new();
+    for (i, &c) in code.iter().enumerate() {
+        match c {
+            b'[' => open.push(i),
+            b']' => {
+                let j = open.pop().unwrap();
+                (jump[i], jump[j]) = (j, i);
+            }
+            _ => {}
+        }
+    }
+    let mut tape = std::collections::HashMap::new();
+    let (mut pc, mut dp, mut depth, mut depths) = (0, 0i64, 0, Vec::new());
+    while depths.len() < steps as usize && pc < code.len() && code[pc] != b',' {
+        let cell = tape.entry(dp).or_insert(0u8);
+        match code[pc] {
+            b'>' => dp += 1,
+            b'<' => dp -= 1,
+            b'+' => *cell = cell.wrapping_add(1),
+            b'-' => *cell = cell.wrapping_sub(1),
+            b'[' if *cell == 0 => pc = jump[pc],
+            b'[' => depth += 1,
+            b']' if *cell != 0 => pc = jump[pc],
+            b']' => depth -= 1,
+            _ => {}
+        }
+        pc += 1;
+        depths.push(depth);
+    }
+    depths
+}
+
+proptest! {
+    // The loop stack is a persistent list; entering and leaving loops must
+    // push and pop it exactly as the program nests, clone or no clone.
+    #[test]
+    fn loop_stack_follows_nesting(src in programs()) {
+        let depths = loop_depths(&src, BUDGET.steps);
+        let program: Program = src.parse().unwrap();
+        let mut node = SearchNode::concrete(program.root);
+        for (step, &depth) in depths.iter().enumerate() {
+            let parent = node.clone();
+            node = match exec_known_step(node, &[]).unwrap() {
+                StepOutcome::Advanced(next) => next,
+                _ => return Err(TestCaseError::fail(format!("{}: stopped at step {}", src, step))),
+            };
+            prop_assert_eq!(node.loop_stack.len(), depth, "{} after step {}", src, step + 1);
+            prop_assert_eq!(parent.steps + 1, node.steps);
+        }
+    }
+}
+
+#[test]
+fn deep_nesting_unwinds() {
+    // enter 16 loops, each on a cell of its own, then leave them all
+    let src = format!("+[{}-]{}", ">+[".repeat(15), "<-]".repeat(15));
+    let depths = loop_depths(&src, 1_000);
+    assert_eq!(depths.iter().max(), Some(&16));
+    let program: Program = src.parse().unwrap();
+    let mut node = SearchNode::concrete(program.root);
+    for &depth in &depths {
+        node = exec_known_step(node, &[]).unwrap().advanced().unwrap();
+        assert_eq!(node.loop_stack.len(), depth);
+    }
+    assert!(node.halted());
+    assert_eq!((node.steps, depths.last()), (94, Some(&0)));
+}
+
+#[test]
+fn programs_are_valid_and_varied() {
+    let mut runner = TestRunner::deterministic();
+    let (mut looped, mut nested, mut halted, mut capped) = (0, 0, 0, 0);
+    for _ in 0..500 {
+        let src = programs().new_tree(&mut runner).unwrap().current();
+        let run = agree(&src, BUDGET).unwrap();
+        looped += src.contains('[') as u32;
+        nested += src.contains("[[") as u32;
+        halted += run.end.stopped() as u32;
+        capped += (run.steps == BUDGET.steps) as u32;
+    }
+    assert!(looped > 200 && nested > 20, "{} with loops, {} nested", looped, nested);
+    assert!(halted > 100 && capped > 10, "{} halted, {} step-capped", halted, capped);
+}
+
+/// A `[-]` fast path gone wrong: the loop runs as `[--]`.
+struct BadFastPath;
+
+impl Runner for BadFastPath {
+    fn name(&self) -> &'static str {
+        "bad fast path"
+    }
+
+    fn run(&self, src: &str, budget: Budget) -> Result<Run, String> {
+        Reference.run(&src.replace("[-]", "[--]"), budget)
+    }
+}
+
+#[test]
+fn counterexamples_shrink() {
+    let config = Config {
+        failure_persistence: None,
+        ..Config::default()
+    };
+    let rng = TestRng::deterministic_rng(config.rng_algorithm);
+    let result = TestRunner::new_with_rng(config, rng).run(&programs(), |src| {
+        prop_assert_eq!(Reference.run(&src, BUDGET), BadFastPath.run(&src, BUDGET));
+        Ok(())
+    });
+    match result {
+        // the smallest program where `[-]` runs at all
+        Err(TestError::Fail(_, src)) => assert!(["+[-]", "-[-]"].contains(&src.as_str()), "{}", src),
+        other => panic!("expected a failure, got {:?}", other),
+    }
+}
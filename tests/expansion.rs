@@ -0,0 +1,151 @@
+// `step_once` on a hole against a plain reference: each child built by
+// `replace_hole` on its own clone of the parent, loop frames looked up
+// again by position in the new tree, then one step run. The expansion
+// builds all children around one located hole instead; the children must
+// come out the same, production for production.
+
+use bf_search::{
+    exec_known_step, node_at, replace_hole, step_once, AdvancePolicy, InstrSet, LoopFrame, PKind,
+    Program, ProgramNode, SearchNode, Shared, StepOutcome,
+};
+use proptest::prelude::*;
+
+/// The search state of `src` when its pc first reaches a hole, if it does
+/// within 2000 steps.
+fn at_hole(src: &str) -> Option<SearchNode> {
+    let p: Program = src.parse().unwrap();
+    let mut node = SearchNode::concrete(p.root);
+    while !matches!(node.pc.kind, PKind::Hole) {
+        if node.steps >= 2_000 {
+            return None;
+        }
+        node = exec_known_step(node, &[]).unwrap().advanced()?;
+    }
+    Some(node)
+}
+
+/// `node` with its hole at the pc filled with `replacement`, the old way.
+fn fill(node: &SearchNode, replacement: Shared<ProgramNode>) -> SearchNode {
+    let mut child = node.clone();
+    child.root = replace_hole(&node.root, node.pc_at, replacement).unwrap();
+    child.pc = node_at(&child.root, node.pc_at).unwrap();
+    let mut frames: Vec<LoopFrame> = node.loop_stack.iter().cloned().collect();
+    frames.reverse();
+    child.loop_stack = frames
+        .into_iter()
+        .map(|f| {
+            let body = node_at(&child.root, f.at + 1).unwrap();
+            let next = node_at(&child.root, f.at + 1 + body.size).unwrap();
+            LoopFrame { at: f.at, body, next }
+        })
+        .collect();
+    child
+}
+
+fn reference(node: &SearchNode, set: &InstrSet, target: &[u8]) -> Vec<StepOutcome> {
+    let empty = fill(node, ProgramNode::empty());
+    let mut out = vec![if empty.loop_stack.is_empty() {
+        StepOutcome::Halted(empty)
+    } else {
+        StepOutcome::Advanced(empty)
+    }];
+    for &i in &set.instrs {
+        let child = fill(node, ProgramNode::instr(i, ProgramNode::hole()));
+        out.push(exec_known_step(child, target).unwrap());
+    }
+    if set.loops {
+        let looped = ProgramNode::looped(ProgramNode::hole(), ProgramNode::hole());
+        out.push(exec_known_step(fill(node, looped), target).unwrap());
+    }
+    out
+}
+
+/// Everything that tells two search states apart, trees by their text and
+/// the counts of their nodes.
+fn describe(outcome: &StepOutcome, target: &[u8]) -> String {
+    let node = match outcome {
+        StepOutcome::Advanced(n) => n,
+        StepOutcome::Halted(n) => return format!("halted {}", describe(&StepOutcome::Advanced(n.clone()), target)),
+        StepOutcome::Pruned(reason) => return format!("pruned: {}", reason.describe()),
+        StepOutcome::Expanded(_) => return "expanded".into(),
+    };
+    let frames: Vec<String> = node
+        .loop_stack
+        .iter()
+        .map(|f| format!("{}:{} {}", f.at, f.body, f.next))
+        .collect();
+    let mut counts = Vec::new();
+    counts_of(&node.root, &mut counts);
+    format!(
+        "{} counts {:?} pc {}:{} frames {:?} dp {} tape {} steps {} output {:?}",
+        node.root,
+        counts,
+        node.pc_at,
+        node.pc,
+        frames,
+        node.dp,
+        node.tape,
+        node.steps,
+        node.output(target)
+    )
+}
+
+fn counts_of(node: &ProgramNode, out: &mut Vec<(u32, u32)>) {
+    out.push((node.min_len, node.size));
+    match &node.kind {
+        PKind::Instr(_, next) => counts_of(next, out),
+        PKind::Loop { body, next } => {
+            counts_of(body, out);
+            counts_of(next, out);
+        }
+        PKind::Hole | PKind::Empty => {}
+    }
+}
+
+fn check(src: &str, set: &InstrSet, target: &[u8]) -> Result<(), String> {
+    let Some(node) = at_hole(src) else { return Ok(()) };
+    let StepOutcome::Expanded(children) = step_once(&node, target, set, AdvancePolicy::Search).unwrap() else {
+        return Err(format!("{}: not expanded", src));
+    };
+    let got: Vec<String> = children.iter().map(|c| describe(c, target)).collect();
+    let want: Vec<String> = reference(&node, set, target).iter().map(|c| describe(c, target)).collect();
+    if got == want {
+        Ok(())
+    } else {
+        Err(format!("{}:\n got {:#?}\nwant {:#?}", src, got, want))
+    }
+}
+
+/// A concrete prefix, then the hole `nest` loops deep.
+fn partial_programs() -> impl Strategy<Value = String> {
+    let instr = prop_oneof![Just("+"), Just("-"), Just(">"), Just("<"), Just(".")];
+    let straight = prop::collection::vec(instr, 0..5).prop_map(|v| v.concat());
+    let prefix = straight.prop_recursive(3, 30, 3, |inner| {
+        let looped = inner.clone().prop_map(|body| format!("[{}]", body));
+        prop::collection::vec(prop_oneof![inner, looped], 1..3).prop_map(|v| v.concat())
+    });
+    (prefix, 0..5usize, "[+>.]{0,3}")
+        .prop_map(|(prefix, nest, tail)| format!("{}{}{}?{}", prefix, "+[".repeat(nest), tail, "]".repeat(nest)))
+}
+
+proptest! {
+    #[test]
+    fn children_match_the_reference(src in partial_programs(), target in prop::collection::vec(0u8..3, 0..3)) {
+        let set = InstrSet::parse("><+-.[]").unwrap();
+        check(&src, &set, &target).map_err(TestCaseError::fail)?;
+    }
+}
+
+#[test]
+fn restricted_sets_and_deep_loops() {
+    let deep = format!("+[{}?{}", ">+[".repeat(15), "]".repeat(16));
+    for (src, set) in [
+        ("?", "+."),
+        ("+>++?", "+-."),
+        ("+[->?]", "><+-.[]"),
+        ("++[>+[>?]]", "+."),
+        (deep.as_str(), "><+-.[]"),
+    ] {
+        check(src, &InstrSet::parse(set).unwrap(), &[1]).unwrap();
+    }
+}
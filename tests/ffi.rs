@@ -0,0 +1,87 @@
+// The C interface from C: `tests/ffi/smoke.c`, built with the `cc` crate's
+// compiler against `include/bf_search.h` and a static library the test
+// builds itself, finds the first solution, retries it after a short
+// buffer, runs a golf search out and a budget down, and passes the NULLs
+// the header allows.
+#![cfg(all(feature = "ffi", unix))]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The host triple, which is the target as well for these tests.
+fn host() -> String {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let out = Command::new(rustc).arg("-vV").output().expect("run rustc");
+    let info = String::from_utf8(out.stdout).unwrap();
+    info.lines().find_map(|l| l.strip_prefix("host: ")).expect("a host line").to_string()
+}
+
+/// `libbf_search.a` with the `ffi` feature, built now: whatever a plain
+/// `cargo build` left in `target/` may predate the C interface.
+fn static_library() -> PathBuf {
+    let mut build = Command::new(env!("CARGO"));
+    build
+        .args(["build", "--lib", "--features", "ffi", "--message-format=json", "--manifest-path"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"));
+    if !cfg!(debug_assertions) {
+        build.arg("--release");
+    }
+    let out = build.output().expect("run cargo");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let messages = String::from_utf8(out.stdout).unwrap();
+    let artifacts = messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|m| m["reason"] == "compiler-artifact" && m["target"]["name"] == "bf_search");
+    let files = artifacts.flat_map(|m| m["filenames"].as_array().cloned().unwrap_or_default());
+    files
+        .filter_map(|f| f.as_str().map(PathBuf::from))
+        .find(|f| f.extension().is_some_and(|e| e == "a"))
+        .expect("cargo built a static library")
+}
+
+#[test]
+fn c_program_against_the_static_library() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib = static_library();
+    let exe = std::env::temp_dir().join(format!("bf_search-ffi-{}", std::process::id()));
+
+    let triple = host();
+    let compiler = cc::Build::new()
+        .target(&triple)
+        .host(&triple)
+        .opt_level(0)
+        .cargo_metadata(false)
+        .get_compiler();
+    let status = compiler
+        .to_command()
+        .args(["-Wall", "-Wextra", "-Werror"])
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg(root.join("tests/ffi/smoke.c"))
+        .arg(&lib)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&exe)
+        .status()
+        .expect("run the C compiler");
+    assert!(status.success());
+
+    let out = Command::new(&exe).output().expect("run the C program");
+    std::fs::remove_file(&exe).unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "defaults: beta 1, gamma 1, golf 0",
+            "empty target: NULL",
+            "bad instr_set: NULL",
+            "small buffer: -2, code_len 4",
+            "first: 0 +.+. (4 instructions, 4 steps)",
+            "golf: 0 +.",
+            "then: 1",
+            "budget: 2",
+            "NULL handle: -1",
+        ]
+    );
+}
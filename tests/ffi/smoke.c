@@ -0,0 +1,49 @@
+/* Run by tests/ffi.rs against the static library: each check prints one
+ * line, which the test compares. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "bf_search.h"
+
+int main(void) {
+    const uint8_t target[] = {1, 2};
+    BfSearchConfig cfg;
+    bf_search_config_default(&cfg);
+    printf("defaults: beta %g, gamma %g, golf %d\n", cfg.beta, cfg.gamma, cfg.golf);
+    printf("empty target: %s\n", bf_search_new(target, 0, &cfg) ? "handle" : "NULL");
+    cfg.instr_set = "+x";
+    printf("bad instr_set: %s\n", bf_search_new(target, 2, &cfg) ? "handle" : "NULL");
+    cfg.instr_set = NULL;
+
+    cfg.max_expansions = 100000;
+    BfSearch *s = bf_search_new(target, sizeof target, &cfg);
+    char small[4], code[256];
+    BfSolutionInfo info;
+    int rc = bf_search_next_solution(s, small, sizeof small, &info);
+    printf("small buffer: %d, code_len %zu\n", rc, info.code_len);
+    rc = bf_search_next_solution(s, code, sizeof code, &info);
+    printf("first: %d %s (%u instructions, %llu steps)\n", rc, code, info.min_len,
+           (unsigned long long)info.steps);
+    bf_search_free(s);
+
+    /* nothing is shorter than "+." for [1] */
+    cfg.instr_set = "+.";
+    cfg.golf = 1;
+    s = bf_search_new(target, 1, &cfg);
+    rc = bf_search_next_solution(s, code, sizeof code, NULL);
+    printf("golf: %d %s\n", rc, code);
+    printf("then: %d\n", bf_search_next_solution(s, code, sizeof code, NULL));
+    bf_search_free(s);
+
+    bf_search_config_default(&cfg);
+    cfg.max_expansions = 10;
+    const uint8_t hard[] = {7, 200, 3};
+    s = bf_search_new(hard, sizeof hard, &cfg);
+    printf("budget: %d\n", bf_search_next_solution(s, code, sizeof code, NULL));
+    bf_search_free(s);
+
+    printf("NULL handle: %d\n", bf_search_next_solution(NULL, code, sizeof code, NULL));
+    bf_search_free(NULL);
+    return 0;
+}
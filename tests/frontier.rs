@@ -0,0 +1,29 @@
+// The bucketed frontier against the heap on the corpus in `common`. Buckets
+// finer than any score difference must reproduce the heap exactly, counts
+// included; at the default width the same solutions must come out
+// in the same order.
+
+mod common;
+
+use bf_search::Frontier;
+use common::{solutions, CORPUS};
+
+#[test]
+fn fine_buckets_match_the_heap() {
+    for &(target, golf) in CORPUS {
+        let heap = solutions(target, golf, Frontier::Heap, 3);
+        assert!(!heap.is_empty(), "{:?}", target);
+        let fine = solutions(target, golf, Frontier::Bucket { width: 1e-9 }, 3);
+        assert_eq!(fine, heap, "{:?} golf={}", target, golf);
+    }
+}
+
+#[test]
+fn default_buckets_find_the_same_solutions() {
+    for &(target, golf) in CORPUS {
+        let heap = solutions(target, golf, Frontier::Heap, 3);
+        let bucket = solutions(target, golf, Frontier::Bucket { width: 0.01 }, 3);
+        let codes = |v: &[common::Found]| v.iter().map(|f| f.code.clone()).collect::<Vec<_>>();
+        assert_eq!(codes(&bucket), codes(&heap), "{:?} golf={}", target, golf);
+    }
+}
@@ -0,0 +1,46 @@
+// Search results pinned on the corpus in `common`: the first solutions in
+// order, with the expansions and pushes it took to find each. A change to
+// how the search stores its nodes must leave every line as it is.
+
+mod common;
+
+use bf_search::Frontier;
+use common::{solutions, CORPUS};
+
+/// One line per solution: target, golf, code, length, expansions, pushes.
+fn report(target: &[u8], golf: bool, n: usize) -> String {
+    let found = solutions(target, golf, Frontier::Heap, n);
+    found
+        .iter()
+        .map(|f| {
+            format!(
+                "{:?} golf={} {} len={} expansions={} pushed={}\n",
+                target, golf, f.code, f.min_len, f.expansions, f.pushed
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn first_solutions_are_pinned() {
+    let got: String = CORPUS.iter().map(|&(target, golf)| report(target, golf, 3)).collect();
+    assert_eq!(got, EXPECTED);
+}
+
+const EXPECTED: &str = "\
+[1, 2, 3] golf=false +.+.+. len=6 expansions=163 pushed=790
+[1, 2, 3] golf=false +[.+] len=5 expansions=765 pushed=3632
+[1, 2, 3] golf=false +.+.+.> len=7 expansions=880 pushed=3994
+[5, 5, 5, 5] golf=false +++++.... len=9 expansions=2408 pushed=9970
+[5, 5, 5, 5] golf=false +++++....> len=10 expansions=11048 pushed=42541
+[5, 5, 5, 5] golf=false +++++....< len=10 expansions=11049 pushed=42548
+[3, 6, 9] golf=false +[++.+] len=7 expansions=11274 pushed=44114
+[0, 1, 0, 1] golf=false .+.>.<. len=7 expansions=506 pushed=2603
+[0, 1, 0, 1] golf=false .+.>.+. len=7 expansions=507 pushed=2610
+[0, 1, 0, 1] golf=false .+.<.>. len=7 expansions=508 pushed=2617
+[2, 4] golf=false ++.++. len=6 expansions=515 pushed=2386
+[2, 4] golf=false ++.++.> len=7 expansions=2486 pushed=10903
+[2, 4] golf=false ++.++.< len=7 expansions=2487 pushed=10910
+[5, 5, 5, 5] golf=true +++++.... len=9 expansions=2408 pushed=9963
+[3, 6, 9] golf=true +[++.+] len=7 expansions=11274 pushed=44113
+";
@@ -0,0 +1,26 @@
+// `--golf` through the binary, with weights that find a long straight-line
+// program first: every later report must be strictly shorter, until the
+// search proves no shorter one exists.
+
+use std::process::{Command, Stdio};
+
+#[test]
+fn reported_lengths_strictly_decrease() {
+    let out = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .args(["--golf", "--beta", "0.3", "--gamma", "2", "--max-steps", "2000", "--solutions", "100"])
+        .args(["1", "2", "3", "4", "5", "6", "7", "8"])
+        .stdin(Stdio::null())
+        .output()
+        .expect("run bf_search");
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    let lengths: Vec<u32> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Program length (inst): "))
+        .map(|len| len.parse().unwrap())
+        .collect();
+    assert!(lengths.len() >= 2, "{}", stdout);
+    assert!(lengths.windows(2).all(|w| w[1] < w[0]), "{:?}", lengths);
+    assert_eq!(lengths.last(), Some(&5)); // +[.+]
+    assert!(stdout.contains("Golf          : best length 5; no shorter program exists"), "{}", stdout);
+}
@@ -0,0 +1,96 @@
+// Interned trees share one node for equal subtrees, so two holes can be the
+// very same node at two places. Filling one of them, by `replace_hole` or
+// by expanding a search node at it, must leave the other a hole, and leave
+// the tree it came from as it was.
+
+use bf_search::intern::Interner;
+use bf_search::{
+    exec_known_step, node_at, replace_hole, step_once_with, AdvancePolicy, Instr, InstrSet, PKind,
+    ProgramNode, SearchNode, Shared, StepOutcome,
+};
+
+/// `first` then `[?]?` through `interner`, the loop's body and next one
+/// hole node.
+fn twin_holes(interner: &mut Interner, first: Instr) -> Shared<ProgramNode> {
+    let (body, next) = (interner.hole(), interner.hole());
+    let looped = interner.looped(body, next);
+    interner.instr(first, looped)
+}
+
+fn same(a: &Shared<ProgramNode>, b: &Shared<ProgramNode>) -> bool {
+    Shared::ptr_eq(a, b)
+}
+
+#[test]
+fn replacing_one_of_two_equal_holes() {
+    let mut interner = Interner::new();
+    let root = twin_holes(&mut interner, Instr::Inc);
+    let (body, next) = (node_at(&root, 2).unwrap(), node_at(&root, 3).unwrap());
+    assert!(same(&body, &next));
+
+    let end = interner.empty();
+    let out = interner.instr(Instr::Output, end);
+    let filled = replace_hole(&root, 2, out.clone()).unwrap();
+    assert_eq!(filled.to_string(), "+[.]?");
+    assert!(same(&node_at(&filled, 2).unwrap(), &out));
+    // the other place still holds the hole node itself
+    assert!(same(&node_at(&filled, 2 + out.size).unwrap(), &next));
+
+    let filled = replace_hole(&root, 3, out.clone()).unwrap();
+    assert_eq!(filled.to_string(), "+[?].");
+    assert!(same(&node_at(&filled, 2).unwrap(), &body));
+
+    assert_eq!(root.to_string(), "+[?]?");
+    assert!(matches!(node_at(&root, 2).unwrap().kind, PKind::Hole));
+}
+
+/// Every child of an expansion, as source.
+fn children(node: &SearchNode, interner: &mut Interner) -> Vec<String> {
+    let set = InstrSet::parse("+.[]").unwrap();
+    let Ok(StepOutcome::Expanded(outcomes)) =
+        step_once_with(node, &[1], &set, AdvancePolicy::Search, interner)
+    else {
+        panic!("no expansion at {}", node.pc_at)
+    };
+    outcomes
+        .into_iter()
+        .map(|o| match o {
+            StepOutcome::Advanced(child) | StepOutcome::Halted(child) => child.root.to_string(),
+            StepOutcome::Pruned(reason) => reason.describe().to_string(),
+            StepOutcome::Expanded(_) => unreachable!(),
+        })
+        .collect()
+}
+
+#[test]
+fn expanding_one_of_two_equal_holes() {
+    let mut interner = Interner::new();
+    let root = twin_holes(&mut interner, Instr::Inc);
+
+    // `+` then `[`: inside the loop, at the body
+    let mut inside = SearchNode::concrete(root.clone());
+    for _ in 0..2 {
+        inside = exec_known_step(inside, &[1]).unwrap().advanced().unwrap();
+    }
+    assert_eq!(inside.pc_at, 2);
+    assert_eq!(
+        children(&inside, &mut interner),
+        ["+[]?", "+[+?]?", "+[.?]?", "+[[?]?]?"]
+    );
+
+    // `>` then `[` on a zero cell: past the loop, at the next
+    let skipped = twin_holes(&mut interner, Instr::IncPtr);
+    let mut after = SearchNode::concrete(skipped.clone());
+    for _ in 0..2 {
+        after = exec_known_step(after, &[1]).unwrap().advanced().unwrap();
+    }
+    assert_eq!(after.pc_at, 3);
+    assert_eq!(
+        children(&after, &mut interner),
+        [">[?]", ">[?]+?", "output mismatch", ">[?][?]?"]
+    );
+    assert_eq!(
+        (root.to_string(), skipped.to_string()),
+        ("+[?]?".into(), ">[?]?".into())
+    );
+}
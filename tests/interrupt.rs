@@ -0,0 +1,87 @@
+// Ctrl+C through the binary: SIGINT during a long search prints the summary
+// with the best partial program and exits with 3; a second one while the
+// first is still being handled aborts with 130.
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+fn sigint(child: &Child) {
+    let status = Command::new("kill").args(["-INT", &child.id().to_string()]).status().expect("run kill");
+    assert!(status.success());
+}
+
+/// `child`'s exit code, failing the test if it takes longer than `limit`.
+fn wait(child: &mut Child, limit: Duration) -> Option<i32> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            return status.code();
+        }
+        if start.elapsed() > limit {
+            child.kill().unwrap();
+            panic!("still running after {:?}", limit);
+        }
+        sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn interrupt_prints_the_summary() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .args(["7", "200", "3", "100", "9", "1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run bf_search");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    while !line.starts_with("Press Ctrl+C") {
+        line.clear();
+        assert!(stdout.read_line(&mut line).unwrap() > 0, "no prompt line");
+    }
+    sleep(Duration::from_millis(300)); // well into the search
+    sigint(&child);
+
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+    assert_eq!(wait(&mut child, Duration::from_secs(20)), Some(3));
+    assert!(rest.contains("Stopped       : interrupted (Ctrl+C)"), "{}", rest);
+    assert!(rest.contains("Best partial program so far"), "{}", rest);
+    let mut stderr = String::new();
+    child.stderr.take().unwrap().read_to_string(&mut stderr).unwrap();
+    assert!(stderr.contains("Interrupted: stopping the search"), "{}", stderr);
+}
+
+#[test]
+fn second_interrupt_aborts() {
+    // The search blocks writing its --record log to a FIFO nobody reads, so
+    // the first interrupt cannot finish and the second must abort.
+    let fifo = std::env::temp_dir().join(format!("bf_search-interrupt-{}.fifo", std::process::id()));
+    let _ = std::fs::remove_file(&fifo);
+    assert!(Command::new("mkfifo").arg(&fifo).status().expect("run mkfifo").success());
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .arg("--record")
+        .arg(&fifo)
+        .args(["7", "200", "3", "100", "9", "1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("run bf_search");
+    let mut log = std::fs::File::open(&fifo).unwrap();
+    let mut header = [0; 16];
+    log.read_exact(&mut header).unwrap();
+    sleep(Duration::from_millis(500)); // the FIFO fills up
+
+    sigint(&child);
+    sleep(Duration::from_millis(300));
+    assert!(child.try_wait().unwrap().is_none(), "the first interrupt should not end a stuck search");
+    sigint(&child);
+    assert_eq!(wait(&mut child, Duration::from_secs(20)), Some(130));
+    drop(log);
+    std::fs::remove_file(&fifo).unwrap();
+}
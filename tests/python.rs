@@ -0,0 +1,56 @@
+// The Python module from Python: the cdylib, copied to where `import
+// bf_search` finds it (what `maturin develop` does), runs the unittest
+// suite in `tests/python`: iterating a search, its first solution,
+// StopIteration at the end, a signal handler interrupting a long `next`,
+// and the exceptions for bad arguments.
+#![cfg(all(feature = "python", unix))]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The extension module with the `python` feature, built now: `cargo test`
+/// links the tests against the rlib, so whatever cdylib `target/` holds may
+/// be stale.
+fn extension_module() -> PathBuf {
+    let mut build = Command::new(env!("CARGO"));
+    build
+        .args(["build", "--lib", "--features", "python", "--message-format=json", "--manifest-path"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"));
+    if !cfg!(debug_assertions) {
+        build.arg("--release");
+    }
+    let out = build.output().expect("run cargo");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let messages = String::from_utf8(out.stdout).unwrap();
+    let artifacts = messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|m| m["reason"] == "compiler-artifact" && m["target"]["name"] == "bf_search");
+    let files = artifacts.flat_map(|m| m["filenames"].as_array().cloned().unwrap_or_default());
+    files
+        .filter_map(|f| f.as_str().map(PathBuf::from))
+        .find(|f| f.extension().is_some_and(|e| e == std::env::consts::DLL_EXTENSION))
+        .expect("cargo built a cdylib")
+}
+
+#[test]
+fn python_suite() {
+    let lib = extension_module();
+    let dir = std::env::temp_dir().join(format!("bf_search-python-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(&lib, dir.join("bf_search.so")).unwrap();
+
+    let python = std::env::var("PYTHON").unwrap_or_else(|_| "python3".into());
+    let suite = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/python");
+    let out = Command::new(python)
+        .args(["-m", "unittest", "discover", "-v", "-s"])
+        .arg(&suite)
+        .env("PYTHONPATH", &dir)
+        .env("PYTHONDONTWRITEBYTECODE", "1")
+        .output()
+        .expect("run python");
+    std::fs::remove_dir_all(&dir).unwrap();
+    let report = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "{}", report);
+    assert!(report.contains("Ran 6 tests"), "{}", report);
+}
@@ -0,0 +1,68 @@
+"""The Python module, run by tests/python.rs against the built extension (or
+on its own after `maturin develop --features python`:
+`python -m unittest discover -s tests/python`)."""
+
+import signal
+import time
+import unittest
+
+import bf_search
+
+
+class SearchTest(unittest.TestCase):
+    def test_first_solution(self):
+        it = bf_search.search(bytes([1, 2]), max_expansions=100_000)
+        self.assertIs(iter(it), it)
+        sol = next(it)
+        self.assertEqual((sol.code, sol.length, sol.steps), ("+.+.", 4, 4))
+        self.assertEqual(bf_search.run(sol.code, 2), b"\x01\x02")
+        self.assertTrue(repr(sol).startswith('Solution(code="+.+.", length=4'))
+
+    def test_for_loop_until_exhausted(self):
+        # nothing is shorter than "+." for [1]
+        it = bf_search.search(bytes([1]), instr_set="+.", golf=True)
+        self.assertEqual([sol.code for sol in it], ["+."])
+        with self.assertRaises(StopIteration):
+            next(it)
+
+    def test_budget_ends_the_iteration(self):
+        it = bf_search.search(bytes([7, 200, 3]), max_expansions=10)
+        self.assertEqual(list(it), [])
+
+    def test_signal_interrupts_next(self):
+        # no solution for a long while: the handler's exception ends `next`
+        def expire(signum, frame):
+            raise TimeoutError
+
+        it = bf_search.search(bytes([7, 200, 3, 100, 9, 1]))
+        previous = signal.signal(signal.SIGALRM, expire)
+        try:
+            start = time.monotonic()
+            signal.setitimer(signal.ITIMER_REAL, 0.2)
+            with self.assertRaises(TimeoutError):
+                next(it)
+            self.assertLess(time.monotonic() - start, 5)
+        finally:
+            signal.setitimer(signal.ITIMER_REAL, 0)
+            signal.signal(signal.SIGALRM, previous)
+
+    def test_invalid_arguments_raise(self):
+        for kwargs, message in [
+            (dict(target=b""), "target must not be empty"),
+            (dict(target=b"\x01", instr_set="+x"), "'x' is not a Brainfuck command"),
+            (dict(target=b"\x01", beta=-1.0), "beta must be a number"),
+        ]:
+            with self.subTest(**kwargs), self.assertRaisesRegex(ValueError, message):
+                bf_search.search(**kwargs)
+        with self.assertRaisesRegex(ValueError, "unmatched"):
+            bf_search.run("[", 1)
+        with self.assertRaises(TypeError):
+            bf_search.search([1, 2], beta="high")
+
+    def test_run(self):
+        self.assertEqual(bf_search.run("++.+.", 2), b"\x02\x03")
+        self.assertEqual(bf_search.run("+[.]", 3), b"\x01\x01\x01")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,126 @@
+// `replace_hole` against a plain reference that rebuilds the whole tree:
+// on random partial programs, filling any of their holes must give the same
+// tree (`min_len`, hole counts, sizes), and every subtree off the path to
+// the hole must come back shared rather than copied.
+
+use bf_search::{replace_hole, Instr, PKind, Program, ProgramNode, SearchError, Shared};
+use proptest::prelude::*;
+
+/// Valid programs with holes, loops nested up to 4 deep. A hole stands for
+/// the rest of its block, so `?` only ends one.
+fn partial_programs() -> impl Strategy<Value = String> {
+    let instr = prop_oneof![Just("+".to_string()), Just(">".to_string()), Just(".".to_string())];
+    let ending = prop_oneof![Just(""), Just("?")];
+    let straight = (prop::collection::vec(instr.clone(), 0..6), ending.clone())
+        .prop_map(|(v, end)| v.concat() + end);
+    straight.prop_recursive(4, 60, 4, move |inner| {
+        let looped = inner.prop_map(|body| format!("[{}]", body));
+        let item = prop_oneof![instr.clone(), looped];
+        (prop::collection::vec(item, 1..4), ending.clone()).prop_map(|(v, end)| v.concat() + end)
+    })
+}
+
+/// Copy of `node` with the hole at position `at` (relative to `node`)
+/// replaced, rebuilding every node.
+fn rebuild_all(node: &ProgramNode, at: i64, replacement: &Shared<ProgramNode>) -> Shared<ProgramNode> {
+    match &node.kind {
+        PKind::Hole if at == 0 => replacement.clone(),
+        PKind::Hole => ProgramNode::hole(),
+        PKind::Empty => ProgramNode::empty(),
+        PKind::Instr(i, next) => ProgramNode::instr(*i, rebuild_all(next, at - 1, replacement)),
+        PKind::Loop { body, next } => ProgramNode::looped(
+            rebuild_all(body, at - 1, replacement),
+            rebuild_all(next, at - 1 - body.size as i64, replacement),
+        ),
+    }
+}
+
+/// Pre-order (min_len, holes, size, kind) of every node.
+fn shape(node: &ProgramNode, out: &mut Vec<(u32, u32, u32, String)>) {
+    let kind = match &node.kind {
+        PKind::Hole => "?".to_string(),
+        PKind::Empty => "".to_string(),
+        PKind::Instr(i, _) => format!("{:?}", i),
+        PKind::Loop { .. } => "[]".to_string(),
+    };
+    out.push((node.min_len, node.holes, node.size, kind));
+    match &node.kind {
+        PKind::Instr(_, next) => shape(next, out),
+        PKind::Loop { body, next } => {
+            shape(body, out);
+            shape(next, out);
+        }
+        PKind::Hole | PKind::Empty => {}
+    }
+}
+
+/// Positions of the holes of `node`, which is at position `at`.
+fn holes(node: &ProgramNode, at: u32, out: &mut Vec<u32>) {
+    match &node.kind {
+        PKind::Hole => out.push(at),
+        PKind::Empty => {}
+        PKind::Instr(_, next) => holes(next, at + 1, out),
+        PKind::Loop { body, next } => {
+            holes(body, at + 1, out);
+            holes(next, at + 1 + body.size, out);
+        }
+    }
+}
+
+/// Check that the subtrees of `old` not holding position `at` (relative to
+/// `old`) are the very nodes in `new`, which has the same shape; returns
+/// how many were shared.
+fn shared_off_path(old: &Shared<ProgramNode>, new: &Shared<ProgramNode>, at: i64) -> Result<usize, String> {
+    if !(0..old.size as i64).contains(&at) {
+        return if Shared::ptr_eq(old, new) {
+            Ok(1)
+        } else {
+            Err(format!("{} copied though off the path", old))
+        };
+    }
+    match (&old.kind, &new.kind) {
+        (PKind::Hole, _) => Ok(0),
+        (PKind::Instr(_, a), PKind::Instr(_, b)) => shared_off_path(a, b, at - 1),
+        (PKind::Loop { body: a, next: a2 }, PKind::Loop { body: b, next: b2 }) => {
+            Ok(shared_off_path(a, b, at - 1)? + shared_off_path(a2, b2, at - 1 - a.size as i64)?)
+        }
+        _ => Err(format!("{} changed kind", old)),
+    }
+}
+
+proptest! {
+    #[test]
+    fn matches_a_full_rebuild(src in partial_programs(), pick in any::<prop::sample::Index>(), fill in 0..3u8) {
+        let p: Program = src.parse().unwrap();
+        let mut found = Vec::new();
+        holes(&p.root, 0, &mut found);
+        prop_assume!(!found.is_empty());
+        let at = found[pick.index(found.len())];
+        // the productions of a hole: Empty, I;P and [P];P with fresh holes
+        let replacement = match fill {
+            0 => ProgramNode::empty(),
+            1 => ProgramNode::instr(Instr::Inc, ProgramNode::hole()),
+            _ => ProgramNode::looped(ProgramNode::hole(), ProgramNode::hole()),
+        };
+
+        let filled = replace_hole(&p.root, at, replacement.clone()).unwrap();
+        let expected = rebuild_all(&p.root, at as i64, &replacement);
+        let (mut got, mut want) = (Vec::new(), Vec::new());
+        shape(&filled, &mut got);
+        shape(&expected, &mut want);
+        prop_assert_eq!(got, want, "{} filling the hole at {}", src, at);
+        shared_off_path(&p.root, &filled, at as i64).map_err(TestCaseError::fail)?;
+    }
+}
+
+#[test]
+fn siblings_of_a_deep_hole_stay_shared() {
+    // a hole behind a 2000-command loop body: only the loop is rebuilt
+    let p: Program = format!("[{}]?", "+".repeat(2000)).parse().unwrap();
+    let at = p.root.size - 1;
+    let filled = replace_hole(&p.root, at, ProgramNode::empty()).unwrap();
+    assert_eq!(shared_off_path(&p.root, &filled, at as i64), Ok(1));
+    assert_eq!(filled.min_len, 2002);
+    let missing = replace_hole(&filled, at, ProgramNode::empty()).err();
+    assert_eq!(missing, Some(SearchError::HoleNotFound(at)));
+}
@@ -0,0 +1,100 @@
+// `bf_search serve` driven through a scripted session on its stdin and
+// stdout: two searches open at once, a tiny target searched to the end,
+// a program run, and the errors malformed requests get.
+
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Feed `requests` to a fresh daemon, one per line, and collect its
+/// responses after the input ends.
+fn session(requests: &[&str]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .arg("serve")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("start bf_search serve");
+    let mut stdin = child.stdin.take().unwrap();
+    for request in requests {
+        writeln!(stdin, "{}", request).unwrap();
+    }
+    drop(stdin);
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    let text = String::from_utf8(out.stdout).unwrap();
+    text.lines().map(|line| serde_json::from_str(line).expect("one JSON response per line")).collect()
+}
+
+#[test]
+fn full_search_of_a_tiny_target() {
+    let replies = session(&[
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "start_search", "params": {"target": [3], "config": {"instr_set": "+.", "max_steps": 10}}}"#,
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "start_search", "params": {"target": [1, 2]}}"#,
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "step", "params": {"search": 1, "expansions": 2}}"#,
+        r#"{"jsonrpc": "2.0", "id": "four", "method": "step", "params": {"search": 2, "expansions": 100}}"#,
+        r#"{"jsonrpc": "2.0", "id": 5, "method": "step", "params": {"search": 1, "expansions": 1000}}"#,
+        r#"{"jsonrpc": "2.0", "id": 6, "method": "cancel", "params": {"search": 2}}"#,
+        r#"{"jsonrpc": "2.0", "id": 7, "method": "step", "params": {"search": 2, "expansions": 1}}"#,
+        r#"{"jsonrpc": "2.0", "id": 8, "method": "run_program", "params": {"code": "+++.[-]", "limit": 5}}"#,
+    ]);
+    let ids: Vec<&Value> = replies.iter().map(|r| &r["id"]).collect();
+    assert_eq!(ids, [&json!(1), &json!(2), &json!(3), &json!("four"), &json!(5), &json!(6), &json!(7), &json!(8)]);
+    assert!(replies.iter().all(|r| r["jsonrpc"] == "2.0"));
+    assert_eq!((&replies[0]["result"]["search"], &replies[1]["result"]["search"]), (&json!(1), &json!(2)));
+
+    // two expansions are not enough for "+++."; the rest runs dry
+    let first = &replies[2]["result"];
+    assert_eq!((&first["expansions"], &first["solutions"], &first["done"]), (&json!(2), &json!([]), &json!(false)));
+    let last = &replies[4]["result"];
+    assert_eq!(last["solutions"][0]["code"], "+++.");
+    assert_eq!(last["solutions"][0]["min_len"], 4);
+    assert_eq!(last["done"], true);
+    assert_eq!(last["frontier"], 0);
+    assert!(last["expansions"].as_u64().unwrap() < 1000);
+
+    // the other search went its own way in between, then was dropped
+    assert_eq!(replies[3]["result"]["solutions"][0]["code"], "+.+.");
+    assert_eq!(replies[5]["result"], true);
+    assert_eq!(replies[6]["error"]["code"], -32602);
+
+    let run = &replies[7]["result"];
+    assert_eq!((&run["output"], &run["end"], &run["steps"]), (&json!([3]), &json!("halted"), &json!(11)));
+}
+
+#[test]
+fn malformed_requests_get_errors() {
+    let replies = session(&[
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "step""#,
+        r#"[1, 2]"#,
+        r#"{"id": 2, "method": "step", "params": {"search": 1, "expansions": 1}}"#,
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "launch"}"#,
+        r#"{"jsonrpc": "2.0", "id": 4, "method": "start_search", "params": {"target": [300]}}"#,
+        r#"{"jsonrpc": "2.0", "id": 5, "method": "start_search", "params": {"target": [], "config": {}}}"#,
+        r#"{"jsonrpc": "2.0", "id": 6, "method": "start_search", "params": {"target": [1], "config": {"colour": 1}}}"#,
+        r#"{"jsonrpc": "2.0", "id": 7, "method": "run_program", "params": {"code": "[", "limit": 1}}"#,
+        r#"{"jsonrpc": "2.0", "id": [8], "method": "cancel"}"#,
+        r#"{"jsonrpc": "2.0", "method": "run_program", "params": {"code": "+.", "limit": 1}}"#,
+        r#"{"jsonrpc": "2.0", "id": 9, "method": "run_program", "params": {"code": "+.", "limit": 1}}"#,
+    ]);
+    let errors: Vec<(Value, Value)> =
+        replies.iter().map(|r| (r["id"].clone(), r["error"]["code"].clone())).collect();
+    assert_eq!(
+        errors,
+        [
+            (Value::Null, json!(-32700)),
+            (Value::Null, json!(-32600)),
+            (json!(2), json!(-32600)),
+            (json!(3), json!(-32601)),
+            (json!(4), json!(-32602)),
+            (json!(5), json!(-32602)),
+            (json!(6), json!(-32602)),
+            (json!(7), json!(-32602)),
+            (Value::Null, json!(-32600)),
+            // the notification got no response; the daemon is still up
+            (json!(9), Value::Null),
+        ]
+    );
+    assert!(replies[5]["error"]["message"].as_str().unwrap().contains("target must not be empty"));
+    assert_eq!(replies[9]["result"]["output"], json!([1]));
+}
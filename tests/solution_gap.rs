@@ -0,0 +1,50 @@
+// `--solution-gap` and `--solution-gap-expansions` on a fast target, with
+// stdin closed so that the batch policy applies: the search must stop soon
+// after its first solution and say why.
+
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+fn run(args: &[&str]) -> (Output, Duration) {
+    let start = Instant::now();
+    let out = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .expect("run bf_search");
+    (out, start.elapsed())
+}
+
+/// The number on the summary's "Solutions" line.
+fn solutions(stdout: &str) -> usize {
+    let line = stdout.lines().find(|l| l.trim_start().starts_with("Solutions")).expect("a summary");
+    line.rsplit(':').next().unwrap().trim().parse().unwrap()
+}
+
+#[test]
+fn time_gap_ends_the_search() {
+    let (out, took) = run(&["--solution-gap", "0.2", "1", "2"]);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(stdout.contains("Stopped       : no new solution within the --solution-gap"), "{}", stdout);
+    assert!(solutions(&stdout) >= 1);
+    assert!(took < Duration::from_secs(20), "took {:?}", took);
+}
+
+#[test]
+fn expansion_gap_ends_the_search() {
+    let (out, _) = run(&["--solution-gap-expansions", "1", "1", "2"]);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(stdout.contains("Stop: when no new solution comes within 1 expansion(s)"));
+    assert!(stdout.contains("Stopped       : no new solution within the --solution-gap"), "{}", stdout);
+    assert_eq!(solutions(&stdout), 1);
+}
+
+#[test]
+fn gap_must_be_positive() {
+    for args in [["--solution-gap", "0"], ["--solution-gap-expansions", "0"]] {
+        let (out, _) = run(&[args[0], args[1], "1", "2"]);
+        assert_eq!(out.status.code(), Some(2));
+    }
+}
@@ -0,0 +1,49 @@
+// `Searcher::solutions` and `next_solution` resume the same search: each
+// solution is new, prints the target, and costs further expansions.
+
+use bf_search::interp::Interpreter;
+use bf_search::{SearchConfig, Searcher};
+
+fn prints(code: &str, target: &[u8]) -> bool {
+    let mut it = Interpreter::from_source(code).unwrap().with_step_cap(10_000);
+    it.run_to_limit(target.len()).unwrap();
+    it.output() == target
+}
+
+#[test]
+fn two_solutions_from_the_iterator() {
+    let target = [1, 2];
+    let mut searcher = Searcher::new(SearchConfig::new(target.to_vec()).build().unwrap());
+    let first = searcher.solutions().next().unwrap();
+    let expansions = searcher.stats().expansions;
+    assert!(expansions > 0);
+
+    // a new iterator goes on from the same frontier
+    let second = searcher.solutions().next().unwrap();
+    assert_ne!(first.code, second.code);
+    assert!(searcher.stats().expansions > expansions, "{} expansions", expansions);
+    assert_eq!(searcher.stats().solutions, 2);
+    for s in [&first, &second] {
+        assert!(prints(&s.code, &target), "{}", s.code);
+        assert_eq!(s.min_len as usize, s.code.len());
+    }
+}
+
+#[test]
+fn next_solution_takes_turns_with_the_iterator() {
+    let target = [3];
+    let mut searcher = Searcher::new(SearchConfig::new(target.to_vec()).build().unwrap());
+    let mut codes = Vec::new();
+    let mut expansions = vec![0];
+    for i in 0..4 {
+        let solution = if i % 2 == 0 { searcher.next_solution() } else { searcher.solutions().next() };
+        codes.push(solution.unwrap().code);
+        expansions.push(searcher.stats().expansions);
+    }
+    assert!(expansions.windows(2).all(|w| w[0] < w[1]), "{:?}", expansions);
+    let mut distinct = codes.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct.len(), codes.len(), "{:?}", codes);
+    assert!(codes.iter().all(|code| prints(code, &target)), "{:?}", codes);
+}
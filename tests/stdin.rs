@@ -0,0 +1,42 @@
+// With stdin closed there is no one to answer the prompt: the search must
+// stop by itself (after one solution by default, or --solutions) rather
+// than read EOF as "continue" forever.
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+fn run(args: &[&str]) -> (Option<i32>, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("run bf_search");
+    let start = Instant::now();
+    while child.try_wait().unwrap().is_none() {
+        if start.elapsed() > Duration::from_secs(30) {
+            child.kill().unwrap();
+            panic!("still running with stdin closed");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let out = child.wait_with_output().unwrap();
+    (out.status.code(), String::from_utf8(out.stdout).unwrap())
+}
+
+#[test]
+fn closed_stdin_stops_after_one_solution() {
+    let (code, stdout) = run(&["1", "2"]);
+    assert_eq!(code, Some(0));
+    assert!(stdout.contains("stdin is not a terminal; stopping after 1 solution(s)."), "{}", stdout);
+    assert!(!stdout.contains("Press Enter"), "{}", stdout);
+    assert_eq!(stdout.matches("Solution #").count(), 1);
+    assert!(stdout.contains("Stopped       : requested number of solutions found"), "{}", stdout);
+}
+
+#[test]
+fn closed_stdin_honors_solutions() {
+    let (code, stdout) = run(&["--solutions", "3", "1", "2"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout.matches("Solution #").count(), 3);
+}
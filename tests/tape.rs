@@ -0,0 +1,46 @@
+// Tapes against a plain map: random writes near and far from the origin,
+// with clones taken along the way, must read back as the map does, leave
+// the clones untouched, and compare equal to a tape built from the same
+// nonzero cells (zero cells are never kept).
+
+use bf_search::Tape;
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+/// (cell, value, clone the tape first); cells span the dense block and
+/// the sparse map on both sides of it, values are often zero.
+fn writes() -> impl Strategy<Value = Vec<(i64, u8, bool)>> {
+    let cell = prop_oneof![-20i64..50, -1_000i64..1_000];
+    let value = prop_oneof![Just(0u8), any::<u8>()];
+    prop::collection::vec((cell, value, prop::bool::weighted(0.2)), 0..200)
+}
+
+fn model_tape(model: &HashMap<i64, u8>) -> Tape {
+    model.iter().filter(|&(_, &v)| v != 0).map(|(&i, &v)| (i, v)).collect()
+}
+
+proptest! {
+    #[test]
+    fn writes_match_a_map(writes in writes()) {
+        let mut tape = Tape::new();
+        let mut model = HashMap::new();
+        let mut clones = Vec::new();
+        for (cell, value, clone) in writes {
+            if clone {
+                clones.push((tape.clone(), model.clone()));
+            }
+            tape.set(cell, value);
+            model.insert(cell, value);
+            prop_assert_eq!(tape.get(cell), value);
+        }
+        prop_assert!(tape == model_tape(&model));
+        let mut cells: Vec<_> = tape.nonzero().collect();
+        cells.sort();
+        let mut expected: Vec<_> = model.into_iter().filter(|&(_, v)| v != 0).collect();
+        expected.sort();
+        prop_assert_eq!(cells, expected);
+        for (copy, at_clone) in clones {
+            prop_assert!(copy == model_tape(&at_clone));
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// `bf_search trace` driven by a script on stdin: command parsing, stepping,
+// breakpoints on output bytes, and how states and deltas are rendered.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The reply to each line of `script`, then what follows the last prompt.
+fn session(args: &[&str], script: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bf_search"))
+        .arg("trace")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("run bf_search");
+    child.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let mut replies: Vec<String> = stdout.split("trace> ").map(|r| r.trim_end().to_string()).collect();
+    assert!(replies.remove(0).starts_with("Commands: s [N] step"));
+    replies
+}
+
+#[test]
+fn commands_parse() {
+    let replies = session(&["+."], "x\ns y\ns 1 2\nb\nb 0\nb z\np 1\nq 1\nq\n");
+    assert_eq!(
+        replies,
+        [
+            "unknown command 'x' (s [N], b N, p, q)",
+            "'y' is not a step count",
+            "too many arguments",
+            "missing an output byte number",
+            "output bytes are numbered from 1",
+            "'z' is not an output byte number",
+            "'p' takes no argument",
+            "'q' takes no argument",
+            "0 steps, 0 output byte(s):",
+        ]
+    );
+}
+
+#[test]
+fn steps_and_deltas() {
+    // Enter alone steps once, like `s`
+    let replies = session(&["++[>++<-]>."], "s\ns 3\n\np\nq\n");
+    assert_eq!(
+        replies,
+        [
+            "executed 1 instruction(s), last '+' (step 1): cells [0] 0 -> 1",
+            "executed 3 instruction(s), last '>' (step 4): dp 0 -> 1; cells [0] 1 -> 2",
+            "executed 1 instruction(s), last '+' (step 5): cells [1] 0 -> 1",
+            "dp = 1, steps = 5, tape[-7..=9]: 0 0 0 0 0 0 0 2 [1] 0 0 0 0 0 0 0 0",
+            "5 steps, 0 output byte(s):",
+        ]
+    );
+}
+
+#[test]
+fn break_on_output() {
+    let replies = session(&["++[>++<-]>.+.,"], "b 2\nb 3\n");
+    assert_eq!(
+        replies,
+        [
+            "executed 19 instruction(s), last '.' (step 19): dp 0 -> 1; cells [1] 0 -> 5; output 4 5",
+            "program stopped at ',' (input is not supported)",
+            "\n19 steps, 2 output byte(s): 4 5",
+        ]
+    );
+    let replies = session(&["+."], "b 2\n");
+    assert_eq!(replies[0], "executed 2 instruction(s), last '.' (step 2): cells [0] 0 -> 1; output 1\nprogram halted");
+}
+
+#[test]
+fn step_cap() {
+    let replies = session(&["--max-steps", "10", "+[]"], "s 100\nq\n");
+    assert_eq!(
+        replies[0],
+        "executed 10 instruction(s), last ']' (step 10): cells [0] 0 -> 1\nstopped after --max-steps 10 instructions"
+    );
+}
@@ -0,0 +1,95 @@
+// The wasm-bindgen interface, headless: a run sliced across `step` calls,
+// cancellation after exactly the requested expansions, and the solutions the
+// slices hand back. The README's WebAssembly section shows how to run them.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use bf_search::wasm::start_search;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Options {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instr_set: Option<&'static str>,
+    golf: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Found {
+    code: String,
+    min_len: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress {
+    solutions: Vec<Found>,
+    expansions: u64,
+    solutions_total: usize,
+    done: bool,
+}
+
+fn step(handle: &mut bf_search::wasm::SearchHandle, n: u32) -> Progress {
+    serde_wasm_bindgen::from_value(handle.step(n).unwrap()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn run_in_slices() {
+    let mut handle = start_search(&[1, 2], JsValue::UNDEFINED).unwrap();
+    for _ in 0..100 {
+        let progress = step(&mut handle, 100);
+        if let Some(first) = progress.solutions.first() {
+            assert_eq!(first.code, "+.+.");
+            assert_eq!(first.min_len, 4);
+            return;
+        }
+        assert!(!progress.done);
+    }
+    panic!("no solution within 10000 expansions");
+}
+
+#[wasm_bindgen_test]
+fn each_slice_stops_after_n_expansions() {
+    let mut handle = start_search(&[7, 200, 3], JsValue::UNDEFINED).unwrap();
+    assert_eq!(step(&mut handle, 5).expansions, 5);
+    assert_eq!(step(&mut handle, 7).expansions, 12);
+    assert_eq!(step(&mut handle, 0).expansions, 12);
+    assert_eq!(step(&mut handle, 1).expansions, 13);
+}
+
+#[wasm_bindgen_test]
+fn slices_hand_back_every_solution_once() {
+    // Golfing `+.` for [1] exhausts once nothing shorter than `+.` is left.
+    let options = Options {
+        instr_set: Some("+."),
+        golf: true,
+    };
+    let config = serde_wasm_bindgen::to_value(&options).unwrap();
+    let mut handle = start_search(&[1], config).unwrap();
+    let mut codes = Vec::new();
+    let mut last = step(&mut handle, 1);
+    codes.extend(last.solutions.drain(..).map(|s| s.code));
+    while !last.done {
+        last = step(&mut handle, 1);
+        codes.extend(last.solutions.drain(..).map(|s| s.code));
+    }
+    assert_eq!(codes, ["+."]);
+    assert_eq!(last.solutions_total, 1);
+    // finished handles keep answering
+    let after = step(&mut handle, 10);
+    assert!(after.done && after.solutions.is_empty());
+    assert_eq!(after.expansions, last.expansions);
+}
+
+#[wasm_bindgen_test]
+fn bad_options_are_errors() {
+    let options = Options {
+        instr_set: Some("+x"),
+        golf: false,
+    };
+    let config = serde_wasm_bindgen::to_value(&options).unwrap();
+    assert!(start_search(&[1], config).is_err());
+}